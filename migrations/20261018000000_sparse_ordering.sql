@@ -0,0 +1,6 @@
+-- Spread ordering keys apart so that a row can be moved between any two
+-- neighbours by rewriting only its own key (see ORDERING_GAP in src/db/ops.rs)
+
+UPDATE todo_lists SET ordering = ordering * 1024;
+
+UPDATE todo_items SET ordering = ordering * 1024;
@@ -0,0 +1,19 @@
+-- Items synced with CalDAV collections, with the state of both sides at the last sync
+-- No foreign key to todo_items: the row of a deleted item tells that it's gone
+-- and has to be deleted from the server too
+
+CREATE TABLE caldav_sync_state (
+    -- URL of the collection
+    collection TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+    -- Path of the resource holding the to-do on the server
+    href TEXT NOT NULL,
+    uid TEXT NOT NULL,
+    -- ETag of the resource, NULL if the server didn't give one
+    etag TEXT,
+    -- updated_at of the item when it was last synced
+    synced_at TEXT NOT NULL,
+    PRIMARY KEY (collection, item_id)
+);
+
+CREATE UNIQUE INDEX idx_caldav_sync_state_href ON caldav_sync_state(collection, href);
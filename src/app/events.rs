@@ -1,110 +1,264 @@
-use crate::app::state::{App, CurrentScreen};
-use crate::ui::components::{ItemsComponent, ListsComponent};
+use crate::keymap::{Action, KeyContext};
+use crate::app::sequence::SequenceOutcome;
+use crate::app::state::{App, CurrentScreen, Pane};
+use crate::ui::components::{ChangeDBPopUp, ItemsComponent, ListsComponent};
 use crate::ui::cursor::CursorState;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Position;
+use tracing::instrument;
 
 pub struct EventHandler;
 
 impl EventHandler {
     /// Handle key press from user in main screen
     pub async fn handle_main_screen_key(app: &mut App, key: KeyEvent) {
-        match (key.code, key.modifiers) {
-            (KeyCode::Char('q'), KeyModifiers::NONE) => app.exit = true, // Quit application
-            (KeyCode::Char('s'), KeyModifiers::NONE) => app.lists_component.select_next(), // Navigate down in lists
-            (KeyCode::Char('w'), KeyModifiers::NONE) => app.lists_component.select_previous(), // Navigate up in lists
-            (KeyCode::Char('A'), KeyModifiers::SHIFT) => app.enter_add_list_screen(), // Add new list
-            (KeyCode::Char('a'), KeyModifiers::NONE) => app.enter_add_item_screen(), // Add new item
-            (KeyCode::Char('C'), KeyModifiers::SHIFT) => app.enter_change_db_screen(), // Change database
-            (KeyCode::Char('M'), KeyModifiers::SHIFT) => {
-                if let Some(selected_list) = app.lists_component.get_selected_list() {
+        Self::handle_bound_key(app, KeyContext::Main, key).await;
+    }
+
+    /// Feed a key to the key buffer and perform the resulting action, if any
+    async fn handle_bound_key(app: &mut App, context: KeyContext, key: KeyEvent) {
+        if let SequenceOutcome::Perform(action, count) =
+            app.key_buffer.feed(&app.key_map, context, key)
+        {
+            let screen = app.current_screen.clone();
+            let selected = app.lists_component.selected();
+            for _ in 0..count {
+                Self::perform_action(app, action).await;
+
+                // Stop repeating once the action opens a pop-up or switches screen
+                if app.current_screen != screen || app.exit {
+                    break;
+                }
+            }
+            Self::refresh_view_if_selected(app, selected).await;
+        }
+    }
+
+    /// Reload a view when it gets selected, so that it shows the items as they are now
+    async fn refresh_view_if_selected(app: &mut App, previous: Option<usize>) {
+        if app.lists_component.selected() != previous
+            && let Err(e) = app.lists_component.refresh_selected_view(&app.pool).await
+        {
+            app.messages.error(format!("Failed to refresh view: {}", e));
+        }
+    }
+
+    /// Perform a bound action
+    #[instrument(level = "debug", skip(app))]
+    pub async fn perform_action(app: &mut App, action: Action) {
+        match app.focused_action(action) {
+            Action::Quit => app.exit = true,
+            Action::NextList => app.lists_component.select_next(),
+            Action::PreviousList => app.lists_component.select_previous(),
+            Action::AddList => app.enter_add_list_screen(),
+            Action::AddItem => app.enter_add_item_screen(),
+            Action::ChangeDb => app.enter_change_db_screen(),
+            Action::ModifyList => {
+                if app.lists_component.is_view_selected() {
+                    app.messages
+                        .warn("Views are changed in judo.toml or with `judo views`");
+                } else if let Some(selected_list) = app.lists_component.get_selected_list() {
                     app.enter_modify_list_screen(&selected_list.list.clone())
                 }
-            } // Modify existing list
-            (KeyCode::Char('m'), KeyModifiers::NONE) => {
+            }
+            Action::ModifyItem => {
                 if let Some(selected_list) = app.lists_component.get_selected_list() {
                     app.enter_modify_item_screen(&selected_list.clone())
                 }
-            } // Modify existing item
-            (KeyCode::Char('D'), KeyModifiers::SHIFT) => {
+            }
+            Action::DeleteList if app.lists_component.is_view_selected() => {
+                if let Err(e) = app.delete_selected_view().await {
+                    app.messages.error(format!("Failed to delete view: {}", e));
+                }
+            }
+            Action::DeleteList => {
                 if let Err(e) =
                     ListsComponent::delete_selected_list_static(&mut app.lists_component, &app.pool)
                         .await
                 {
-                    // Log error but don't crash the application
-                    eprintln!("Failed to delete list: {}", e);
+                    // Report error but don't crash the application
+                    app.messages.error(format!("Failed to delete list: {}", e));
                 }
             }
-            (KeyCode::Char('d'), KeyModifiers::NONE) => {
+            Action::DeleteItem => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut()
                     && let Err(e) =
                         ItemsComponent::delete_selected_item(selected_list, &app.pool).await
                 {
-                    eprintln!("Failed to delete item: {}", e);
+                    app.messages.error(format!("Failed to delete item: {}", e));
                 }
             }
-            (KeyCode::Enter, KeyModifiers::NONE) => {
+            Action::ToggleDone => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut()
                     && let Err(e) = ItemsComponent::toggle_item_done(selected_list, &app.pool).await
                 {
-                    eprintln!("Failed to toggle item: {}", e);
+                    app.messages.error(format!("Failed to toggle item: {}", e));
                 }
             }
-            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
-                // Ctrl+S: Move selected list down
+            Action::MoveListDown => {
                 if let Err(e) =
                     ListsComponent::move_selected_list_down(&mut app.lists_component, &app.pool)
                         .await
                 {
-                    eprintln!("Failed to move list down: {}", e);
+                    app.messages
+                        .error(format!("Failed to move list down: {}", e));
                 }
             }
-            (KeyCode::Char('w'), KeyModifiers::CONTROL) => {
-                // Ctrl+W: Move selected list up
+            Action::MoveListUp => {
                 if let Err(e) =
                     ListsComponent::move_selected_list_up(&mut app.lists_component, &app.pool).await
                 {
-                    eprintln!("Failed to move list up: {}", e);
+                    app.messages.error(format!("Failed to move list up: {}", e));
                 }
             }
-            (KeyCode::Up, KeyModifiers::CONTROL) => {
-                // Ctrl+Up: Move selected item up
+            Action::MoveListToBottom => {
+                if let Err(e) = ListsComponent::move_selected_list_to_bottom(
+                    &mut app.lists_component,
+                    &app.pool,
+                )
+                .await
+                {
+                    app.messages
+                        .error(format!("Failed to move list to bottom: {}", e));
+                }
+            }
+            Action::MoveListToTop => {
+                if let Err(e) =
+                    ListsComponent::move_selected_list_to_top(&mut app.lists_component, &app.pool)
+                        .await
+                {
+                    app.messages
+                        .error(format!("Failed to move list to top: {}", e));
+                }
+            }
+            Action::MoveItemUp => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut()
                     && let Err(e) =
                         ItemsComponent::move_selected_item_up(selected_list, &app.pool).await
                 {
-                    eprintln!("Failed to move item up: {}", e);
+                    app.messages.error(format!("Failed to move item up: {}", e));
                 }
             }
-            (KeyCode::Down, KeyModifiers::CONTROL) => {
-                // Ctrl+Down: Move selected item down
+            Action::MoveItemDown => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut()
                     && let Err(e) =
                         ItemsComponent::move_selected_item_down(selected_list, &app.pool).await
                 {
-                    eprintln!("Failed to move item down: {}", e);
+                    app.messages
+                        .error(format!("Failed to move item down: {}", e));
                 }
             }
-            (KeyCode::Down, KeyModifiers::NONE) => {
+            Action::MoveItemToTop => {
+                if let Some(selected_list) = app.lists_component.get_selected_list_mut()
+                    && let Err(e) =
+                        ItemsComponent::move_selected_item_to_top(selected_list, &app.pool).await
+                {
+                    app.messages
+                        .error(format!("Failed to move item to top: {}", e));
+                }
+            }
+            Action::MoveItemToBottom => {
+                if let Some(selected_list) = app.lists_component.get_selected_list_mut()
+                    && let Err(e) =
+                        ItemsComponent::move_selected_item_to_bottom(selected_list, &app.pool).await
+                {
+                    app.messages
+                        .error(format!("Failed to move item to bottom: {}", e));
+                }
+            }
+            Action::NextItem => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
                     ItemsComponent::select_next_item(selected_list);
                 }
             }
-            (KeyCode::Up, KeyModifiers::NONE) => {
+            Action::PreviousItem => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
                     ItemsComponent::select_previous_item(selected_list);
                 }
             }
-            (KeyCode::Left, KeyModifiers::NONE) => {
+            Action::DeselectItem => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
                     ItemsComponent::remove_item_selection(selected_list);
                 }
             }
-            (KeyCode::Right, KeyModifiers::NONE) => {
+            Action::SelectItem => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
                     ItemsComponent::select_first_item(selected_list);
                 }
             }
-            _ => {}
+            Action::FocusLists => app.focus_pane(Pane::Lists),
+            Action::FocusItems => app.focus_pane(Pane::Items),
+            Action::ToggleFocus => match app.focus {
+                Pane::Lists => app.focus_pane(Pane::Items),
+                Pane::Items => app.focus_pane(Pane::Lists),
+            },
+            Action::CursorTop => match app.focus {
+                Pane::Lists => app.lists_component.select_top(),
+                Pane::Items => {
+                    if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
+                        ItemsComponent::select_top_item(selected_list);
+                    }
+                }
+            },
+            Action::CursorBottom => match app.focus {
+                Pane::Lists => app.lists_component.select_bottom(),
+                Pane::Items => {
+                    if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
+                        ItemsComponent::select_bottom_item(selected_list);
+                    }
+                }
+            },
+            Action::AddItemBelow | Action::AddItemAbove => {
+                let selected = app
+                    .lists_component
+                    .get_selected_list()
+                    .and_then(|ui_list| ui_list.item_state.selected());
+
+                app.enter_add_item_screen();
+                app.insert_position = match (action, selected) {
+                    (Action::AddItemBelow, Some(j)) => Some(j + 1),
+                    (Action::AddItemAbove, Some(j)) => Some(j),
+                    // Without a selection, "above" means at the top
+                    (Action::AddItemAbove, None) => Some(0),
+                    _ => None,
+                };
+            }
+            // Pane-relative actions are resolved by `focused_action`, and repeats
+            // are replayed by the key buffer
+            Action::CursorDown | Action::CursorUp | Action::DeleteSelected | Action::RepeatLast => {
+            }
+            Action::FilterItems => app.enter_filter_screen(),
+            Action::SaveView => app.enter_save_view_screen(),
+            Action::ShowHelp => app.enter_help_screen(),
+            Action::ShowMessages => app.enter_message_log_screen(),
+            Action::ScrollMessagesDown => {
+                app.messages_scroll = app.messages_scroll.saturating_add(1)
+            }
+            Action::ScrollMessagesUp => app.messages_scroll = app.messages_scroll.saturating_sub(1),
+            Action::CloseMessages => app.exit_message_log_screen(),
+            Action::OpenPalette => app.enter_palette_screen().await,
+            Action::ScrollHelpDown => app.help_scroll = app.help_scroll.saturating_add(1),
+            Action::ScrollHelpUp => app.help_scroll = app.help_scroll.saturating_sub(1),
+            Action::CloseHelp => app.exit_help_screen(),
+            Action::CloseDbPopup => app.exit_change_db_without_saving(),
+            Action::PreviousDb => app.select_previous_db(),
+            Action::NextDb => app.select_next_db(),
+            Action::SwitchDb => {
+                if let Err(e) = app.switch_to_selected_db().await {
+                    app.messages
+                        .error(format!("Failed to switch database: {}", e));
+                }
+            }
+            Action::AddDb => app.enter_add_db_screen(),
+            Action::RenameDb => app.enter_rename_db_screen(),
+            Action::RemoveDb => app.enter_remove_db_screen(),
+            Action::SetDefaultDb => {
+                // Set selected database as default
+                if let Err(e) = app.set_selected_db_as_default().await {
+                    app.messages
+                        .error(format!("Failed to set database as default: {}", e));
+                }
+            }
         }
     }
 
@@ -129,7 +283,7 @@ impl EventHandler {
                         )
                         .await
                         {
-                            eprintln!("Failed to update list: {}", e);
+                            app.messages.error(format!("Failed to update list: {}", e));
                         } else {
                             app.current_screen = CurrentScreen::Main;
                             app.input_state.clear();
@@ -138,7 +292,7 @@ impl EventHandler {
                         ListsComponent::create_list(&mut app.lists_component, list_name, &app.pool)
                             .await
                     {
-                        eprintln!("Failed to create list: {}", e);
+                        app.messages.error(format!("Failed to create list: {}", e));
                     } else {
                         app.current_screen = CurrentScreen::Main;
                         app.input_state.clear();
@@ -167,18 +321,35 @@ impl EventHandler {
                         if let Err(e) =
                             ItemsComponent::update_item(selected_list, item_name, &app.pool).await
                         {
-                            eprintln!("Failed to update item: {}", e);
+                            app.messages.error(format!("Failed to update item: {}", e));
                         } else {
                             app.current_screen = CurrentScreen::Main;
                             app.input_state.clear();
                         }
-                    } else if let Err(e) =
-                        ItemsComponent::create_item(selected_list, item_name, &app.pool).await
-                    {
-                        eprintln!("Failed to create item: {}", e);
                     } else {
-                        app.current_screen = CurrentScreen::Main;
-                        app.input_state.clear();
+                        let created = match app.insert_position {
+                            Some(position) => {
+                                ItemsComponent::create_item_at(
+                                    selected_list,
+                                    item_name,
+                                    position,
+                                    &app.pool,
+                                )
+                                .await
+                            }
+                            None => {
+                                ItemsComponent::create_item(selected_list, item_name, &app.pool)
+                                    .await
+                            }
+                        };
+
+                        if let Err(e) = created {
+                            app.messages.error(format!("Failed to create item: {}", e));
+                        } else {
+                            app.current_screen = CurrentScreen::Main;
+                            app.input_state.clear();
+                            app.insert_position = None;
+                        }
                     }
                 }
             }
@@ -188,20 +359,85 @@ impl EventHandler {
 
     /// Handle change of db
     pub async fn handle_change_db_screen_key(app: &mut App, key: KeyEvent) {
+        Self::handle_bound_key(app, KeyContext::Database, key).await;
+    }
+
+    /// Handle key press from user in help overlay
+    pub async fn handle_help_screen_key(app: &mut App, key: KeyEvent) {
+        Self::handle_bound_key(app, KeyContext::Help, key).await;
+    }
+
+    /// Handle key press from user in message log
+    pub async fn handle_message_log_screen_key(app: &mut App, key: KeyEvent) {
+        Self::handle_bound_key(app, KeyContext::Messages, key).await;
+    }
+
+    /// Handle key press from user in command palette
+    pub async fn handle_palette_screen_key(app: &mut App, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
         match key.code {
-            KeyCode::Esc => app.exit_change_db_without_saving(),
-            KeyCode::Up => app.select_previous_db(),
-            KeyCode::Down => app.select_next_db(),
+            KeyCode::Esc => app.exit_palette_screen(),
+            KeyCode::Up => app.palette.select_previous(),
+            KeyCode::Down | KeyCode::Tab => app.palette.select_next(),
+            KeyCode::Char('p') if ctrl => app.palette.select_previous(),
+            KeyCode::Char('n') if ctrl => app.palette.select_next(),
+            KeyCode::Left => app.palette.move_cursor_left(),
+            KeyCode::Right => app.palette.move_cursor_right(),
+            KeyCode::Backspace => {
+                app.palette.remove_char_before_cursor();
+                app.palette.update_matches();
+            }
+            KeyCode::Delete => {
+                app.palette.delete_char_after_cursor();
+                app.palette.update_matches();
+            }
+            KeyCode::Char(value) => {
+                app.palette.add_char(value);
+                app.palette.update_matches();
+            }
             KeyCode::Enter => {
-                if let Err(e) = app.switch_to_selected_db().await {
-                    eprintln!("Failed to switch database: {}", e);
+                if let Err(e) = app.run_palette_entry().await {
+                    app.messages.error(format!("Failed to run command: {}", e));
                 }
             }
-            KeyCode::Char('A') => app.enter_add_db_screen(),
-            KeyCode::Char('S') => {
-                // Set selected database as default
-                if let Err(e) = app.set_selected_db_as_default().await {
-                    eprintln!("Failed to set database as default: {}", e);
+            _ => {}
+        }
+    }
+
+    /// Handle key press from user in filter screen
+    pub async fn handle_filter_screen_key(app: &mut App, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => app.exit_filter_screen(),
+            KeyCode::Backspace => app.input_state.remove_char_before_cursor(),
+            KeyCode::Delete => app.input_state.delete_char_after_cursor(),
+            KeyCode::Char(value) => app.input_state.add_char(value),
+            KeyCode::Left => app.input_state.move_cursor_left(),
+            KeyCode::Right => app.input_state.move_cursor_right(),
+            KeyCode::Enter => {
+                // An invalid query keeps the pop-up open so it can be fixed
+                if let Err(e) = app.apply_filter().await {
+                    app.messages.error(format!("Invalid query: {}", e));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle key press from user in save view screen
+    pub async fn handle_save_view_screen_key(app: &mut App, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => app.exit_save_view_screen(),
+            KeyCode::Backspace => app.input_state.remove_char_before_cursor(),
+            KeyCode::Delete => app.input_state.delete_char_after_cursor(),
+            KeyCode::Char(value) => app.input_state.add_char(value),
+            KeyCode::Left => app.input_state.move_cursor_left(),
+            KeyCode::Right => app.input_state.move_cursor_right(),
+            KeyCode::Enter => {
+                let name = app.input_state.get_text().trim().to_string();
+                if !name.is_empty()
+                    && let Err(e) = app.save_filter_as_view(name).await
+                {
+                    app.messages.error(format!("Failed to save view: {}", e));
                 }
             }
             _ => {}
@@ -221,7 +457,8 @@ impl EventHandler {
                 let db_name = app.input_state.get_text().to_string();
                 if !db_name.trim().is_empty() {
                     if let Err(e) = app.create_new_database(db_name, false).await {
-                        eprintln!("Failed to create database: {}", e);
+                        app.messages
+                            .error(format!("Failed to create database: {}", e));
                     } else {
                         app.current_screen = CurrentScreen::ChangeDB;
                         app.input_state.clear();
@@ -231,4 +468,243 @@ impl EventHandler {
             _ => {}
         }
     }
+
+    /// Handle key press from user in rename database pop-up
+    pub async fn handle_rename_db_screen_key(app: &mut App, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => app.exit_db_input_screen(),
+            KeyCode::Backspace => app.input_state.remove_char_before_cursor(),
+            KeyCode::Delete => app.input_state.delete_char_after_cursor(),
+            KeyCode::Char(value) => app.input_state.add_char(value),
+            KeyCode::Left => app.input_state.move_cursor_left(),
+            KeyCode::Right => app.input_state.move_cursor_right(),
+            KeyCode::Enter => {
+                let new_name = app.input_state.get_text().trim().to_string();
+                if !new_name.is_empty()
+                    && let Err(e) = app.rename_selected_db(new_name).await
+                {
+                    app.messages
+                        .error(format!("Failed to rename database: {}", e));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle key press from user in remove database pop-up
+    pub async fn handle_remove_db_screen_key(app: &mut App, key: KeyEvent) {
+        let delete_file = match key.code {
+            KeyCode::Char('f') => false,
+            KeyCode::Char('d') => true,
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q') => {
+                app.exit_db_input_screen();
+                return;
+            }
+            _ => return,
+        };
+        if let Err(e) = app.remove_selected_db(delete_file).await {
+            app.messages
+                .error(format!("Failed to remove database: {}", e));
+            app.exit_db_input_screen();
+        }
+    }
+
+    /// Handle mouse input, delegating on the current screen
+    pub async fn handle_mouse_event(app: &mut App, mouse: MouseEvent) {
+        match app.current_screen {
+            CurrentScreen::Main => Self::handle_main_screen_mouse(app, mouse).await,
+            CurrentScreen::ChangeDB => Self::handle_change_db_screen_mouse(app, mouse).await,
+            CurrentScreen::Help => match mouse.kind {
+                MouseEventKind::ScrollDown => app.help_scroll = app.help_scroll.saturating_add(1),
+                MouseEventKind::ScrollUp => app.help_scroll = app.help_scroll.saturating_sub(1),
+                _ => {}
+            },
+            CurrentScreen::MessageLog => match mouse.kind {
+                MouseEventKind::ScrollDown => {
+                    app.messages_scroll = app.messages_scroll.saturating_add(1)
+                }
+                MouseEventKind::ScrollUp => {
+                    app.messages_scroll = app.messages_scroll.saturating_sub(1)
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Handle mouse input in main screen
+    async fn handle_main_screen_mouse(app: &mut App, mouse: MouseEvent) {
+        let position = Position::new(mouse.column, mouse.row);
+        let areas = app.areas;
+        let selected = app.lists_component.selected();
+
+        match mouse.kind {
+            MouseEventKind::ScrollDown => {
+                if areas.lists.contains(position) {
+                    app.lists_component.select_next();
+                } else if areas.items.contains(position)
+                    && let Some(selected_list) = app.lists_component.get_selected_list_mut()
+                {
+                    ItemsComponent::select_next_item(selected_list);
+                }
+            }
+            MouseEventKind::ScrollUp => {
+                if areas.lists.contains(position) {
+                    app.lists_component.select_previous();
+                } else if areas.items.contains(position)
+                    && let Some(selected_list) = app.lists_component.get_selected_list_mut()
+                {
+                    ItemsComponent::select_previous_item(selected_list);
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let is_double_click = app.register_click(&mouse);
+
+                if areas.closed_selector.contains(position) {
+                    // Click on the database selector opens the database pop-up
+                    app.enter_change_db_screen();
+                } else if let Some(i) = app.lists_component.list_at(areas.lists, position) {
+                    app.lists_component.list_state.select(Some(i));
+                } else if let Some(selected_list) = app.lists_component.get_selected_list_mut()
+                    && let Some(j) = ItemsComponent::item_at(selected_list, areas.items, position)
+                {
+                    selected_list.item_state.select(Some(j));
+
+                    // Double-click toggles the item
+                    if is_double_click
+                        && let Err(e) =
+                            ItemsComponent::toggle_item_done(selected_list, &app.pool).await
+                    {
+                        app.messages.error(format!("Failed to toggle item: {}", e));
+                    }
+                }
+            }
+            _ => {}
+        }
+        Self::refresh_view_if_selected(app, selected).await;
+    }
+
+    /// Handle mouse input in change db screen
+    async fn handle_change_db_screen_mouse(app: &mut App, mouse: MouseEvent) {
+        let position = Position::new(mouse.column, mouse.row);
+        let area = app.areas.db_selector;
+
+        match mouse.kind {
+            MouseEventKind::ScrollDown if area.contains(position) => app.select_next_db(),
+            MouseEventKind::ScrollUp if area.contains(position) => app.select_previous_db(),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let is_double_click = app.register_click(&mouse);
+
+                if !area.contains(position) {
+                    // Click outside the pop-up closes it
+                    app.exit_change_db_without_saving();
+                } else if let Some(i) =
+                    ChangeDBPopUp::db_at(&app.config, app.selected_db_index, area, position)
+                {
+                    app.selected_db_index = i;
+
+                    // Double-click switches to the database
+                    if is_double_click && let Err(e) = app.switch_to_selected_db().await {
+                        app.messages
+                            .error(format!("Failed to switch database: {}", e));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::config::Config;
+    use crate::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+    use crate::ui::layout::MainAreas;
+    use ratatui::layout::Rect;
+    use std::path::Path;
+
+    /// An app on a database in `dir` with lists Chores (Laundry, Dishes) and
+    /// Errands, laid out on a known screen
+    async fn test_app(dir: &Path) -> App {
+        let config: Config = toml::from_str(&format!(
+            "default = \"work\"\n[[dbs]]\nname = \"work\"\nconnection_str = \"sqlite:{}\"\n",
+            dir.join("judo.db").display()
+        ))
+        .unwrap();
+        let mut app = App::with_config(config, false).await.unwrap();
+
+        for name in ["Chores", "Errands"] {
+            TodoList::create(
+                &app.pool,
+                NewTodoList {
+                    name: name.to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        }
+        for name in ["Laundry", "Dishes"] {
+            TodoItem::create(
+                &app.pool,
+                NewTodoItem {
+                    list_id: 1,
+                    name: name.to_string(),
+                    priority: None,
+                    due_date: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        app.lists_component.load_lists(&app.pool).await.unwrap();
+
+        // The first entry of both panes is drawn on row 2
+        app.areas = MainAreas {
+            lists: Rect::new(0, 0, 30, 12),
+            items: Rect::new(30, 0, 40, 12),
+            closed_selector: Rect::new(0, 12, 30, 3),
+            ..MainAreas::default()
+        };
+        app
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_mouse_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = test_app(dir.path()).await;
+        let click = MouseEventKind::Down(MouseButton::Left);
+
+        // Clicking a list selects it, scrolling over the lists moves the selection
+        EventHandler::handle_mouse_event(&mut app, mouse(click, 5, 3)).await;
+        assert_eq!(app.lists_component.list_state.selected(), Some(1));
+        EventHandler::handle_mouse_event(&mut app, mouse(MouseEventKind::ScrollUp, 5, 8)).await;
+        assert_eq!(app.lists_component.list_state.selected(), Some(0));
+
+        // Clicking an item selects it, and clicking it again toggles it
+        EventHandler::handle_mouse_event(&mut app, mouse(click, 35, 3)).await;
+        let chores = app.lists_component.get_selected_list_mut().unwrap();
+        assert_eq!(chores.item_state.selected(), Some(1));
+        assert!(!chores.items[1].item.is_done);
+        EventHandler::handle_mouse_event(&mut app, mouse(click, 35, 3)).await;
+        let dishes = TodoItem::get_by_id(&app.pool, 2).await.unwrap().unwrap();
+        assert!(dishes.is_done);
+
+        // Clicks outside of any list or item change nothing
+        EventHandler::handle_mouse_event(&mut app, mouse(click, 5, 10)).await;
+        assert_eq!(app.lists_component.list_state.selected(), Some(0));
+
+        // Clicking the database selector opens the database pop-up
+        EventHandler::handle_mouse_event(&mut app, mouse(click, 5, 13)).await;
+        assert_eq!(app.current_screen, CurrentScreen::ChangeDB);
+    }
 }
@@ -0,0 +1,199 @@
+//! Fuzzy matching of the command palette's query against the labels of its
+//! entries, ranking them by how well they match and highlighting the matched
+//! characters.
+
+/// Score of every matched character
+const SCORE_MATCH: i64 = 16;
+/// Bonus for matching the first character of the candidate
+const BONUS_FIRST_CHAR: i64 = 12;
+/// Bonus for matching the start of a word ("sd" in "switch database")
+const BONUS_BOUNDARY: i64 = 10;
+/// Bonus for matching right after the previous matched character
+const BONUS_CONSECUTIVE: i64 = 8;
+/// Penalty for opening a gap between two matched characters
+const PENALTY_GAP_START: i64 = 3;
+/// Penalty for every further character of a gap
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// A candidate matched by a fuzzy pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Higher is better
+    pub score: i64,
+    /// Character indices of the candidate matched by the pattern, in order
+    pub positions: Vec<usize>,
+}
+
+/// Match a pattern against a candidate, the characters of the pattern having to
+/// appear in the candidate in the same order but not necessarily next to each other.
+///
+/// The alignment with the best score is found with dynamic programming, rewarding
+/// consecutive characters and word starts and penalising gaps. Matching ignores case
+/// unless the pattern contains an uppercase letter. Whitespace in the pattern is ignored.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let normalise = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(normalise)
+        .collect();
+    let original: Vec<char> = candidate.chars().collect();
+    let chars: Vec<char> = original.iter().copied().map(normalise).collect();
+
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    if pattern.len() > chars.len() {
+        return None;
+    }
+
+    let n = pattern.len();
+    let m = chars.len();
+
+    // best[i][j]: best score with pattern[i] matched at chars[j] (None if impossible)
+    // from[i][j]: position of pattern[i - 1] in that alignment
+    let mut best = vec![vec![None; m]; n];
+    let mut from = vec![vec![0usize; m]; n];
+
+    for i in 0..n {
+        // Best alignment of pattern[..i] ending at least two characters back,
+        // with the gap penalty up to the current column already applied
+        let mut gapped: Option<(i64, usize)> = None;
+
+        for j in i..m {
+            if i > 0 && j >= 2 {
+                gapped = gapped.map(|(score, k)| (score - PENALTY_GAP_EXTENSION, k));
+                if let Some(score) = best[i - 1][j - 2] {
+                    let opened = score - PENALTY_GAP_START;
+                    if gapped.is_none_or(|(current, _)| opened > current) {
+                        gapped = Some((opened, j - 2));
+                    }
+                }
+            }
+
+            if chars[j] != pattern[i] {
+                continue;
+            }
+
+            let bonus = SCORE_MATCH + boundary_bonus(&original, j);
+
+            if i == 0 {
+                // Unmatched characters before the first match cost a little,
+                // so earlier matches win ties
+                best[i][j] = Some(bonus - j.min(3) as i64);
+                continue;
+            }
+
+            let consecutive = (j >= 1)
+                .then(|| best[i - 1][j - 1])
+                .flatten()
+                .map(|score| (score + BONUS_CONSECUTIVE, j - 1));
+
+            let choice = match (consecutive, gapped) {
+                (Some(c), Some(g)) => Some(if c.0 >= g.0 { c } else { g }),
+                (c, g) => c.or(g),
+            };
+
+            if let Some((score, k)) = choice {
+                best[i][j] = Some(score + bonus);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    // Pick the best end position and walk the alignment back
+    let (end, score) = best[n - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|s| (j, s)))
+        .max_by_key(|(j, score)| (*score, std::cmp::Reverse(*j)))?;
+
+    let mut positions = vec![end; n];
+    for i in (1..n).rev() {
+        positions[i - 1] = from[i][positions[i]];
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Bonus for matching the character at the given position
+fn boundary_bonus(chars: &[char], j: usize) -> i64 {
+    if j == 0 {
+        return BONUS_FIRST_CHAR;
+    }
+
+    let previous = chars[j - 1];
+    let current = chars[j];
+    if !previous.is_alphanumeric() || (previous.is_lowercase() && current.is_uppercase()) {
+        BONUS_BOUNDARY
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn score(pattern: &str, candidate: &str) -> i64 {
+        fuzzy_match(pattern, candidate)
+            .map(|m| m.score)
+            .unwrap_or(i64::MIN)
+    }
+
+    #[test]
+    fn test_subsequence_required() {
+        assert!(fuzzy_match("adl", "Add list").is_some());
+        assert!(fuzzy_match("lda", "Add list").is_none());
+        assert!(fuzzy_match("toolong", "short").is_none());
+    }
+
+    #[test]
+    fn test_empty_pattern_matches_everything() {
+        assert_eq!(fuzzy_match("", "anything").unwrap().score, 0);
+        assert_eq!(fuzzy_match("  ", "anything").unwrap().score, 0);
+    }
+
+    #[test]
+    fn test_smart_case() {
+        assert!(fuzzy_match("add", "Add list").is_some());
+        assert!(fuzzy_match("Add", "add list").is_none());
+    }
+
+    #[test]
+    fn test_positions_prefer_word_starts() {
+        let m = fuzzy_match("sd", "Switch database").unwrap();
+        assert_eq!(m.positions, vec![0, 7]);
+
+        // The "l" of "list" is a better match than the first "l" in "all"
+        let m = fuzzy_match("dl", "Delete all lists").unwrap();
+        assert_eq!(m.positions, vec![0, 11]);
+    }
+
+    #[test]
+    fn test_ranking() {
+        // Consecutive characters beat scattered ones
+        assert!(score("list", "Add list") > score("list", "Lower importance stat"));
+        // Word starts beat characters in the middle of words
+        assert!(score("ai", "Add item") > score("ai", "Maintain"));
+        // Prefixes beat later matches
+        assert!(score("mod", "Modify list") > score("mod", "Set mode"));
+    }
+
+    #[test]
+    fn test_unicode() {
+        let m = fuzzy_match("éc", "Les écoles").unwrap();
+        assert_eq!(m.positions, vec![4, 5]);
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Most messages kept in the message log, older ones are dropped
+const MAX_MESSAGES: usize = 200;
+
+/// Severity of a message shown to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MessageLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl MessageLevel {
+    /// How long a message of this level stays in the status bar
+    pub fn toast_duration(&self) -> Duration {
+        match self {
+            MessageLevel::Info => Duration::from_secs(3),
+            MessageLevel::Warn => Duration::from_secs(5),
+            MessageLevel::Error => Duration::from_secs(8),
+        }
+    }
+
+    /// Short label shown in front of the message
+    pub fn label(&self) -> &'static str {
+        match self {
+            MessageLevel::Info => "INFO",
+            MessageLevel::Warn => "WARN",
+            MessageLevel::Error => "ERROR",
+        }
+    }
+}
+
+/// A message reported to the user
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub level: MessageLevel,
+    pub text: String,
+    /// Wall clock time, shown in the message log
+    pub time: DateTime<Local>,
+    /// Monotonic time, used to expire the toast
+    pub created: Instant,
+}
+
+/// Messages reported while the TUI runs: the latest one is shown in the status
+/// bar for a while, and all of them can be reviewed in the message log screen
+#[derive(Debug, Clone, Default)]
+pub struct MessageLog {
+    /// Messages, oldest first
+    messages: VecDeque<Message>,
+    /// Whether the latest message was dismissed from the status bar
+    dismissed: bool,
+}
+
+impl MessageLog {
+    /// Report a message, also writing it to the log file
+    pub fn push(&mut self, level: MessageLevel, text: impl Into<String>) {
+        let text = text.into();
+        match level {
+            MessageLevel::Info => tracing::info!("{}", text),
+            MessageLevel::Warn => tracing::warn!("{}", text),
+            MessageLevel::Error => tracing::error!("{}", text),
+        }
+
+        if self.messages.len() == MAX_MESSAGES {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(Message {
+            level,
+            text,
+            time: Local::now(),
+            created: Instant::now(),
+        });
+        self.dismissed = false;
+    }
+
+    /// Report an informational message
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(MessageLevel::Info, text);
+    }
+
+    /// Report a warning
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.push(MessageLevel::Warn, text);
+    }
+
+    /// Report an error
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(MessageLevel::Error, text);
+    }
+
+    /// Latest message, while it's young enough to be shown in the status bar
+    pub fn toast(&self, now: Instant) -> Option<&Message> {
+        self.messages
+            .back()
+            .filter(|m| !self.dismissed && now.duration_since(m.created) < m.level.toast_duration())
+    }
+
+    /// Time until the current toast expires, if one is shown
+    pub fn toast_remaining(&self, now: Instant) -> Option<Duration> {
+        self.toast(now)
+            .map(|m| m.level.toast_duration() - now.duration_since(m.created))
+    }
+
+    /// Hide the current toast (it stays in the log)
+    pub fn dismiss(&mut self) {
+        self.dismissed = true;
+    }
+
+    /// All messages, oldest first
+    pub fn messages(&self) -> impl DoubleEndedIterator<Item = &Message> {
+        self.messages.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_toast_expires() {
+        let mut log = MessageLog::default();
+        let start = Instant::now();
+        assert!(log.toast(start).is_none());
+
+        log.info("Saved");
+        let now = Instant::now();
+        assert_eq!(log.toast(now).map(|m| m.text.as_str()), Some("Saved"));
+        assert!(log.toast_remaining(now).unwrap() <= MessageLevel::Info.toast_duration());
+
+        let later = now + MessageLevel::Info.toast_duration();
+        assert!(log.toast(later).is_none());
+        assert_eq!(log.len(), 1);
+    }
+
+    #[test]
+    fn test_errors_stay_longer() {
+        let mut log = MessageLog::default();
+        log.error("Failed");
+        let now = Instant::now() + MessageLevel::Info.toast_duration();
+        assert!(log.toast(now).is_some());
+    }
+
+    #[test]
+    fn test_dismiss_keeps_log() {
+        let mut log = MessageLog::default();
+        log.warn("Careful");
+        log.dismiss();
+        assert!(log.toast(Instant::now()).is_none());
+        assert_eq!(log.len(), 1);
+
+        // A new message shows again
+        log.info("Next");
+        assert!(log.toast(Instant::now()).is_some());
+    }
+
+    #[test]
+    fn test_log_is_capped() {
+        let mut log = MessageLog::default();
+        for i in 0..MAX_MESSAGES + 5 {
+            log.info(format!("message {i}"));
+        }
+        assert_eq!(log.len(), MAX_MESSAGES);
+        assert_eq!(log.messages().next().unwrap().text, "message 5");
+    }
+}
@@ -1,5 +1,10 @@
 pub mod events;
+pub mod fuzzy;
+pub mod messages;
+pub mod palette;
+pub mod sequence;
 pub mod state;
 
 pub use events::EventHandler;
+pub use crate::keymap::{Action, KeyMap};
 pub use state::{App, CurrentScreen};
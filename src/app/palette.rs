@@ -0,0 +1,225 @@
+use crate::app::fuzzy::{FuzzyMatch, fuzzy_match};
+use crate::keymap::{Action, KeyContext, KeyMap};
+use crate::db::models::Priority;
+use crate::ui::cursor::CursorState;
+
+/// What a command palette entry does when chosen
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteTarget {
+    /// Perform a main screen action
+    Action(Action),
+    /// Switch to the database at the given index of the config
+    SwitchDb(usize),
+    /// Set the priority of the selected item
+    SetPriority(Priority),
+    /// Jump to a list, switching database if needed
+    List { db_index: usize, list_id: i64 },
+    /// Jump to an item, switching database if needed
+    Item {
+        db_index: usize,
+        list_id: i64,
+        item_id: i64,
+    },
+}
+
+/// Entry of the command palette
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteEntry {
+    /// Text the entry is matched against and shown as
+    pub label: String,
+    /// Key bound to the entry, shown next to it
+    pub key: String,
+    pub target: PaletteTarget,
+}
+
+impl PaletteEntry {
+    pub fn new(label: impl Into<String>, target: PaletteTarget) -> Self {
+        Self {
+            label: label.into(),
+            key: String::new(),
+            target,
+        }
+    }
+
+    /// Entries for every main screen action that has a meaning on its own
+    pub fn actions(key_map: &KeyMap) -> Vec<Self> {
+        Action::ALL
+            .into_iter()
+            .filter(|action| {
+                action.contexts().contains(&KeyContext::Main)
+                    && !matches!(action, Action::RepeatLast | Action::OpenPalette)
+            })
+            .map(|action| Self {
+                label: action.description().to_string(),
+                key: key_map.key_label(action),
+                target: PaletteTarget::Action(action),
+            })
+            .collect()
+    }
+
+    /// Entries setting the priority of the selected item
+    pub fn priorities() -> Vec<Self> {
+        [
+            ("high", Priority::High),
+            ("medium", Priority::Medium),
+            ("low", Priority::Low),
+        ]
+        .into_iter()
+        .map(|(name, priority)| {
+            Self::new(
+                format!("Set priority of selected item to {}", name),
+                PaletteTarget::SetPriority(priority),
+            )
+        })
+        .collect()
+    }
+}
+
+/// State of the command palette: the query, its matches and the selected match
+#[derive(Debug, Clone, Default)]
+pub struct PaletteState {
+    /// Text typed by the user
+    pub query: String,
+    /// Position of cursor in the query
+    pub cursor_pos: usize,
+    /// Everything the palette can do
+    pub entries: Vec<PaletteEntry>,
+    /// Indices of the entries matching the query with their match, best first
+    pub matches: Vec<(usize, FuzzyMatch)>,
+    /// Index in `matches` of the highlighted entry
+    pub selected: usize,
+}
+
+impl PaletteState {
+    /// Fresh palette over the given entries
+    pub fn new(entries: Vec<PaletteEntry>) -> Self {
+        let mut state = Self {
+            entries,
+            ..Self::default()
+        };
+        state.update_matches();
+        state
+    }
+
+    /// Re-rank the entries against the query, best first. Ties keep the entries'
+    /// order, so with an empty query actions come first
+    pub fn update_matches(&mut self) {
+        let mut matches: Vec<(usize, FuzzyMatch)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| fuzzy_match(&self.query, &entry.label).map(|m| (i, m)))
+            .collect();
+        matches.sort_by_key(|(i, m)| (std::cmp::Reverse(m.score), *i));
+
+        self.matches = matches;
+        self.selected = 0;
+    }
+
+    /// Highlighted entry, if anything matches
+    pub fn selected_entry(&self) -> Option<&PaletteEntry> {
+        self.matches
+            .get(self.selected)
+            .map(|(i, _)| &self.entries[*i])
+    }
+
+    /// Move the highlight down, wrapping around
+    pub fn select_next(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + 1) % self.matches.len();
+        }
+    }
+
+    /// Move the highlight up, wrapping around
+    pub fn select_previous(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.matches.len() - 1);
+        }
+    }
+}
+
+impl CursorState for PaletteState {
+    fn get_text(&self) -> &str {
+        &self.query
+    }
+
+    fn get_text_mut(&mut self) -> &mut String {
+        &mut self.query
+    }
+
+    fn get_cursor_pos(&self) -> usize {
+        self.cursor_pos
+    }
+
+    fn set_cursor_pos(&mut self, pos: usize) {
+        self.cursor_pos = pos;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn palette() -> PaletteState {
+        let mut entries = PaletteEntry::actions(&KeyMap::default());
+        entries.push(PaletteEntry::new(
+            "List: Groceries (work)",
+            PaletteTarget::List {
+                db_index: 1,
+                list_id: 7,
+            },
+        ));
+        PaletteState::new(entries)
+    }
+
+    #[test]
+    fn test_empty_query_lists_everything_in_order() {
+        let state = palette();
+        assert_eq!(state.matches.len(), state.entries.len());
+        assert_eq!(
+            state.selected_entry().map(|e| &e.target),
+            Some(&PaletteTarget::Action(Action::Quit))
+        );
+    }
+
+    #[test]
+    fn test_query_ranks_best_match_first() {
+        let mut state = palette();
+        for c in "groc".chars() {
+            state.add_char(c);
+        }
+        state.update_matches();
+
+        assert_eq!(
+            state.selected_entry().map(|e| &e.target),
+            Some(&PaletteTarget::List {
+                db_index: 1,
+                list_id: 7
+            })
+        );
+
+        state.query = "add list".to_string();
+        state.update_matches();
+        assert_eq!(
+            state.selected_entry().map(|e| &e.target),
+            Some(&PaletteTarget::Action(Action::AddList))
+        );
+    }
+
+    #[test]
+    fn test_selection_wraps() {
+        let mut state = palette();
+        state.select_previous();
+        assert_eq!(state.selected, state.matches.len() - 1);
+        state.select_next();
+        assert_eq!(state.selected, 0);
+
+        state.query = "zzzz".to_string();
+        state.update_matches();
+        state.select_next();
+        assert!(state.selected_entry().is_none());
+    }
+}
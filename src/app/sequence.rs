@@ -0,0 +1,241 @@
+use crate::keymap::{Action, KeyChord, KeyContext, KeyMap, Resolution};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Largest count accepted before a command, so a stuck key can't queue up
+/// an endless run of database writes
+const MAX_COUNT: usize = 999;
+
+/// What to do after feeding a key press to the sequence parser
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceOutcome {
+    /// The keys typed so far are a count or the start of a longer sequence
+    Pending,
+    /// Perform the action the given number of times
+    Perform(Action, usize),
+    /// The keys don't form any binding and were discarded
+    Unbound,
+}
+
+/// Collects counts and multi-key sequences (e.g. `3dd`, `gg`) typed outside of text input
+#[derive(Debug, Clone, Default)]
+pub struct KeyBuffer {
+    /// Chords typed so far that form the start of a longer sequence
+    pending: Vec<KeyChord>,
+    /// Count typed before the command, if any
+    count: Option<usize>,
+    /// Last repeatable action and its count, replayed by `RepeatLast`
+    last_repeatable: Option<(Action, usize)>,
+}
+
+impl KeyBuffer {
+    /// Feed a key press, resolving it against the key map once a binding is complete
+    pub fn feed(
+        &mut self,
+        key_map: &KeyMap,
+        context: KeyContext,
+        key: KeyEvent,
+    ) -> SequenceOutcome {
+        // Esc always abandons a half-typed command
+        if key.code == KeyCode::Esc && self.is_pending() {
+            self.reset();
+            return SequenceOutcome::Unbound;
+        }
+
+        let chord = KeyChord::from_event(key);
+
+        // Digits build up a count, unless they are part of a sequence. A leading
+        // zero is not a count, which leaves "0" free to be bound
+        if key_map.counts_enabled()
+            && self.pending.is_empty()
+            && let Some(digit) = Self::digit(&chord)
+            && (digit != 0 || self.count.is_some())
+        {
+            let count = self.count.unwrap_or(0) * 10 + digit;
+            self.count = Some(count.min(MAX_COUNT));
+            return SequenceOutcome::Pending;
+        }
+
+        self.pending.push(chord);
+        match key_map.resolve(context, &self.pending) {
+            Resolution::Prefix => SequenceOutcome::Pending,
+            Resolution::Unbound => {
+                self.reset();
+                SequenceOutcome::Unbound
+            }
+            Resolution::Action(Action::RepeatLast) => {
+                let count = self.count;
+                self.reset();
+                match self.last_repeatable {
+                    // An explicit count replaces the one of the repeated command
+                    Some((action, last_count)) => {
+                        let count = count.unwrap_or(last_count);
+                        self.last_repeatable = Some((action, count));
+                        SequenceOutcome::Perform(action, count)
+                    }
+                    None => SequenceOutcome::Unbound,
+                }
+            }
+            Resolution::Action(action) => {
+                let count = self.count.unwrap_or(1);
+                self.reset();
+                if action.is_repeatable() {
+                    self.last_repeatable = Some((action, count));
+                }
+                SequenceOutcome::Perform(action, count)
+            }
+        }
+    }
+
+    /// Whether a count or the start of a sequence is waiting for more keys
+    pub fn is_pending(&self) -> bool {
+        self.count.is_some() || !self.pending.is_empty()
+    }
+
+    /// The count and keys typed so far, e.g. "3d", shown while waiting for more keys
+    pub fn pending_display(&self) -> Option<String> {
+        if !self.is_pending() {
+            return None;
+        }
+
+        let count = self.count.map(|c| c.to_string()).unwrap_or_default();
+        let keys: String = self.pending.iter().map(|c| c.to_string()).collect();
+        Some(format!("{}{}", count, keys))
+    }
+
+    /// Forget any half-typed command (the repeatable action is kept)
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.count = None;
+    }
+
+    /// Value of a plain digit key
+    fn digit(chord: &KeyChord) -> Option<usize> {
+        match (chord.code, chord.modifiers) {
+            (KeyCode::Char(c), KeyModifiers::NONE) => c.to_digit(10).map(|d| d as usize),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keymap::KeymapPreset;
+    use std::collections::BTreeMap;
+
+    fn vim() -> KeyMap {
+        KeyMap::new(KeymapPreset::Vim, &BTreeMap::new()).unwrap()
+    }
+
+    fn feed_str(buffer: &mut KeyBuffer, key_map: &KeyMap, keys: &str) -> SequenceOutcome {
+        let mut outcome = SequenceOutcome::Unbound;
+        for c in keys.chars() {
+            let modifiers = if c.is_ascii_uppercase() {
+                KeyModifiers::SHIFT
+            } else {
+                KeyModifiers::NONE
+            };
+            let key = KeyEvent::new(KeyCode::Char(c), modifiers);
+            outcome = buffer.feed(key_map, KeyContext::Main, key);
+        }
+        outcome
+    }
+
+    #[test]
+    fn test_sequences_wait_for_completion() {
+        let key_map = vim();
+        let mut buffer = KeyBuffer::default();
+
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "g"),
+            SequenceOutcome::Pending
+        );
+        assert_eq!(buffer.pending_display().as_deref(), Some("g"));
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "g"),
+            SequenceOutcome::Perform(Action::CursorTop, 1)
+        );
+        assert!(!buffer.is_pending());
+
+        // A wrong second key drops the whole buffer
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "gq"),
+            SequenceOutcome::Unbound
+        );
+        assert!(!buffer.is_pending());
+    }
+
+    #[test]
+    fn test_counts() {
+        let key_map = vim();
+        let mut buffer = KeyBuffer::default();
+
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "3j"),
+            SequenceOutcome::Perform(Action::CursorDown, 3)
+        );
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "12d"),
+            SequenceOutcome::Pending
+        );
+        assert_eq!(buffer.pending_display().as_deref(), Some("12d"));
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "d"),
+            SequenceOutcome::Perform(Action::DeleteSelected, 12)
+        );
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "99999j"),
+            SequenceOutcome::Perform(Action::CursorDown, MAX_COUNT)
+        );
+    }
+
+    #[test]
+    fn test_counts_need_vim_preset() {
+        let key_map = KeyMap::default();
+        let mut buffer = KeyBuffer::default();
+
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "3"),
+            SequenceOutcome::Unbound
+        );
+        assert!(!buffer.is_pending());
+    }
+
+    #[test]
+    fn test_escape_cancels() {
+        let key_map = vim();
+        let mut buffer = KeyBuffer::default();
+
+        feed_str(&mut buffer, &key_map, "4d");
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(
+            buffer.feed(&key_map, KeyContext::Main, esc),
+            SequenceOutcome::Unbound
+        );
+        assert!(!buffer.is_pending());
+    }
+
+    #[test]
+    fn test_repeat_last() {
+        let key_map = vim();
+        let mut buffer = KeyBuffer::default();
+
+        // Nothing to repeat yet
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "."),
+            SequenceOutcome::Unbound
+        );
+
+        feed_str(&mut buffer, &key_map, "2dd");
+        // Movements don't replace the repeatable action
+        feed_str(&mut buffer, &key_map, "j");
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "."),
+            SequenceOutcome::Perform(Action::DeleteSelected, 2)
+        );
+        assert_eq!(
+            feed_str(&mut buffer, &key_map, "5."),
+            SequenceOutcome::Perform(Action::DeleteSelected, 5)
+        );
+    }
+}
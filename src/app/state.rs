@@ -1,20 +1,36 @@
 use crate::app::events::EventHandler;
-use crate::db::config::{Config, DBConfig};
-use crate::db::connections::init_db;
-use crate::db::models::{TodoList, UIList};
+use crate::keymap::{Action, KeyContext, KeyMap};
+use crate::app::messages::MessageLog;
+use crate::app::palette::{PaletteEntry, PaletteState, PaletteTarget};
+use crate::app::sequence::KeyBuffer;
+use crate::db::backup;
+use crate::db::config::{Config, DBConfig, ViewConfig};
+use crate::db::connections::{
+    db_file_path, delete_db_file, get_db_pool, get_read_only_db_pool, init_db, open_db_to_read,
+};
+use crate::db::models::{ItemSource, Priority, SavedView, TodoList, UIList};
+use crate::db::query::Query;
+use crate::error::JudoError;
 use crate::ui::components::{
-    AddDBPopUp, AddItemPopUp, AddListPopUp, ChangeDBPopUp, DBSelector, InputState, ItemsComponent,
-    ListsComponent, Logo, ModifyItemPopUp, ModifyListPopUp,
+    AddDBPopUp, AddItemPopUp, AddListPopUp, ChangeDBPopUp, CommandPalettePopUp, DBSelector,
+    FilterPopUp, HelpPopUp, InputState, ItemsComponent, ListsComponent, Logo, MessageLogPopUp,
+    ModifyItemPopUp, ModifyListPopUp, RemoveDBPopUp, RenameDBPopUp, SaveViewPopUp, StatusBar,
 };
 use crate::ui::cursor::CursorState;
-use crate::ui::layout::AppLayout;
+use crate::ui::layout::{AppLayout, MainAreas};
 use color_eyre::Result;
-use crossterm::event::{self, KeyEvent};
+use crossterm::event::{self, KeyEvent, MouseEvent};
 use ratatui::DefaultTerminal;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
+use ratatui::text::Line;
 use ratatui::widgets::Widget;
 use sqlx::SqlitePool;
+use std::time::{Duration, Instant};
+use tracing::instrument;
+
+/// Maximum delay between two clicks on the same cell to count as a double-click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
 
 /// Enum representing the different screens in the application
 #[derive(Debug, Clone, PartialEq)]
@@ -33,24 +49,72 @@ pub enum CurrentScreen {
     ChangeDB,
     /// Pop-up for adding a new database
     AddDB,
+    /// Pop-up for renaming the selected database
+    RenameDB,
+    /// Pop-up asking whether to keep the file of the database being removed
+    RemoveDB,
+    /// Overlay listing the key bindings of the screen below it
+    Help,
+    /// Pop-up for fuzzy searching actions, lists and items
+    CommandPalette,
+    /// Overlay listing the messages reported so far
+    MessageLog,
+    /// Pop-up for entering the query the items are filtered with
+    Filter,
+    /// Pop-up for naming a view of the current filter
+    SaveView,
+}
+
+/// Pane of the main screen that pane-relative actions apply to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pane {
+    /// The todo lists on the left
+    #[default]
+    Lists,
+    /// The items of the selected list
+    Items,
 }
 
 /// Main application state
 pub struct App {
     /// Configuration of available databases
     pub config: Config,
+    /// Active key bindings
+    pub key_map: KeyMap,
+    /// Counts and key sequences typed so far
+    pub key_buffer: KeyBuffer,
+    /// Pane that pane-relative actions (cursor moves, delete) apply to
+    pub focus: Pane,
+    /// Position the item being added is inserted at (None appends it)
+    pub insert_position: Option<usize>,
     /// Config of currently selected database
     pub current_db_config: DBConfig,
     /// Current active screen (Main, AddList, ModifyList, or AddItem)
     pub current_screen: CurrentScreen,
     /// Database connection pool
     pub pool: SqlitePool,
+    /// Whether databases are opened read-only, without migrating them
+    pub read_only: bool,
     /// Lists component for managing todo lists
     pub lists_component: ListsComponent,
     /// State of user-provided input
     pub input_state: InputState,
     /// Selected database index for DB selector
     pub selected_db_index: usize,
+    /// Layout areas from the last render, used to hit-test mouse events
+    pub areas: MainAreas,
+    /// Time and position of the last left click, used to detect double-clicks
+    pub last_click: Option<(Instant, u16, u16)>,
+    /// Screen the help overlay was opened from, and returns to
+    pub help_return_screen: CurrentScreen,
+    /// Lines the help overlay is scrolled down by
+    pub help_scroll: usize,
+    /// State of the command palette
+    pub palette: PaletteState,
+    /// Messages reported to the user, shown in the status bar and message log
+    pub messages: MessageLog,
+    /// Lines the message log is scrolled down by
+    pub messages_scroll: usize,
     /// Flag to indicate if the application should exit
     pub exit: bool,
 }
@@ -59,18 +123,27 @@ impl App {
     /// Create new app instance
     ///
     /// Initializes the database connection, loads existing lists from the database,
-    /// and sets up the initial UI state.
-    pub async fn new() -> Self {
+    /// and sets up the initial UI state. With `read_only`, databases are opened
+    /// without migrating or writing to them, so that those of a newer version of
+    /// judo can still be read.
+    pub async fn new(read_only: bool) -> std::result::Result<Self, JudoError> {
         // Read the config (creates default if missing)
-        let config = Config::read().expect("Failed to read config file");
+        Self::with_config(Config::read()?, read_only).await
+    }
+
+    /// Create an app instance from a config already read
+    pub async fn with_config(
+        config: Config,
+        read_only: bool,
+    ) -> std::result::Result<Self, JudoError> {
+        // Resolve key bindings, failing on invalid or conflicting ones
+        let key_map = config.key_map()?;
 
         // Extract the default db and its connection string
-        let default_db_config = config
-            .get_default()
-            .expect("Couldn't fetch default database");
-        let pool = init_db(&default_db_config.connection_str)
+        let default_db_config = config.get_default()?;
+        let pool = connect(&default_db_config.connection_str, read_only)
             .await
-            .expect("Failed to connect to database");
+            .map_err(JudoError::Db)?;
 
         // Start from main screen
         let current_screen = CurrentScreen::Main;
@@ -80,18 +153,37 @@ impl App {
         lists_component
             .load_lists(&pool)
             .await
-            .expect("Failed to read lists");
+            .map_err(JudoError::Db)?;
 
-        Self {
+        let mut app = Self {
             config,
+            key_map,
+            key_buffer: KeyBuffer::default(),
+            focus: Pane::default(),
+            insert_position: None,
             current_db_config: default_db_config,
             current_screen,
             pool,
+            read_only,
             lists_component,
             input_state: InputState::new(),
             selected_db_index: 0,
+            areas: MainAreas::default(),
+            last_click: None,
+            help_return_screen: CurrentScreen::Main,
+            help_scroll: 0,
+            palette: PaletteState::default(),
+            messages: MessageLog::default(),
+            messages_scroll: 0,
             exit: false,
+        };
+        if read_only {
+            app.messages
+                .warn("Databases are open read-only, changes can't be saved");
         }
+        app.load_views().await;
+
+        Ok(app)
     }
 
     /// Run the application
@@ -99,13 +191,27 @@ impl App {
     /// Main event loop that handles terminal drawing and user input.
     /// Continues until the user exits the application.
     pub async fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        for warning in self.backup_databases().await {
+            self.messages.warn(warning);
+        }
+
         while !self.exit {
             // Draw the current state of the application
             terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
 
-            // Handle keyboard input based on current screen
-            if let Some(key) = event::read()?.as_key_press_event() {
+            // Wake up when the status bar message expires, so it disappears on time
+            if let Some(timeout) = self.messages.toast_remaining(Instant::now())
+                && !event::poll(timeout)?
+            {
+                continue;
+            }
+
+            // Handle keyboard and mouse input based on current screen
+            let event = event::read()?;
+            if let Some(key) = event.as_key_press_event() {
                 self.handle_key_event(key).await;
+            } else if let Some(mouse) = event.as_mouse_event() {
+                EventHandler::handle_mouse_event(&mut self, mouse).await;
             }
         }
         Ok(())
@@ -116,15 +222,14 @@ impl App {
         &mut self,
         db_name: String,
         set_as_default: bool,
-    ) -> Result<()> {
+    ) -> std::result::Result<(), JudoError> {
         // Use data directory to standardize storage
         let data_dir = dirs::data_dir()
-            .ok_or_else(|| color_eyre::eyre::eyre!("Could not find data directory"))?
+            .ok_or_else(|| JudoError::ConfigInvalid("Could not find data directory".to_string()))?
             .join("judo");
 
         // Create directory if it doesn't exist
-        std::fs::create_dir_all(&data_dir)
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to create data directory: {}", e))?;
+        std::fs::create_dir_all(&data_dir).map_err(JudoError::io(&data_dir))?;
 
         // Create path to new db file
         let db_file = format!("{}.db", db_name);
@@ -137,12 +242,11 @@ impl App {
         let new_db_config = DBConfig {
             name: db_name.clone(),
             connection_str: connection_str.clone(),
+            caldav: None,
         };
 
         // Initialize the new database (this creates the file and runs migrations)
-        init_db(&connection_str)
-            .await
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to initialize new database: {}", e))?;
+        init_db(&connection_str).await.map_err(JudoError::Db)?;
 
         // Add to config
         self.config.dbs.push(new_db_config);
@@ -153,22 +257,18 @@ impl App {
         }
 
         // Write updated config to file
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?
-            .join("judo");
-        let config_path = config_dir.join("judo.toml");
-
-        self.config
-            .write(&config_path)
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to save config: {}", e))?;
+        self.config.save()?;
 
         // Update selected index to point to the new database
         self.selected_db_index = self.config.dbs.len() - 1;
 
+        self.messages.info(format!("Created database {}", db_name));
+
         Ok(())
     }
 
     /// Handle key events and delegate to appropriate handler
+    #[instrument(level = "debug", skip(self), fields(screen = ?self.current_screen))]
     async fn handle_key_event(&mut self, key: KeyEvent) {
         match self.current_screen {
             CurrentScreen::Main => EventHandler::handle_main_screen_key(self, key).await,
@@ -183,6 +283,478 @@ impl App {
             }
             CurrentScreen::ChangeDB => EventHandler::handle_change_db_screen_key(self, key).await,
             CurrentScreen::AddDB => EventHandler::handle_add_db_screen_key(self, key).await,
+            CurrentScreen::RenameDB => EventHandler::handle_rename_db_screen_key(self, key).await,
+            CurrentScreen::RemoveDB => EventHandler::handle_remove_db_screen_key(self, key).await,
+            CurrentScreen::Help => EventHandler::handle_help_screen_key(self, key).await,
+            CurrentScreen::CommandPalette => {
+                EventHandler::handle_palette_screen_key(self, key).await
+            }
+            CurrentScreen::MessageLog => {
+                EventHandler::handle_message_log_screen_key(self, key).await
+            }
+            CurrentScreen::Filter => EventHandler::handle_filter_screen_key(self, key).await,
+            CurrentScreen::SaveView => EventHandler::handle_save_view_screen_key(self, key).await,
+        }
+    }
+
+    /// Record a left click and report whether it completes a double-click
+    pub fn register_click(&mut self, mouse: &MouseEvent) -> bool {
+        let now = Instant::now();
+        let is_double_click = matches!(
+            self.last_click,
+            Some((at, column, row))
+                if column == mouse.column
+                    && row == mouse.row
+                    && now.duration_since(at) <= DOUBLE_CLICK_INTERVAL
+        );
+
+        // A double-click consumes both clicks, so a third click starts over
+        self.last_click = if is_double_click {
+            None
+        } else {
+            Some((now, mouse.column, mouse.row))
+        };
+
+        is_double_click
+    }
+
+    /// Resolve an action that depends on the focused pane to its concrete counterpart
+    pub fn focused_action(&self, action: Action) -> Action {
+        match (action, self.focus) {
+            (Action::CursorDown, Pane::Lists) => Action::NextList,
+            (Action::CursorDown, Pane::Items) => Action::NextItem,
+            (Action::CursorUp, Pane::Lists) => Action::PreviousList,
+            (Action::CursorUp, Pane::Items) => Action::PreviousItem,
+            (Action::DeleteSelected, Pane::Lists) => Action::DeleteList,
+            (Action::DeleteSelected, Pane::Items) => Action::DeleteItem,
+            _ => action,
+        }
+    }
+
+    /// Move the focus to a pane, selecting an item when entering the items pane
+    pub fn focus_pane(&mut self, pane: Pane) {
+        self.focus = pane;
+        if pane == Pane::Items
+            && let Some(selected_list) = self.lists_component.get_selected_list_mut()
+        {
+            ItemsComponent::select_first_item(selected_list);
+        }
+    }
+
+    /// Open the help overlay for the current screen
+    pub fn enter_help_screen(&mut self) {
+        self.help_return_screen = self.current_screen.clone();
+        self.help_scroll = 0;
+        self.current_screen = CurrentScreen::Help;
+    }
+
+    /// Close the help overlay, returning to the screen it was opened from
+    pub fn exit_help_screen(&mut self) {
+        self.current_screen = self.help_return_screen.clone();
+    }
+
+    /// Open the command palette over actions, databases, and the lists and
+    /// items of every database
+    pub async fn enter_palette_screen(&mut self) {
+        let mut entries = PaletteEntry::actions(&self.key_map);
+
+        for (i, db) in self.config.dbs.iter().enumerate() {
+            if db.name != self.current_db_config.name {
+                entries.push(PaletteEntry::new(
+                    format!("Switch to database {}", db.name),
+                    PaletteTarget::SwitchDb(i),
+                ));
+            }
+        }
+
+        if self
+            .lists_component
+            .get_selected_list()
+            .is_some_and(|ui_list| ui_list.item_state.selected().is_some())
+        {
+            entries.extend(PaletteEntry::priorities());
+        }
+
+        for (db_index, db) in self.config.dbs.iter().enumerate() {
+            // The current database is already loaded, the others are read on demand
+            let lists = if db.name == self.current_db_config.name {
+                self.lists_component.lists.clone()
+            } else {
+                match Self::read_db_lists(&db.connection_str, self.read_only).await {
+                    Ok(lists) => lists,
+                    Err(e) => {
+                        self.messages.warn(format!(
+                            "Failed to read lists of database {}: {}",
+                            db.name, e
+                        ));
+                        continue;
+                    }
+                }
+            };
+
+            for ui_list in &lists {
+                entries.push(PaletteEntry::new(
+                    format!("List: {} ({})", ui_list.list.name, db.name),
+                    PaletteTarget::List {
+                        db_index,
+                        list_id: ui_list.list.id,
+                    },
+                ));
+            }
+            for ui_list in &lists {
+                for ui_item in &ui_list.items {
+                    entries.push(PaletteEntry::new(
+                        format!(
+                            "Item: {} in {} ({})",
+                            ui_item.item.name, ui_list.list.name, db.name
+                        ),
+                        PaletteTarget::Item {
+                            db_index,
+                            list_id: ui_list.list.id,
+                            item_id: ui_item.item.id,
+                        },
+                    ));
+                }
+            }
+        }
+
+        self.palette = PaletteState::new(entries);
+        self.current_screen = CurrentScreen::CommandPalette;
+    }
+
+    /// Read all lists and items of a database that isn't the current one
+    async fn read_db_lists(connection_str: &str, read_only: bool) -> Result<Vec<UIList>> {
+        let pool = open_db_to_read(connection_str, read_only)
+            .await
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to connect to database: {}", e))?;
+        let lists = UIList::get_all(&pool)
+            .await
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to load lists: {}", e));
+        pool.close().await;
+        lists
+    }
+
+    /// Close the command palette without running anything
+    pub fn exit_palette_screen(&mut self) {
+        self.current_screen = CurrentScreen::Main;
+        self.palette = PaletteState::default();
+    }
+
+    /// Run the highlighted command palette entry
+    pub async fn run_palette_entry(&mut self) -> Result<()> {
+        let Some(entry) = self.palette.selected_entry().cloned() else {
+            return Ok(());
+        };
+        self.exit_palette_screen();
+
+        match entry.target {
+            PaletteTarget::Action(action) => EventHandler::perform_action(self, action).await,
+            PaletteTarget::SwitchDb(db_index) => {
+                self.selected_db_index = db_index;
+                self.switch_to_selected_db().await?;
+            }
+            PaletteTarget::SetPriority(priority) => {
+                self.set_selected_item_priority(priority).await?
+            }
+            PaletteTarget::List { db_index, list_id } => {
+                self.open_db_at(db_index).await?;
+                self.select_list_by_id(list_id);
+                self.focus = Pane::Lists;
+            }
+            PaletteTarget::Item {
+                db_index,
+                list_id,
+                item_id,
+            } => {
+                self.open_db_at(db_index).await?;
+                self.select_list_by_id(list_id);
+                if let Some(ui_list) = self.lists_component.get_selected_list_mut()
+                    && let Some(j) = ui_list.items.iter().position(|i| i.item.id == item_id)
+                {
+                    ui_list.item_state.select(Some(j));
+                }
+                self.focus = Pane::Items;
+            }
+        }
+        Ok(())
+    }
+
+    /// Switch to the database at the given index of the config, unless it's the current one
+    async fn open_db_at(&mut self, db_index: usize) -> Result<()> {
+        let is_current = self
+            .config
+            .dbs
+            .get(db_index)
+            .is_some_and(|db| db.name == self.current_db_config.name);
+        if !is_current {
+            self.selected_db_index = db_index;
+            self.switch_to_selected_db().await?;
+        }
+        Ok(())
+    }
+
+    /// Select the list with the given ID, if it exists
+    fn select_list_by_id(&mut self, list_id: i64) {
+        if let Some(i) = self
+            .lists_component
+            .lists
+            .iter()
+            .position(|ui_list| ui_list.list.id == list_id)
+        {
+            self.lists_component.list_state.select(Some(i));
+        }
+    }
+
+    /// Set the priority of the selected item
+    async fn set_selected_item_priority(&mut self, priority: Priority) -> Result<()> {
+        if let Some(ui_list) = self.lists_component.get_selected_list_mut()
+            && let Some(j) = ui_list.item_state.selected()
+        {
+            // Items of a view are changed in their own database
+            let pool = ui_list.items[j].pool(&self.pool).clone();
+            ui_list.items[j]
+                .item
+                .update_priority(&pool, priority)
+                .await
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to set priority: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Open the message log
+    pub fn enter_message_log_screen(&mut self) {
+        self.messages_scroll = 0;
+        self.messages.dismiss();
+        self.current_screen = CurrentScreen::MessageLog;
+    }
+
+    /// Close the message log
+    pub fn exit_message_log_screen(&mut self) {
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    /// Open the filter pop-up with the current query
+    pub fn enter_filter_screen(&mut self) {
+        let text = self
+            .lists_component
+            .filter
+            .as_ref()
+            .map(|query| query.text.clone())
+            .unwrap_or_default();
+        self.input_state = InputState {
+            cursor_pos: text.chars().count(),
+            current_input: text,
+            is_modifying: false,
+        };
+        self.current_screen = CurrentScreen::Filter;
+    }
+
+    /// Close the filter pop-up without changing the filter
+    pub fn exit_filter_screen(&mut self) {
+        self.current_screen = CurrentScreen::Main;
+        self.input_state.clear();
+    }
+
+    /// Filter the items with the entered query, or show all items if it's empty
+    pub async fn apply_filter(&mut self) -> Result<()> {
+        let text = self.input_state.get_text().trim().to_string();
+        let filter = if text.is_empty() {
+            None
+        } else {
+            Some(text.parse::<Query>()?)
+        };
+
+        self.lists_component.filter = filter;
+        self.lists_component
+            .refresh_lists(&self.pool)
+            .await
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to load lists: {}", e))?;
+
+        match &self.lists_component.filter {
+            Some(query) => self
+                .messages
+                .info(format!("Filtering items by {}", query.text)),
+            None => self.messages.info("Showing all items"),
+        }
+        self.exit_filter_screen();
+        Ok(())
+    }
+
+    /// Open the pop-up for saving the filter as a view
+    pub fn enter_save_view_screen(&mut self) {
+        if self.lists_component.filter.is_none() {
+            self.messages.warn(format!(
+                "Filter the items with {} before saving a view",
+                self.key_map.key_label(Action::FilterItems)
+            ));
+            return;
+        }
+        self.input_state = InputState::default();
+        self.current_screen = CurrentScreen::SaveView;
+    }
+
+    /// Close the save view pop-up without saving
+    pub fn exit_save_view_screen(&mut self) {
+        self.current_screen = CurrentScreen::Main;
+        self.input_state.clear();
+    }
+
+    /// Save the filter as a view of all databases, and select it
+    pub async fn save_filter_as_view(&mut self, name: String) -> Result<()> {
+        let Some(filter) = &self.lists_component.filter else {
+            return Ok(());
+        };
+        if self.config.views.iter().any(|view| view.name == name) {
+            return Err(color_eyre::eyre::eyre!(
+                "A view named {} already exists",
+                name
+            ));
+        }
+
+        self.config.views.push(ViewConfig {
+            name: name.clone(),
+            query: filter.text.clone(),
+            dbs: Vec::new(),
+        });
+        self.config.save()?;
+        self.load_views().await;
+
+        if let Some(k) = self
+            .lists_component
+            .views
+            .iter()
+            .position(|view| view.list.name == name)
+        {
+            self.lists_component
+                .list_state
+                .select(Some(self.lists_component.lists.len() + k));
+        }
+        self.messages.info(format!("Saved view {}", name));
+        self.exit_save_view_screen();
+        Ok(())
+    }
+
+    /// Remove the selected view from judo.toml
+    pub async fn delete_selected_view(&mut self) -> Result<()> {
+        let Some(name) = self
+            .lists_component
+            .get_selected_list()
+            .filter(|ui_list| ui_list.view.is_some())
+            .map(|ui_list| ui_list.list.name.clone())
+        else {
+            return Ok(());
+        };
+
+        self.config.views.retain(|view| view.name != name);
+        self.config.save()?;
+        self.load_views().await;
+
+        // Keep the selection within the lists and views left
+        if let Some(i) = self.lists_component.selected()
+            && i >= self.lists_component.len()
+        {
+            self.lists_component
+                .list_state
+                .select(self.lists_component.len().checked_sub(1));
+        }
+        self.messages.info(format!("Deleted view {}", name));
+        Ok(())
+    }
+
+    /// Take the scheduled backups of the databases that are due one, unless
+    /// they are open read-only, returning a warning for each that failed
+    pub async fn backup_databases(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.read_only {
+            return warnings;
+        }
+
+        for db in &self.config.dbs {
+            let Some(path) = db_file_path(&db.connection_str) else {
+                continue;
+            };
+            if !path.exists() {
+                continue;
+            }
+
+            // The current database is already connected
+            let result = if db.name == self.current_db_config.name {
+                backup::run_schedule(&self.pool, &path, &self.config.backup).await
+            } else {
+                match get_db_pool(&db.connection_str).await {
+                    Ok(pool) => {
+                        let result = backup::run_schedule(&pool, &path, &self.config.backup).await;
+                        pool.close().await;
+                        result
+                    }
+                    Err(e) => Err(e),
+                }
+            };
+            if let Err(e) = result {
+                warnings.push(format!("Failed to back up database {}: {:#}", db.name, e));
+            }
+        }
+        warnings
+    }
+
+    /// Load the views of judo.toml, reporting those that can't be read
+    pub async fn load_views(&mut self) {
+        let mut views = Vec::new();
+        for view in self.config.views.clone() {
+            match self.read_view(&view).await {
+                Ok(ui_list) => views.push(ui_list),
+                Err(e) => self
+                    .messages
+                    .warn(format!("Failed to load view {}: {}", view.name, e)),
+            }
+        }
+        self.lists_component.views = views;
+    }
+
+    /// Connect to the databases of a view and read its items
+    async fn read_view(&self, view: &ViewConfig) -> Result<UIList> {
+        let query: Query = view.query.parse()?;
+
+        let mut sources = Vec::new();
+        for db in self.config.view_dbs(view)? {
+            // The current database is already connected
+            let pool = if db.name == self.current_db_config.name {
+                self.pool.clone()
+            } else {
+                open_db_to_read(&db.connection_str, self.read_only)
+                    .await
+                    .map_err(|e| {
+                        color_eyre::eyre::eyre!("Failed to connect to database {}: {}", db.name, e)
+                    })?
+            };
+            sources.push(ItemSource {
+                db_name: db.name,
+                pool,
+            });
+        }
+
+        UIList::get_view(SavedView {
+            name: view.name.clone(),
+            query,
+            sources,
+        })
+        .await
+        .map_err(|e| color_eyre::eyre::eyre!("{:#}", e))
+    }
+
+    /// Screen drawn below any overlay
+    fn underlying_screen(&self) -> &CurrentScreen {
+        match self.current_screen {
+            CurrentScreen::Help => &self.help_return_screen,
+            _ => &self.current_screen,
+        }
+    }
+
+    /// Key bindings listed in the help overlay
+    fn help_context(&self) -> KeyContext {
+        match self.help_return_screen {
+            CurrentScreen::ChangeDB => KeyContext::Database,
+            _ => KeyContext::Main,
         }
     }
 
@@ -204,8 +776,12 @@ impl App {
 
     /// Enter the "Add Item" screen by opening the corresponding pop-up
     pub fn enter_add_item_screen(&mut self) {
-        if self.lists_component.selected().is_some() {
+        if self.lists_component.is_view_selected() {
+            self.messages
+                .warn("Items can only be added to a list, not to a view");
+        } else if self.lists_component.selected().is_some() {
             self.input_state = InputState::default();
+            self.insert_position = None;
             self.current_screen = CurrentScreen::AddItem;
         }
     }
@@ -236,6 +812,7 @@ impl App {
     pub fn exit_add_item_without_saving(&mut self) {
         self.current_screen = CurrentScreen::Main;
         self.input_state.clear();
+        self.insert_position = None;
     }
 
     /// Enter the "Change DB" screen by opening the corresponding pop-up
@@ -266,6 +843,115 @@ impl App {
         self.input_state.clear();
     }
 
+    /// Enter the "Rename DB" screen, starting from the current name
+    pub fn enter_rename_db_screen(&mut self) {
+        if let Some(db) = self.config.dbs.get(self.selected_db_index) {
+            self.input_state = InputState {
+                cursor_pos: db.name.chars().count(),
+                current_input: db.name.clone(),
+                is_modifying: true,
+            };
+            self.current_screen = CurrentScreen::RenameDB;
+        }
+    }
+
+    /// Rename the selected database in judo.toml, keeping its file
+    pub async fn rename_selected_db(&mut self, new_name: String) -> Result<()> {
+        let Some(db) = self.config.dbs.get(self.selected_db_index).cloned() else {
+            return Ok(());
+        };
+        if new_name != db.name {
+            self.config.rename_db(&db.name, &new_name)?;
+            self.config.save()?;
+
+            if self.current_db_config.name == db.name {
+                self.current_db_config.name = new_name.clone();
+            }
+            // Items of views show the name of their database
+            self.load_views().await;
+            self.messages
+                .info(format!("Renamed database {} to {}", db.name, new_name));
+        }
+
+        self.exit_db_input_screen();
+        Ok(())
+    }
+
+    /// Enter the "Remove DB" screen, unless the selected database is in use
+    pub fn enter_remove_db_screen(&mut self) {
+        let Some(db) = self.config.dbs.get(self.selected_db_index) else {
+            return;
+        };
+        if db.name == self.current_db_config.name {
+            self.messages.warn(format!(
+                "{} is open, switch to another database before removing it",
+                db.name
+            ));
+        } else if db.name == self.config.default {
+            self.messages.warn(format!(
+                "{} is the default database, make another database the default first",
+                db.name
+            ));
+        } else {
+            self.current_screen = CurrentScreen::RemoveDB;
+        }
+    }
+
+    /// Remove the selected database from judo.toml, and delete its file if asked to
+    pub async fn remove_selected_db(&mut self, delete_file: bool) -> Result<()> {
+        let Some(name) = self
+            .config
+            .dbs
+            .get(self.selected_db_index)
+            .map(|db| db.name.clone())
+        else {
+            return Ok(());
+        };
+        if name == self.current_db_config.name {
+            return Err(color_eyre::eyre::eyre!(
+                "{} is open, switch to another database before removing it",
+                name
+            ));
+        }
+
+        // Delete the file before forgetting it, so that a failure leaves the
+        // database in the config
+        let mut config = self.config.clone();
+        let db = config.remove_db(&name)?;
+        let deleted = match db_file_path(&db.connection_str) {
+            Some(path) if delete_file => {
+                delete_db_file(&path).map_err(JudoError::io(&path))?;
+                Some(path)
+            }
+            _ => None,
+        };
+        config.save()?;
+        self.config = config;
+
+        // Stay on the database below the removed one
+        self.selected_db_index = self
+            .selected_db_index
+            .min(self.config.dbs.len().saturating_sub(1));
+        self.current_screen = CurrentScreen::ChangeDB;
+        self.load_views().await;
+
+        match deleted {
+            Some(path) => self.messages.info(format!(
+                "Removed database {} and deleted {}",
+                name,
+                path.display()
+            )),
+            None => self.messages.info(format!("Removed database {}", name)),
+        }
+        Ok(())
+    }
+
+    /// Return from renaming or removing a database to the database pop-up
+    pub fn exit_db_input_screen(&mut self) {
+        self.current_screen = CurrentScreen::ChangeDB;
+        self.input_state.clear();
+    }
+
     /// Move selection up in DB list
     pub fn select_previous_db(&mut self) {
         if self.config.dbs.is_empty() {
@@ -290,7 +976,7 @@ impl App {
     pub async fn switch_to_selected_db(&mut self) -> Result<()> {
         if let Some(selected_db) = self.config.dbs.get(self.selected_db_index) {
             // Initialize connection to the new database
-            let new_pool = init_db(&selected_db.connection_str)
+            let new_pool = connect(&selected_db.connection_str, self.read_only)
                 .await
                 .map_err(|e| color_eyre::eyre::eyre!("Failed to connect to database: {}", e))?;
 
@@ -298,8 +984,12 @@ impl App {
             self.current_db_config = selected_db.clone();
             self.pool = new_pool;
 
-            // Reload all lists from the new database
+            // Reload all lists from the new database, keeping the filter and views
+            let filter = self.lists_component.filter.take();
+            let views = std::mem::take(&mut self.lists_component.views);
             self.lists_component = ListsComponent::new();
+            self.lists_component.filter = filter;
+            self.lists_component.views = views;
             self.lists_component
                 .load_lists(&self.pool)
                 .await
@@ -307,6 +997,10 @@ impl App {
 
             // Return to main screen
             self.current_screen = CurrentScreen::Main;
+            self.messages.info(format!(
+                "Switched to database {}",
+                self.current_db_config.name
+            ));
         }
         Ok(())
     }
@@ -318,76 +1012,195 @@ impl App {
             self.config.default = selected_db.name.clone();
 
             // Write updated config to file
-            let config_dir = dirs::config_dir()
-                .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?
-                .join("judo");
-            let config_path = config_dir.join("judo.toml");
+            self.config.save()?;
 
-            self.config
-                .write(&config_path)
-                .map_err(|e| color_eyre::eyre::eyre!("Failed to save config: {}", e))?;
+            self.messages.info(format!(
+                "{} is now the default database",
+                self.config.default
+            ));
         }
         Ok(())
     }
 }
 
+/// Connect to a database, migrating it unless it's opened read-only
+async fn connect(connection_str: &str, read_only: bool) -> anyhow::Result<SqlitePool> {
+    if read_only {
+        get_read_only_db_pool(connection_str).await
+    } else {
+        init_db(connection_str).await
+    }
+}
+
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         // Render background
         AppLayout::render_background(self.config.clone(), area, buf);
 
         // Calculate layout areas
-        let (lists_area, items_area, logo_area, db_selector_area, closed_selector_area) =
-            AppLayout::calculate_main_layout(area);
+        let (
+            lists_area,
+            items_area,
+            logo_area,
+            db_selector_area,
+            closed_selector_area,
+            status_area,
+        ) = AppLayout::calculate_main_layout(area);
+
+        // Keep the areas around for mouse hit-testing
+        self.areas = MainAreas {
+            lists: lists_area,
+            items: items_area,
+            db_selector: db_selector_area,
+            closed_selector: closed_selector_area,
+            status: status_area,
+        };
 
         // Render logo
         Logo::render(logo_area, buf);
 
         // Render db selector only when not in database-related popups
         if !matches!(
-            self.current_screen,
-            CurrentScreen::ChangeDB | CurrentScreen::AddDB
+            self.underlying_screen(),
+            CurrentScreen::ChangeDB
+                | CurrentScreen::AddDB
+                | CurrentScreen::RenameDB
+                | CurrentScreen::RemoveDB
         ) {
             DBSelector::render(
                 closed_selector_area,
                 buf,
                 &self.current_db_config.name,
                 self.config.clone(),
+                &self.key_map,
             );
         }
 
+        // Only highlight the focused pane if focus can actually be moved
+        let show_focus = self.key_map.uses_focus();
+
         // Render the main areas
-        self.lists_component
-            .render(lists_area, buf, self.config.clone());
+        self.lists_component.render(
+            lists_area,
+            buf,
+            self.config.clone(),
+            &self.key_map,
+            show_focus && self.focus == Pane::Lists,
+        );
 
         // Render items with the selected list
         let selected_list = self.lists_component.get_selected_list_mut();
-        ItemsComponent::render(selected_list, items_area, buf, self.config.clone());
+        ItemsComponent::render(
+            selected_list,
+            items_area,
+            buf,
+            self.config.clone(),
+            &self.key_map,
+            show_focus && self.focus == Pane::Items,
+        );
+
+        // Show the count and keys of a half-typed command in the top right corner,
+        // or else the active filter
+        let corner_label = self.key_buffer.pending_display().or_else(|| {
+            self.lists_component
+                .filter
+                .as_ref()
+                .map(|query| format!("filter: {}", query.text))
+        });
+        if let Some(pending) = corner_label {
+            let width = (pending.chars().count() as u16 + 2).min(items_area.width);
+            let pending_area = Rect {
+                x: items_area.right().saturating_sub(width + 2),
+                y: items_area.y,
+                width,
+                height: 1.min(items_area.height),
+            };
+            Line::raw(format!(" {} ", pending)).render(pending_area, buf);
+        }
 
         // Render popup screens if active
-        match self.current_screen {
+        match self.underlying_screen() {
             CurrentScreen::AddList => {
                 AddListPopUp::render(self.config.clone(), &self.input_state, lists_area, buf)
             }
             CurrentScreen::ModifyList => {
                 ModifyListPopUp::render(self.config.clone(), &self.input_state, lists_area, buf)
             }
+            CurrentScreen::SaveView => {
+                SaveViewPopUp::render(self.config.clone(), &self.input_state, lists_area, buf)
+            }
             CurrentScreen::AddItem => {
                 AddItemPopUp::render(self.config.clone(), &self.input_state, items_area, buf)
             }
             CurrentScreen::ModifyItem => {
                 ModifyItemPopUp::render(self.config.clone(), &self.input_state, items_area, buf)
             }
-            CurrentScreen::ChangeDB => {
-                ChangeDBPopUp::render(&self.config, self.selected_db_index, db_selector_area, buf)
+            CurrentScreen::Filter => {
+                FilterPopUp::render(self.config.clone(), &self.input_state, items_area, buf)
             }
+            CurrentScreen::ChangeDB => ChangeDBPopUp::render(
+                &self.config,
+                &self.key_map,
+                self.selected_db_index,
+                db_selector_area,
+                buf,
+            ),
             CurrentScreen::AddDB => AddDBPopUp::render(
                 self.config.clone(),
                 &self.input_state,
                 db_selector_area,
                 buf,
             ),
+            CurrentScreen::RenameDB => RenameDBPopUp::render(
+                self.config.clone(),
+                &self.input_state,
+                db_selector_area,
+                buf,
+            ),
+            CurrentScreen::RemoveDB => {
+                if let Some(db) = self.config.dbs.get(self.selected_db_index) {
+                    RemoveDBPopUp::render(&self.config, db, db_selector_area, buf)
+                }
+            }
             _ => {}
         }
+
+        // Render the latest message or the global hints in the status bar
+        StatusBar::render(
+            &self.config,
+            &self.key_map,
+            self.messages.toast(Instant::now()),
+            status_area,
+            buf,
+        );
+
+        // Render the message log over the whole screen
+        if self.current_screen == CurrentScreen::MessageLog {
+            self.messages_scroll = MessageLogPopUp::render(
+                &self.config,
+                &self.key_map,
+                &self.messages,
+                self.messages_scroll,
+                area,
+                buf,
+            );
+        }
+
+        // Render the command palette over the whole screen
+        if self.current_screen == CurrentScreen::CommandPalette {
+            CommandPalettePopUp::render(self.config.clone(), &self.palette, area, buf);
+        }
+
+        // Render the help overlay on top of everything else
+        if self.current_screen == CurrentScreen::Help {
+            self.help_scroll = HelpPopUp::render(
+                &self.config,
+                &self.key_map,
+                self.help_context(),
+                self.help_scroll,
+                area,
+                buf,
+            );
+        }
     }
 }
@@ -1,10 +1,26 @@
-use clap::{Parser, Subcommand};
+use crate::cli::output::OutputFormat;
+use crate::db::doctor::OrphanFix;
+use crate::db::models::Priority;
+use crate::db::ops::ItemSort;
+use crate::db::snapshot::ImportMode;
+use crate::formats;
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(version, about)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    ///Output format of the show commands (default table) and of export (default json)
+    #[arg(long, global = true, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    ///Open databases read-only, without migrating them (for databases of a newer judo)
+    #[arg(long, global = true)]
+    pub read_only: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -23,7 +39,70 @@ pub enum Commands {
 
     ///Manage todo items
     Items {#[command(subcommand)]
-        command: Option<ItemCommands>}
+        command: Option<ItemCommands>},
+
+    ///Manage views, saved filters shown as lists in the TUI
+    Views {
+        #[command(subcommand)]
+        command: Option<ViewCommands>
+    },
+
+    ///Write all lists and items of a database to standard output (JSON, Markdown, todo.txt or iCalendar)
+    Export {
+        ///Name of the database to export (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>,
+
+        ///Only export the list with this name (do not use with -i|--list-id)
+        #[arg(short, long)]
+        list_name: Option<String>,
+
+        ///Only export the list with this ID (do not use with -l|--list-name)
+        #[arg(short = 'i', long)]
+        list_id: Option<i64>
+    },
+
+    ///Read the lists and items of a file (JSON written by judo export, Markdown, todo.txt, iCalendar or Taskwarrior) into a database
+    Import {
+        ///File to import, - for standard input (its format is told by --format, or else the extension)
+        file: PathBuf,
+
+        ///Name of the database to import into (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>,
+
+        ///Whether to merge into the lists and items of the database (merge) or replace them (replace)
+        #[arg(short, long, value_parser = parse_import_mode, default_value = "merge")]
+        mode: ImportMode
+    },
+
+    ///Sync a database with a task server
+    Sync {
+        #[command(subcommand)]
+        command: Option<SyncCommands>
+    },
+
+    ///Back databases up and restore them
+    Backup {
+        #[command(subcommand)]
+        command: Option<BackupCommands>
+    }
+}
+
+impl Commands {
+    /// Whether a command was given rather than only its group (e.g. `judo lists`),
+    /// which opens the TUI
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Commands::Dbs { command } => command.is_some(),
+            Commands::Lists { command } => command.is_some(),
+            Commands::Items { command } => command.is_some(),
+            Commands::Views { command } => command.is_some(),
+            Commands::Sync { command } => command.is_some(),
+            Commands::Backup { command } => command.is_some(),
+            Commands::Export { .. } | Commands::Import { .. } => true,
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -37,6 +116,61 @@ pub enum DbCommands {
         #[arg(short, long)]
         name: String
     },
+
+    ///Remove a database from the configuration, optionally deleting its file too
+    Remove {
+        ///Name of the database to be removed
+        #[arg(short, long)]
+        name: String,
+
+        ///Also delete the database file
+        #[arg(long)]
+        delete_file: bool
+    },
+
+    ///Rename a database (its file keeps its name)
+    Rename {
+        ///Current name of the database
+        #[arg(short, long)]
+        name: String,
+
+        ///New name of the database
+        #[arg(short = 'N', long)]
+        new_name: String
+    },
+
+    ///Make a database the default
+    Default {
+        ///Name of the new default database
+        #[arg(short, long)]
+        name: String
+    },
+
+    ///Check databases for damage and inconsistencies, optionally fixing them
+    Doctor {
+        ///Name of the database to check (every database if omitted)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        ///Fix what can be fixed, after taking a backup, and vacuum the database
+        #[arg(long)]
+        fix: bool,
+
+        ///What to do with items whose list is gone when fixing: move them to a "Recovered items" list (reattach) or delete them (delete)
+        #[arg(long, value_parser = parse_orphan_fix, default_value = "reattach", requires = "fix")]
+        orphans: OrphanFix
+    },
+
+    ///Migrate databases to this version of judo, or only report what would be migrated
+    Migrate {
+        ///Name of the database to migrate (every database if omitted)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        ///Only report the pending migrations, changing nothing
+        #[arg(long)]
+        dry_run: bool
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -69,12 +203,52 @@ pub enum ListCommands {
         #[arg(short, long)]
         db: Option<String>
     },
+
+    ///Rename a todo list with the given name or ID in the specified database (default DB if omitted)
+    Rename {
+        ///Name of the list to be renamed (do not use with -i|--id)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        ///ID of the list to be renamed (do not use with -n|--name)
+        #[arg(short, long)]
+        id: Option<i64>,
+
+        ///New name of the list
+        #[arg(short = 'N', long)]
+        new_name: String,
+
+        ///Name of the database that contains the target list (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>
+    },
+
+    ///Move a todo list with the given name or ID up, down or to a position in the ordering
+    Move {
+        ///Name of the list to be moved (do not use with -i|--id)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        ///ID of the list to be moved (do not use with -n|--name)
+        #[arg(short, long)]
+        id: Option<i64>,
+
+        #[command(flatten)]
+        movement: Movement,
+
+        ///Name of the database that contains the target list (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>
+    },
 }
 
 #[derive(Subcommand, Debug)]
 pub enum ItemCommands {
-    ///List all todo items in a table which shows what list and database each belongs to
-    Show,
+    ///List todo items in a table which shows what list and database each belongs to, optionally filtered and sorted
+    Show {
+        #[command(flatten)]
+        filters: ItemFilters,
+    },
 
     ///Add a new todo item with the given name to the specified list (by ID or name) and database (default DB if omitted)
     Add {
@@ -92,7 +266,83 @@ pub enum ItemCommands {
 
         ///ID of the list to hold the new todo item (do not use with -n|--name)
         #[arg(short = 'i', long)]
-        list_id: Option<i64>
+        list_id: Option<i64>,
+
+        ///Priority of the new todo item (high, medium or low)
+        #[arg(short, long, value_parser = parse_priority)]
+        priority: Option<Priority>,
+
+        ///Due date (YYYY-MM-DD, local midnight) or time (RFC 3339) of the new todo item
+        #[arg(long, value_parser = parse_date)]
+        due: Option<DateTime<Utc>>
+    },
+
+    ///Change the name, priority or due date of a todo item, or remove its priority or due date
+    Edit {
+        ///ID of the target todo item
+        #[arg(short, long)]
+        id: i64,
+
+        ///Name of the database containing the target item (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>,
+
+        ///New name of the item
+        #[arg(short, long)]
+        name: Option<String>,
+
+        ///New priority of the item (high, medium or low)
+        #[arg(short, long, value_parser = parse_priority, conflicts_with = "no_priority")]
+        priority: Option<Priority>,
+
+        ///Remove the priority of the item
+        #[arg(long)]
+        no_priority: bool,
+
+        ///New due date (YYYY-MM-DD, local midnight) or time (RFC 3339) of the item
+        #[arg(long, value_parser = parse_date, conflicts_with = "no_due")]
+        due: Option<DateTime<Utc>>,
+
+        ///Remove the due date of the item
+        #[arg(long)]
+        no_due: bool
+    },
+
+    ///Move a todo item up one place in its list
+    MoveUp {
+        ///ID of the target todo item
+        #[arg(short, long)]
+        id: i64,
+
+        ///Name of the database containing the target item (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>
+    },
+
+    ///Move a todo item down one place in its list
+    MoveDown {
+        ///ID of the target todo item
+        #[arg(short, long)]
+        id: i64,
+
+        ///Name of the database containing the target item (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>
+    },
+
+    ///Move a todo item to a position in its list
+    MoveTo {
+        ///ID of the target todo item
+        #[arg(short, long)]
+        id: i64,
+
+        ///Position to move the item to: a number starting from 1, "top" or "bottom"
+        #[arg(short, long, value_parser = parse_position)]
+        position: usize,
+
+        ///Name of the database containing the target item (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>
     },
 
     ///Delete an existing todo item with the given ID from the given database (default DB if omitted)
@@ -119,3 +369,214 @@ pub enum ItemCommands {
     }
 }
 
+
+#[derive(Subcommand, Debug)]
+pub enum ViewCommands {
+    ///List all views
+    Show,
+
+    ///Save a query as a view of the given databases (all databases if omitted)
+    Add {
+        ///Name of the new view
+        #[arg(short, long)]
+        name: String,
+
+        ///Query the items of the view match, e.g. "priority:high due<fri not done" (see README)
+        #[arg(short, long)]
+        query: String,
+
+        ///Name of a database to take items from, can be repeated (all databases if omitted)
+        #[arg(short, long)]
+        db: Vec<String>
+    },
+
+    ///Remove the view with the given name
+    Remove {
+        ///Name of the view to be removed
+        #[arg(short, long)]
+        name: String
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SyncCommands {
+    ///Two-way sync of a database with the to-dos of a CalDAV calendar
+    Caldav {
+        ///Name of the database to sync (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>,
+
+        ///URL of the calendar collection (default the url of [dbs.caldav] in judo.toml)
+        #[arg(long)]
+        url: Option<String>,
+
+        ///Name of the CalDAV user (default the username of [dbs.caldav] in judo.toml)
+        #[arg(short, long)]
+        username: Option<String>
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BackupCommands {
+    ///Take a backup of a database now
+    Create {
+        ///Name of the database to back up (default DB if omitted)
+        #[arg(short, long, conflicts_with = "all")]
+        db: Option<String>,
+
+        ///Back up every database
+        #[arg(long)]
+        all: bool
+    },
+
+    ///List the backups of the databases, oldest first
+    List {
+        ///Name of the database (every database if omitted)
+        #[arg(short, long)]
+        db: Option<String>
+    },
+
+    ///Replace a database with one of its backups, after backing it up
+    Restore {
+        ///Name of the database to restore (default DB if omitted)
+        #[arg(short, long)]
+        db: Option<String>,
+
+        ///Name of the backup as shown by `judo backup list` (latest if omitted)
+        #[arg(short, long)]
+        name: Option<String>
+    },
+}
+
+/// Where to move a list or item, exactly one of the options is required
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct Movement {
+    ///Move up one place
+    #[arg(long)]
+    pub up: bool,
+
+    ///Move down one place
+    #[arg(long)]
+    pub down: bool,
+
+    ///Move to a position: a number starting from 1, "top" or "bottom"
+    #[arg(long, value_parser = parse_position)]
+    pub to: Option<usize>,
+}
+
+/// A move of a list or item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    Up,
+    Down,
+    /// To a 0-based position, clamped to the end
+    To(usize),
+}
+
+impl From<&Movement> for Move {
+    fn from(movement: &Movement) -> Self {
+        match movement.to {
+            Some(position) => Move::To(position),
+            None if movement.up => Move::Up,
+            None => Move::Down,
+        }
+    }
+}
+
+#[derive(Args, Debug, Default)]
+pub struct ItemFilters {
+    ///Only show items of this database (all databases if omitted)
+    #[arg(short, long)]
+    pub db: Option<String>,
+
+    ///Only show items of this view, in its databases unless --db is given
+    #[arg(long)]
+    pub view: Option<String>,
+
+    ///Only show items of the list with this name
+    #[arg(short, long)]
+    pub list: Option<String>,
+
+    ///Only show items marked as done
+    #[arg(long, conflicts_with = "open")]
+    pub done: bool,
+
+    ///Only show items not marked as done
+    #[arg(long)]
+    pub open: bool,
+
+    ///Only show items with this priority (high, medium or low)
+    #[arg(short, long, value_parser = parse_priority)]
+    pub priority: Option<Priority>,
+
+    ///Only show items due before this date (YYYY-MM-DD, local midnight) or time (RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    pub due_before: Option<DateTime<Utc>>,
+
+    ///Only show items due after this date (YYYY-MM-DD, local midnight) or time (RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    pub due_after: Option<DateTime<Utc>>,
+
+    ///Only show items not marked as done whose due date has passed
+    #[arg(long)]
+    pub overdue: bool,
+
+    ///Only show items whose name contains this text (ignoring case)
+    #[arg(short, long)]
+    pub search: Option<String>,
+
+    ///Only show items matching this query, e.g. "priority:high due<fri not done" (see README)
+    #[arg(short, long)]
+    pub query: Option<String>,
+
+    ///Show at most this many items
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+
+    ///Order of the items: ordering (as in the TUI), due (earliest first), priority (highest
+    ///first), name or created (oldest first)
+    #[arg(long, value_parser = parse_sort, default_value = "ordering")]
+    pub sort: ItemSort,
+}
+
+/// Parse a 1-based position, "top" or "bottom" into a 0-based position
+/// (past the end for the bottom)
+pub fn parse_position(s: &str) -> Result<usize, String> {
+    match s {
+        "top" => Ok(0),
+        "bottom" => Ok(usize::MAX),
+        _ => match s.parse::<usize>() {
+            Ok(position) if position >= 1 => Ok(position - 1),
+            _ => Err(format!(
+                "'{}' is neither a position starting from 1 nor top or bottom",
+                s
+            )),
+        },
+    }
+}
+
+/// Parse a priority, see `Priority::from_str`
+pub fn parse_priority(s: &str) -> Result<Priority, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse an order of items, see `ItemSort::from_str`
+pub fn parse_sort(s: &str) -> Result<ItemSort, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse an import mode, see `ImportMode::from_str`
+pub fn parse_import_mode(s: &str) -> Result<ImportMode, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse what to do with orphaned items, see `OrphanFix::from_str`
+pub fn parse_orphan_fix(s: &str) -> Result<OrphanFix, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parse a date (midnight local time) or an RFC 3339 time
+pub fn parse_date(s: &str) -> Result<DateTime<Utc>, String> {
+    formats::parse_date(s).map_err(|e| e.to_string())
+}
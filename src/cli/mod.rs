@@ -1,4 +1,5 @@
 pub mod args;
 pub mod ops;
+pub mod output;
 
 pub use ops::*;
\ No newline at end of file
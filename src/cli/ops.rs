@@ -1,177 +1,901 @@
-use std::io::Write;
-
-use crate::db::config::DBConfig;
-use crate::db::connections::{self, get_db_pool};
 use crate::app::App;
-use crate::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use crate::cli::args::{ItemFilters, Move};
+use crate::cli::output::{
+    BackupRecord, DbRecord, ItemRecord, ListRecord, OutputFormat, ViewRecord, print_records,
+};
+use crate::db::backup::{self, BackupReason};
+use crate::db::config::{Config, DBConfig, ViewConfig};
+use crate::db::connections::{
+    db_file_path, delete_db_file, get_db_pool, get_read_only_db_pool, init_db, migration_status,
+    open_db,
+};
+use crate::db::doctor::{self, OrphanFix};
+use crate::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use crate::db::ops::ItemQuery;
+use crate::db::query::Expr;
+use crate::db::snapshot::{ImportMode, Snapshot};
+use crate::error::{JudoError, Result};
+use crate::formats::{ical, markdown, taskwarrior, todotxt};
+use crate::sync::caldav;
+use crate::sync::webdav::CalDavClient;
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
 use sqlx::{Pool, Sqlite};
-use tabwriter::TabWriter;
-
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 //Database operations
 
-pub fn list_dbs(app: &App) {
-    let db_list = &app.config.dbs;
+pub fn list_dbs(app: &App, format: OutputFormat) -> Result<()> {
+    let records: Vec<DbRecord> = app
+        .config
+        .dbs
+        .iter()
+        .map(|db| DbRecord {
+            name: &db.name,
+            connection_str: &db.connection_str,
+            default: db.name == app.config.default,
+        })
+        .collect();
+
+    print_records(format, &records)
+}
 
-    let mut tw = TabWriter::new(vec![]);
-    writeln!(tw, "Name\tConnection string").unwrap();
-    writeln!(tw, "----\t-----------------").unwrap();
-    for db in db_list {
-        writeln!(tw, "{}\t{}", db.name, db.connection_str).unwrap();
+pub async fn add_db(mut app: App, name: String) -> Result<()> {
+    app.create_new_database(name, false).await
+}
+
+pub fn remove_db(mut app: App, name: String, delete_file: bool) -> Result<()> {
+    let db = app.config.remove_db(&name)?;
+
+    // Delete the file before forgetting it, so that a failure leaves the
+    // database in the config
+    if delete_file && let Some(path) = db_file_path(&db.connection_str) {
+        delete_db_file(&path).map_err(JudoError::io(&path))?;
     }
-    tw.flush().unwrap();
-    let output = String::from_utf8(tw.into_inner().unwrap()).unwrap();
-    print!("{output}")
+    app.config.save()
 }
 
-pub async fn add_db(mut app: App, name: String) {
-    app.create_new_database(name, false).await.unwrap()
+pub fn rename_db(mut app: App, name: String, new_name: String) -> Result<()> {
+    app.config.rename_db(&name, &new_name)?;
+    app.config.save()
 }
 
+pub fn set_default_db(mut app: App, name: String) -> Result<()> {
+    app.config.set_default_db(&name)?;
+    app.config.save()
+}
 
-//List operations
+pub async fn doctor_dbs(
+    config: &Config,
+    name: &Option<String>,
+    fix: bool,
+    orphans: OrphanFix,
+) -> Result<()> {
+    let dbs = match name {
+        Some(name) => vec![config.get_db_by_name(name)?],
+        None => config.dbs.clone(),
+    };
+
+    let mut unhealthy = Vec::new();
+    for db in dbs {
+        let pool = get_db_pool(db.connection_str.as_str())
+            .await
+            .map_err(JudoError::Db)?;
+        let report = doctor::check(&pool).await.map_err(JudoError::Db)?;
+        if report.is_healthy() {
+            println!("Database {}: no problems found", db.name);
+        } else {
+            println!("Database {}:", db.name);
+            for problem in report.problems() {
+                println!("  {}", problem);
+            }
+        }
+
+        if !fix {
+            if !report.is_healthy() {
+                unhealthy.push(db.name);
+            }
+            continue;
+        }
+        if !report.is_repairable() {
+            println!(
+                "  Can't be fixed by judo, restore a backup with `judo backup restore --db {}`",
+                db.name
+            );
+            unhealthy.push(db.name);
+            continue;
+        }
+
+        if let Some(path) = db_file_path(&db.connection_str) {
+            let backup = backup::create(&pool, &path, BackupReason::Repair)
+                .await
+                .map_err(backup_error(&path))?;
+            backup::prune(&path, config.backup.keep).map_err(backup_error(&path))?;
+            println!("  Backed up to {}", backup.path.display());
+        }
+        let summary = doctor::repair(&pool, &report, orphans)
+            .await
+            .map_err(JudoError::Db)?;
+        if !report.is_healthy() {
+            println!(
+                "  Fixed: respaced {} ordering(s), reattached {} and deleted {} orphaned item(s), \
+                 fixed the priority of {} item(s)",
+                summary.renormalized, summary.reattached, summary.deleted, summary.priorities_fixed
+            );
+        }
+        doctor::vacuum(&pool).await.map_err(JudoError::Db)?;
+        println!("  Vacuumed");
+    }
+
+    if !unhealthy.is_empty() {
+        return Err(JudoError::Db(anyhow::anyhow!(
+            "Problems found in database(s) {}",
+            unhealthy.join(", ")
+        )));
+    }
+    Ok(())
+}
 
-pub async fn list_lists(app: &App) {
-    let dbs = &app.config.dbs;
-    let mut tw = TabWriter::new(vec![]);
-    writeln!(tw, "Name\tID\tDB\tNo of items").unwrap();
-    writeln!(tw, "----\t--\t--\t-----------").unwrap();
-    
+pub async fn migrate_dbs(config: &Config, name: &Option<String>, dry_run: bool) -> Result<()> {
+    let dbs = match name {
+        Some(name) => vec![config.get_db_by_name(name)?],
+        None => config.dbs.clone(),
+    };
+
+    let mut newer = Vec::new();
     for db in dbs {
-        let db_pool = connections::get_db_pool(
-            db.connection_str.as_str()
-        ).await.unwrap();
-        let lists = TodoList::get_all(&db_pool).await.unwrap();
-        
+        if let Some(path) = db_file_path(&db.connection_str)
+            && !path.exists()
+        {
+            println!("Database {}: no file yet, set up when first used", db.name);
+            continue;
+        }
+
+        // Read without migrating, which a newer database mustn't be
+        let pool = get_read_only_db_pool(db.connection_str.as_str())
+            .await
+            .map_err(JudoError::Db)?;
+        let status = migration_status(&pool).await.map_err(JudoError::Db)?;
+        pool.close().await;
+
+        if !status.unknown.is_empty() {
+            let versions: Vec<String> = status.unknown.iter().map(|v| v.to_string()).collect();
+            println!(
+                "Database {}: set up by a newer version of judo (unknown migration(s) {})",
+                db.name,
+                versions.join(", ")
+            );
+            newer.push(db.name);
+            continue;
+        }
+        if status.pending.is_empty() {
+            println!("Database {}: up to date", db.name);
+            continue;
+        }
+
+        if dry_run {
+            println!(
+                "Database {}: {} pending migration(s)",
+                db.name,
+                status.pending.len()
+            );
+        } else {
+            init_db(db.connection_str.as_str())
+                .await
+                .map_err(JudoError::Db)?
+                .close()
+                .await;
+            println!(
+                "Database {}: applied {} migration(s)",
+                db.name,
+                status.pending.len()
+            );
+        }
+        for migration in &status.pending {
+            println!("  {} {}", migration.version, migration.description);
+        }
+    }
+
+    if !newer.is_empty() {
+        return Err(JudoError::Db(anyhow::anyhow!(
+            "Database(s) {} set up by a newer version of judo, update judo or use --read-only",
+            newer.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+//List operations
+
+pub async fn list_lists(app: &App, format: OutputFormat) -> Result<()> {
+    let mut records = Vec::new();
+
+    for db in &app.config.dbs {
+        let db_pool = open_db(db.connection_str.as_str(), app.read_only)
+            .await
+            .map_err(JudoError::Db)?;
+        let lists = TodoList::get_all(&db_pool).await.map_err(JudoError::Db)?;
+
         for list in lists {
-            let num = list.get_all_items(&db_pool).await.unwrap().len();
-            writeln!(tw, "{}\t{}\t{}\t{}", list.name, list.id, db.name, num).unwrap();
+            let item_count = list
+                .get_all_items(&db_pool)
+                .await
+                .map_err(JudoError::Db)?
+                .len();
+            records.push(ListRecord {
+                db: &db.name,
+                list,
+                item_count,
+            });
         }
     }
-    tw.flush().unwrap();
-    let output = String::from_utf8(tw.into_inner().unwrap()).unwrap();
-    print!("{output}")
+
+    print_records(format, &records)
 }
 
-pub async fn add_list(app: &App, name: String, db_name: &Option<String>) {
-    let pool = get_db_pool_from_option(app, db_name).await;
-    
-    let list = NewTodoList { name: name };
-    TodoList::create(&pool, list).await.unwrap();
+pub async fn add_list(app: &App, name: String, db_name: &Option<String>) -> Result<()> {
+    check_list_name(&name)?;
+    let (_, pool) = get_db_and_pool_from_option(app, db_name).await?;
+
+    let list = NewTodoList { name };
+    TodoList::create(&pool, list).await.map_err(JudoError::Db)?;
+    Ok(())
 }
 
-pub async fn delete_list(app: &App, name: Option<String>, id: Option<i64>, db_name: &Option<String>) {
-    let pool = get_db_pool_from_option(app, db_name).await;
+pub async fn delete_list(
+    app: &App,
+    name: Option<String>,
+    id: Option<i64>,
+    db_name: &Option<String>,
+) -> Result<()> {
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
 
-    let target_list = get_list_by_name_or_id(app, name, id, db_name).await;
-    target_list.delete(&pool).await.unwrap();
+    let target_list = get_list_by_name_or_id(&db, &pool, name, id).await?;
+    target_list.delete(&pool).await.map_err(JudoError::Db)?;
+    Ok(())
 }
 
+pub async fn rename_list(
+    app: &App,
+    name: Option<String>,
+    id: Option<i64>,
+    new_name: String,
+    db_name: &Option<String>,
+) -> Result<()> {
+    check_list_name(&new_name)?;
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
+
+    let mut target_list = get_list_by_name_or_id(&db, &pool, name, id).await?;
+    target_list
+        .update_name(&pool, new_name)
+        .await
+        .map_err(JudoError::Db)?;
+    Ok(())
+}
+
+pub async fn move_list(
+    app: &App,
+    name: Option<String>,
+    id: Option<i64>,
+    movement: Move,
+    db_name: &Option<String>,
+) -> Result<()> {
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
+
+    let mut target_list = get_list_by_name_or_id(&db, &pool, name, id).await?;
+    let moved = match movement {
+        Move::Up => target_list.move_up(&pool).await,
+        Move::Down => target_list.move_down(&pool).await,
+        Move::To(position) => target_list.move_to_position(&pool, position).await,
+    };
+    moved.map_err(JudoError::Db)
+}
 
 //Item operations
 
-pub async fn list_items(app: &App) {
-    let dbs = &app.config.dbs;
-    let mut tw = TabWriter::new(vec![]);
-    writeln!(tw, "Name\tID\tList name\tList ID\tDB\tDone?").unwrap();
-    writeln!(tw, "----\t--\t---------\t-------\t--\t-----").unwrap();
+pub async fn list_items(app: &App, filters: &ItemFilters, format: OutputFormat) -> Result<()> {
+    let view = match &filters.view {
+        Some(name) => Some(app.config.get_view_by_name(name)?),
+        None => None,
+    };
+    let dbs = match (&filters.db, view) {
+        (Some(name), _) => vec![app.config.get_db_by_name(name)?],
+        (None, Some(view)) => app.config.view_dbs(view)?,
+        (None, None) => app.config.dbs.clone(),
+    };
+    let mut query = item_query(filters)?;
+    if let Some(view) = view {
+        query = query.matching(&parse_query(&view.query)?, &Local::now());
+    }
+
+    let mut records = Vec::new();
+    for db in &dbs {
+        let pool = open_db(db.connection_str.as_str(), app.read_only)
+            .await
+            .map_err(JudoError::Db)?;
+        for listed in query.fetch(&pool).await.map_err(JudoError::Db)? {
+            records.push(ItemRecord {
+                db: &db.name,
+                list_name: listed.list_name,
+                item: listed.item,
+            });
+        }
+    }
 
-    for db in dbs {
-        let pool = get_db_pool(db.connection_str.as_str()).await.unwrap();
-        let lists = TodoList::get_all(&pool).await.unwrap();
-        for list in lists {
-            let items = TodoItem::get_by_list_id(&pool, list.id).await.unwrap();
-            for item in items {
-                writeln!(tw, "{}\t{}\t{}\t{}\t{}\t{}", item.name, item.id, list.name, list.id, db.name, item.is_done).unwrap()
-            }
+    // Each database is sorted and limited by its query, merge them
+    if dbs.len() > 1 {
+        records.sort_by(|a, b| filters.sort.compare(&a.item, &b.item));
+        if let Some(limit) = filters.limit {
+            records.truncate(limit);
         }
     }
-    tw.flush().unwrap();
-    let output = String::from_utf8(tw.into_inner().unwrap()).unwrap();
-    print!("{output}")
+
+    print_records(format, &records)
+}
+
+pub async fn add_item(
+    app: &App,
+    name: String,
+    db_name: &Option<String>,
+    list_id: Option<i64>,
+    list_name: Option<String>,
+    priority: Option<Priority>,
+    due_date: Option<DateTime<Utc>>,
+) -> Result<()> {
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
+    let target_list = get_list_by_name_or_id(&db, &pool, list_name, list_id).await?;
+
+    let new_item = NewTodoItem {
+        name,
+        list_id: target_list.id,
+        priority,
+        due_date,
+    };
+    TodoItem::create(&pool, new_item)
+        .await
+        .map_err(JudoError::Db)?;
+    Ok(())
+}
+
+pub async fn delete_item(app: &App, id: i64, db_name: &Option<String>) -> Result<()> {
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
+    let item = get_item_by_id(&db, &pool, id).await?;
+
+    item.delete(&pool).await.map_err(JudoError::Db)?;
+    Ok(())
+}
+
+pub async fn toggle_done_item(app: &App, id: i64, db_name: &Option<String>) -> Result<()> {
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
+    let mut item = get_item_by_id(&db, &pool, id).await?;
+
+    item.toggle_done(&pool).await.map_err(JudoError::Db)?;
+    Ok(())
+}
+
+/// Change the given fields of an item, `Some(None)` removing its priority or
+/// due date
+pub async fn edit_item(
+    app: &App,
+    id: i64,
+    db_name: &Option<String>,
+    name: Option<String>,
+    priority: Option<Option<Priority>>,
+    due_date: Option<Option<DateTime<Utc>>>,
+) -> Result<()> {
+    if name.is_none() && priority.is_none() && due_date.is_none() {
+        return Err(JudoError::InvalidInput(
+            "Please provide a new name, priority or due date of the item".to_string(),
+        ));
+    }
+
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
+    let mut item = get_item_by_id(&db, &pool, id).await?;
+
+    if let Some(name) = name {
+        item.update_name(&pool, name).await.map_err(JudoError::Db)?;
+    }
+    match priority {
+        Some(Some(priority)) => item
+            .update_priority(&pool, priority)
+            .await
+            .map_err(JudoError::Db)?,
+        Some(None) => item.clear_priority(&pool).await.map_err(JudoError::Db)?,
+        None => {}
+    }
+    match due_date {
+        Some(Some(due_date)) => item
+            .update_due_date(&pool, due_date)
+            .await
+            .map_err(JudoError::Db)?,
+        Some(None) => item.clear_due_date(&pool).await.map_err(JudoError::Db)?,
+        None => {}
+    }
+    Ok(())
+}
+
+pub async fn move_item(app: &App, id: i64, movement: Move, db_name: &Option<String>) -> Result<()> {
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
+    let mut item = get_item_by_id(&db, &pool, id).await?;
+
+    let moved = match movement {
+        Move::Up => item.move_up(&pool).await,
+        Move::Down => item.move_down(&pool).await,
+        Move::To(position) => item.move_to_position(&pool, position).await,
+    };
+    moved.map_err(JudoError::Db)
+}
+
+//View operations
+
+pub fn list_views(app: &App, format: OutputFormat) -> Result<()> {
+    let records: Vec<ViewRecord> = app
+        .config
+        .views
+        .iter()
+        .map(|view| ViewRecord { view })
+        .collect();
+
+    print_records(format, &records)
+}
+
+pub fn add_view(mut app: App, name: String, query: String, dbs: Vec<String>) -> Result<()> {
+    if app.config.views.iter().any(|view| view.name == name) {
+        return Err(JudoError::InvalidInput(format!(
+            "A view named '{}' already exists",
+            name
+        )));
+    }
+
+    parse_query(&query)?;
+    let view = ViewConfig { name, query, dbs };
+    app.config.view_dbs(&view)?;
+
+    app.config.views.push(view);
+    app.config.save()
 }
 
-pub async fn add_item(app: &App, name: String, db_name: &Option<String>, list_id: Option<i64>, list_name: Option<String>) {
-    let pool = get_db_pool_from_option(app, db_name).await;
-    let target_list = get_list_by_name_or_id(app, list_name, list_id, db_name).await;
+pub fn remove_view(mut app: App, name: String) -> Result<()> {
+    app.config.get_view_by_name(&name)?;
+    app.config.views.retain(|view| view.name != name);
+    app.config.save()
+}
+
+//Import and export
+
+pub async fn export_db(
+    app: &App,
+    db_name: &Option<String>,
+    list_name: Option<String>,
+    list_id: Option<i64>,
+    format: Option<OutputFormat>,
+) -> Result<()> {
+    let format = exchange_format(format.unwrap_or(OutputFormat::Json))?;
+    if format == OutputFormat::Taskwarrior {
+        return Err(JudoError::InvalidInput(
+            "Databases can be imported from taskwarrior but not exported to it".to_string(),
+        ));
+    }
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
+
+    let mut snapshot = Snapshot::from_db(&pool).await.map_err(JudoError::Db)?;
+    let mut title = Some(db.name.as_str());
+    if list_name.is_some() || list_id.is_some() {
+        let list = get_list_by_name_or_id(&db, &pool, list_name, list_id).await?;
+        snapshot
+            .lists
+            .retain(|snapshot_list| snapshot_list.id == list.id);
+        title = None;
+    }
 
-    let new_item = NewTodoItem{name: name, list_id: target_list.id, priority: None, due_date: None};
-    TodoItem::create(&pool, new_item).await.unwrap();
+    let text = match format {
+        OutputFormat::Markdown => markdown::to_markdown(&snapshot, title),
+        OutputFormat::Todotxt => todotxt::to_todotxt(&snapshot),
+        OutputFormat::Ical => ical::to_ical(&snapshot, title),
+        _ => snapshot.to_json().map_err(JudoError::Db)? + "\n",
+    };
+    let mut out = std::io::stdout().lock();
+    write!(out, "{}", text).map_err(JudoError::io("stdout"))
 }
 
-pub async fn delete_item(app: &App, id: i64, db_name: &Option<String>) {
-    let db = get_db_from_option(app, db_name);
-    let pool = get_db_pool_from_option(app, db_name).await;
-    let item = match TodoItem::get_by_id(&pool, id).await.unwrap() {
-        Some(this) => this,
-        None => {
-            eprintln!("Error: Item with ID '{}' not found in database '{}'", id, db.name);
-            std::process::exit(exitcode::DATAERR)
+pub async fn import_db(
+    app: &App,
+    file: &Path,
+    db_name: &Option<String>,
+    mode: ImportMode,
+    format: Option<OutputFormat>,
+) -> Result<()> {
+    // Without --format, tell the format by the extension
+    let format = format.unwrap_or_else(|| match file.extension().and_then(|e| e.to_str()) {
+        Some("md" | "markdown") => OutputFormat::Markdown,
+        Some("txt") => OutputFormat::Todotxt,
+        Some("ics" | "ical") => OutputFormat::Ical,
+        _ => OutputFormat::Json,
+    });
+    let format = exchange_format(format)?;
+
+    let text = if file == Path::new("-") {
+        std::io::read_to_string(std::io::stdin()).map_err(JudoError::io("stdin"))?
+    } else {
+        std::fs::read_to_string(file).map_err(JudoError::io(file))?
+    };
+    let invalid =
+        |e: anyhow::Error| JudoError::InvalidInput(format!("{}: {:#}", file.display(), e));
+    let mut report = None;
+    let snapshot = match format {
+        OutputFormat::Markdown => markdown::from_markdown(&text),
+        OutputFormat::Todotxt => todotxt::from_todotxt(&text),
+        OutputFormat::Ical => ical::from_ical(&text).map_err(invalid)?,
+        OutputFormat::Taskwarrior => {
+            let (snapshot, taskwarrior_report) =
+                taskwarrior::from_taskwarrior(&text).map_err(invalid)?;
+            report = Some(taskwarrior_report);
+            snapshot
         }
+        _ => Snapshot::from_json(&text).map_err(invalid)?,
     };
 
-    item.delete(&pool).await.unwrap();
+    let (db, pool) = get_db_and_pool_from_option(app, db_name).await?;
+    let summary = snapshot.import(&pool, mode).await.map_err(JudoError::Db)?;
+
+    let count = |n: usize, what: &str| format!("{} {}{}", n, what, if n == 1 { "" } else { "s" });
+    let mut lists = count(summary.lists_created + summary.lists_merged, "list");
+    if summary.lists_merged > 0 {
+        lists += &format!(" ({} merged into existing lists)", summary.lists_merged);
+    }
+    let mut items = count(summary.items_created, "item");
+    if summary.items_skipped > 0 {
+        items += &format!(" (skipped {} already there)", summary.items_skipped);
+    }
+    println!("Imported {} and {} into database {}", lists, items, db.name);
+
+    if let Some(report) = report {
+        if report.deleted + report.recurring > 0 {
+            println!(
+                "Skipped {} deleted task(s) and {} template(s) of recurring tasks",
+                report.deleted, report.recurring
+            );
+        }
+        if !report.unmapped.is_empty() {
+            let fields: Vec<String> = report
+                .unmapped
+                .iter()
+                .map(|(field, n)| format!("{} ({})", field, count(*n, "task")))
+                .collect();
+            eprintln!(
+                "Warning: judo has no place for these fields, which were left out: {}",
+                fields.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+//Sync
+
+pub async fn sync_caldav(
+    app: &App,
+    db_name: &Option<String>,
+    url: Option<String>,
+    username: Option<String>,
+) -> Result<()> {
+    let db = get_db_from_option(&app.config, db_name)?;
+
+    // Arguments take precedence over judo.toml, the environment over the password there
+    let config = db.caldav.clone();
+    let url = url
+        .or_else(|| config.as_ref().map(|caldav| caldav.url.clone()))
+        .ok_or_else(|| {
+            JudoError::InvalidInput(format!(
+                "No CalDAV URL for database '{}', give one with --url or in [dbs.caldav] of judo.toml",
+                db.name
+            ))
+        })?;
+    let username = username.or_else(|| config.as_ref().and_then(|caldav| caldav.username.clone()));
+    let password = std::env::var(caldav::PASSWORD_ENV_VAR)
+        .ok()
+        .or_else(|| config.and_then(|caldav| caldav.password));
+
+    let client = CalDavClient::new(&url, username, password)
+        .map_err(|e| JudoError::InvalidInput(format!("{:#}", e)))?;
+    let pool = open_db(db.connection_str.as_str(), app.read_only)
+        .await
+        .map_err(JudoError::Db)?;
+    let summary = caldav::sync(&pool, &client)
+        .await
+        .map_err(JudoError::Sync)?;
+
+    println!(
+        "Synced database {} with {}: created {}, updated {} and deleted {} item(s) here, \
+         created {}, updated {} and deleted {} to-do(s) on the server",
+        db.name,
+        client.url(),
+        summary.created_local,
+        summary.updated_local,
+        summary.deleted_local,
+        summary.created_remote,
+        summary.updated_remote,
+        summary.deleted_remote,
+    );
+    if summary.conflicts > 0 {
+        println!(
+            "{} item(s) changed on both sides, the latest change was kept",
+            summary.conflicts
+        );
+    }
+    if summary.skipped > 0 {
+        println!(
+            "{} to-do(s) skipped, unreadable or changed on the server during the sync",
+            summary.skipped
+        );
+    }
+    Ok(())
 }
 
-pub async fn toggle_done_item(app: &App, id: i64, db_name: &Option<String>) {
-    let db = get_db_from_option(app, db_name);
-    let pool = get_db_pool_from_option(app, db_name).await;
-    let item = TodoItem::get_by_id(&pool, id).await.unwrap();
-    match item {
-        Some(mut this) => this.toggle_done(&pool).await.unwrap(),
-        None => {
-            eprintln!("Error: Item with ID '{}' not found in database '{}'", id, db.name);
-            std::process::exit(exitcode::DATAERR);
+//Backups
+
+pub async fn create_backup(config: &Config, db_name: &Option<String>, all: bool) -> Result<()> {
+    let dbs = if all {
+        config.dbs.clone()
+    } else {
+        vec![get_db_from_option(config, db_name)?]
+    };
+
+    for db in dbs {
+        let path = db_file(&db)?;
+        let pool = get_db_pool(db.connection_str.as_str())
+            .await
+            .map_err(JudoError::Db)?;
+        let backup = backup::create(&pool, &path, BackupReason::Manual)
+            .await
+            .map_err(backup_error(&path))?;
+        pool.close().await;
+        let pruned = backup::prune(&path, config.backup.keep).map_err(backup_error(&path))?;
+
+        println!(
+            "Backed up database {} to {}",
+            db.name,
+            backup.path.display()
+        );
+        if !pruned.is_empty() {
+            println!(
+                "Deleted {} old backup(s) of database {}",
+                pruned.len(),
+                db.name
+            );
         }
     }
+    Ok(())
 }
 
+pub fn list_backups(config: &Config, db_name: &Option<String>, format: OutputFormat) -> Result<()> {
+    let dbs = match db_name {
+        Some(name) => vec![config.get_db_by_name(name)?],
+        None => config.dbs.clone(),
+    };
+
+    let mut backups = Vec::new();
+    for db in &dbs {
+        // In-memory databases have nothing to back up
+        if let Some(path) = db_file_path(&db.connection_str) {
+            for backup in backup::list(&path).map_err(backup_error(&path))? {
+                backups.push((db.name.as_str(), backup));
+            }
+        }
+    }
+
+    let records: Vec<BackupRecord> = backups
+        .iter()
+        .map(|(db, backup)| BackupRecord {
+            db,
+            name: backup.name(),
+            backup,
+        })
+        .collect();
+    print_records(format, &records)
+}
+
+pub async fn restore_backup(
+    config: &Config,
+    db_name: &Option<String>,
+    name: Option<String>,
+) -> Result<()> {
+    let db = get_db_from_option(config, db_name)?;
+    let path = db_file(&db)?;
+
+    let backups = backup::list(&path).map_err(backup_error(&path))?;
+    let chosen = match &name {
+        Some(name) => backups
+            .iter()
+            .find(|backup| backup.name() == *name)
+            .ok_or_else(|| {
+                JudoError::NotFound(format!("Backup '{}' of database '{}'", name, db.name))
+            })?,
+        None => backups
+            .last()
+            .ok_or_else(|| JudoError::NotFound(format!("Backup of database '{}'", db.name)))?,
+    };
+
+    let pool = get_db_pool(db.connection_str.as_str())
+        .await
+        .map_err(JudoError::Db)?;
+    let previous = backup::create(&pool, &path, BackupReason::Restore)
+        .await
+        .map_err(backup_error(&path))?;
+    backup::restore(pool, &path, chosen)
+        .await
+        .map_err(JudoError::db_files(&path))?;
+    backup::prune(&path, config.backup.keep).map_err(backup_error(&path))?;
+
+    println!(
+        "Restored database {} from backup {}, its previous state is in backup {}",
+        db.name,
+        chosen.name(),
+        previous.name()
+    );
+    Ok(())
+}
 
 //General
 
-async fn get_list_by_name_or_id(app: &App, name: Option<String>, id: Option<i64>, db_name: &Option<String>) -> TodoList {
-    let db = get_db_from_option(app, db_name);
-    let pool = get_db_pool_from_option(app, db_name).await;
+// Path of the file of a database, which in-memory databases don't have
+fn db_file(db: &DBConfig) -> Result<PathBuf> {
+    db_file_path(&db.connection_str).ok_or_else(|| {
+        JudoError::InvalidInput(format!(
+            "Database '{}' is kept in memory and has no file to back up",
+            db.name
+        ))
+    })
+}
+
+// Failure of the backups of a database, an I/O error on their folder if that's
+// what it comes down to
+fn backup_error(db_path: &Path) -> impl FnOnce(anyhow::Error) -> JudoError {
+    JudoError::db_files(backup::backup_dir(db_path))
+}
+
+// Formats databases can be exported to and imported from
+fn exchange_format(format: OutputFormat) -> Result<OutputFormat> {
+    match format {
+        OutputFormat::Json
+        | OutputFormat::Markdown
+        | OutputFormat::Todotxt
+        | OutputFormat::Ical
+        | OutputFormat::Taskwarrior => Ok(format),
+        other => Err(JudoError::InvalidInput(format!(
+            "Databases can only be exported and imported as json, markdown, todotxt, ical or taskwarrior, not {}",
+            other
+                .to_possible_value()
+                .map_or_else(String::new, |v| v.get_name().to_string())
+        ))),
+    }
+}
+
+// Parse a query, pointing at the error if it's invalid
+fn parse_query(text: &str) -> Result<Expr> {
+    Expr::parse(text).map_err(|e| {
+        JudoError::InvalidInput(format!("Invalid query\n  {}\n  {}", text, e.underline()))
+    })
+}
+
+// Query for the items matching the filters
+fn item_query(filters: &ItemFilters) -> Result<ItemQuery> {
+    let mut query = ItemQuery::new().sort(filters.sort);
+
+    if let Some(text) = &filters.query {
+        query = query.matching(&parse_query(text)?, &Local::now());
+    }
+
+    if let Some(list) = &filters.list {
+        query = query.list_name(list);
+    }
+    if filters.done {
+        query = query.done(true);
+    }
+    if filters.open {
+        query = query.done(false);
+    }
+    if let Some(priority) = &filters.priority {
+        query = query.priority(priority);
+    }
+    if let Some(time) = filters.due_before {
+        query = query.due_before(time);
+    }
+    if let Some(time) = filters.due_after {
+        query = query.due_after(time);
+    }
+    if filters.overdue {
+        query = query.overdue(Utc::now());
+    }
+    if let Some(text) = &filters.search {
+        query = query.search(text);
+    }
+    if let Some(limit) = filters.limit {
+        query = query.limit(limit);
+    }
+
+    Ok(query)
+}
+
+// Refuse a list name that is empty or only whitespace
+fn check_list_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(JudoError::InvalidInput(
+            "The name of a list can't be empty".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn get_item_by_id(db: &DBConfig, pool: &Pool<Sqlite>, id: i64) -> Result<TodoItem> {
+    TodoItem::get_by_id(pool, id)
+        .await
+        .map_err(JudoError::Db)?
+        .ok_or_else(|| {
+            JudoError::NotFound(format!("Item with ID '{}' in database '{}'", id, db.name))
+        })
+}
+
+async fn get_list_by_name_or_id(
+    db: &DBConfig,
+    pool: &Pool<Sqlite>,
+    name: Option<String>,
+    id: Option<i64>,
+) -> Result<TodoList> {
     match (id, name) {
-        (Some(list_id), None) => {
-            return match TodoList::get_by_id(&pool, list_id).await.unwrap() {
-                Some(list) => list,
-                None => {
-                    eprintln!("Error: List with ID '{}' not found in database '{}'", list_id, db.name);
-                    std::process::exit(exitcode::DATAERR)
-                }
-            }
-        }
+        (Some(list_id), None) => TodoList::get_by_id(pool, list_id)
+            .await
+            .map_err(JudoError::Db)?
+            .ok_or_else(|| {
+                JudoError::NotFound(format!(
+                    "List with ID '{}' in database '{}'",
+                    list_id, db.name
+                ))
+            }),
         (None, Some(list_name)) => {
-            let lists = TodoList::get_all(&pool).await.unwrap();
-            for list in lists {
-                if list.name == list_name {
-                    return list;
-                }
+            let mut lists: Vec<TodoList> = TodoList::get_all(pool)
+                .await
+                .map_err(JudoError::Db)?
+                .into_iter()
+                .filter(|list| list.name == list_name)
+                .collect();
+            let what = format!("List with name '{}' in database '{}'", list_name, db.name);
+
+            match lists.len() {
+                0 => Err(JudoError::NotFound(what)),
+                1 => Ok(lists.remove(0)),
+                n => Err(JudoError::AmbiguousName(
+                    what,
+                    format!("{} lists have that name, select one by ID instead", n),
+                )),
             }
-            eprintln!("Error: List with name '{}' not found in database '{}'", list_name, db.name);
-            std::process::exit(exitcode::DATAERR)
-        }
-        (Some(_), Some(_)) => {
-            eprintln!("Please provide either the name or the ID of the list, not both");
-            std::process::exit(exitcode::DATAERR);
-        }
-        (None, None) => {
-            eprintln!("Please provide either the name or the ID of the list");
-            std::process::exit(exitcode::DATAERR);
         }
+        (Some(_), Some(_)) => Err(JudoError::InvalidInput(
+            "Please provide either the name or the ID of the list, not both".to_string(),
+        )),
+        (None, None) => Err(JudoError::InvalidInput(
+            "Please provide either the name or the ID of the list".to_string(),
+        )),
     }
 }
 
-async fn get_db_pool_from_option(app: &App, db_option: &Option<String>) -> Pool<Sqlite> {
-    let target_db = get_db_from_option(app, db_option);
-    return get_db_pool(target_db.connection_str.as_str()).await.unwrap();
+// Returns the specified DB (or the default if omitted) with a connection to it
+async fn get_db_and_pool_from_option(
+    app: &App,
+    db_option: &Option<String>,
+) -> Result<(DBConfig, Pool<Sqlite>)> {
+    let target_db = get_db_from_option(&app.config, db_option)?;
+    let pool = open_db(target_db.connection_str.as_str(), app.read_only)
+        .await
+        .map_err(JudoError::Db)?;
+    Ok((target_db, pool))
 }
 
 // Returns the specified DB or the default if omitted
-fn get_db_from_option(app: &App, db: &Option<String>) -> DBConfig {
-    return match db {
-        Some(name) => app.config.clone().get_db_by_name((name).to_string()).unwrap(),
-        None => app.config.get_default().unwrap()
-    };
-}
\ No newline at end of file
+fn get_db_from_option(config: &Config, db: &Option<String>) -> Result<DBConfig> {
+    match db {
+        Some(name) => config.get_db_by_name(name),
+        None => config.get_default(),
+    }
+}
@@ -0,0 +1,376 @@
+use crate::db::backup::Backup;
+use crate::db::config::ViewConfig;
+use crate::db::models::{TodoItem, TodoList};
+use crate::error::{JudoError, Result};
+use chrono::Local;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use tabwriter::TabWriter;
+
+/// Output format of the show commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for humans
+    #[default]
+    Table,
+    /// A single JSON array of records
+    Json,
+    /// One JSON record per line
+    Jsonl,
+    /// Comma separated values with a header row
+    Csv,
+    /// Tab separated values with a header row
+    Tsv,
+    /// GitHub-flavored Markdown checklist (export and import only)
+    Markdown,
+    /// todo.txt, one task per line (export and import only)
+    Todotxt,
+    /// iCalendar to-dos (export and import only)
+    Ical,
+    /// Taskwarrior `task export` JSON (import only)
+    Taskwarrior,
+}
+
+/// A row printed by a show command.
+///
+/// The human readable table only shows a few columns, the machine readable
+/// formats include every field of `FIELDS`, in that order.
+pub trait Record: Serialize {
+    /// Fields of the machine readable output, in order
+    const FIELDS: &'static [&'static str];
+    /// Column names of the table
+    const TABLE_HEADER: &'static [&'static str];
+
+    /// Cells of the table, one per column of `TABLE_HEADER`
+    fn table_row(&self) -> Vec<String>;
+}
+
+/// A database of judo.toml (without its CalDAV settings, which hold a password)
+#[derive(Debug, Serialize)]
+pub struct DbRecord<'a> {
+    pub name: &'a str,
+    pub connection_str: &'a str,
+    /// Whether this is the default database
+    pub default: bool,
+}
+
+impl Record for DbRecord<'_> {
+    const FIELDS: &'static [&'static str] = &["name", "connection_str", "default"];
+    const TABLE_HEADER: &'static [&'static str] = &["Name", "Connection string"];
+
+    fn table_row(&self) -> Vec<String> {
+        vec![self.name.to_string(), self.connection_str.to_string()]
+    }
+}
+
+/// A view of judo.toml
+#[derive(Debug, Serialize)]
+pub struct ViewRecord<'a> {
+    #[serde(flatten)]
+    pub view: &'a ViewConfig,
+}
+
+impl Record for ViewRecord<'_> {
+    const FIELDS: &'static [&'static str] = &["name", "query", "dbs"];
+    const TABLE_HEADER: &'static [&'static str] = &["Name", "Query", "Databases"];
+
+    fn table_row(&self) -> Vec<String> {
+        let dbs = if self.view.dbs.is_empty() {
+            "all".to_string()
+        } else {
+            self.view.dbs.join(", ")
+        };
+        vec![self.view.name.clone(), self.view.query.clone(), dbs]
+    }
+}
+
+/// A backup with the database it was taken of
+#[derive(Debug, Serialize)]
+pub struct BackupRecord<'a> {
+    pub db: &'a str,
+    pub name: String,
+    #[serde(flatten)]
+    pub backup: &'a Backup,
+}
+
+impl Record for BackupRecord<'_> {
+    const FIELDS: &'static [&'static str] = &["db", "name", "created_at", "reason", "path"];
+    const TABLE_HEADER: &'static [&'static str] = &["Name", "DB", "Taken at", "Reason"];
+
+    fn table_row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.db.to_string(),
+            self.backup
+                .created_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            self.backup.reason.name().to_string(),
+        ]
+    }
+}
+
+/// A todo list with the database it belongs to
+#[derive(Debug, Serialize)]
+pub struct ListRecord<'a> {
+    pub db: &'a str,
+    #[serde(flatten)]
+    pub list: TodoList,
+    /// Number of items in the list
+    pub item_count: usize,
+}
+
+impl Record for ListRecord<'_> {
+    const FIELDS: &'static [&'static str] = &[
+        "db",
+        "id",
+        "name",
+        "ordering",
+        "created_at",
+        "updated_at",
+        "item_count",
+    ];
+    const TABLE_HEADER: &'static [&'static str] = &["Name", "ID", "DB", "No of items"];
+
+    fn table_row(&self) -> Vec<String> {
+        vec![
+            self.list.name.clone(),
+            self.list.id.to_string(),
+            self.db.to_string(),
+            self.item_count.to_string(),
+        ]
+    }
+}
+
+/// A todo item with the list and database it belongs to
+#[derive(Debug, Serialize)]
+pub struct ItemRecord<'a> {
+    pub db: &'a str,
+    pub list_name: String,
+    #[serde(flatten)]
+    pub item: TodoItem,
+}
+
+impl Record for ItemRecord<'_> {
+    const FIELDS: &'static [&'static str] = &[
+        "db",
+        "list_name",
+        "id",
+        "list_id",
+        "name",
+        "is_done",
+        "priority",
+        "due_date",
+        "ordering",
+        "created_at",
+        "updated_at",
+    ];
+    const TABLE_HEADER: &'static [&'static str] =
+        &["Name", "ID", "List name", "List ID", "DB", "Done?"];
+
+    fn table_row(&self) -> Vec<String> {
+        vec![
+            self.item.name.clone(),
+            self.item.id.to_string(),
+            self.list_name.clone(),
+            self.item.list_id.to_string(),
+            self.db.to_string(),
+            self.item.is_done.to_string(),
+        ]
+    }
+}
+
+/// Print the records to stdout in the given format
+pub fn print_records<R: Record>(format: OutputFormat, records: &[R]) -> Result<()> {
+    write_records(format, records, std::io::stdout().lock())
+}
+
+/// Write the records in the given format
+pub fn write_records<R: Record, W: Write>(
+    format: OutputFormat,
+    records: &[R],
+    mut out: W,
+) -> Result<()> {
+    match format {
+        OutputFormat::Table => write_table(records, out).map_err(JudoError::io("stdout")),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, records).map_err(json_error)?;
+            writeln!(out).map_err(JudoError::io("stdout"))
+        }
+        OutputFormat::Jsonl => {
+            for record in records {
+                serde_json::to_writer(&mut out, record).map_err(json_error)?;
+                writeln!(out).map_err(JudoError::io("stdout"))?;
+            }
+            Ok(())
+        }
+        OutputFormat::Csv => write_delimited(records, out, b','),
+        OutputFormat::Tsv => write_delimited(records, out, b'\t'),
+        OutputFormat::Markdown
+        | OutputFormat::Todotxt
+        | OutputFormat::Ical
+        | OutputFormat::Taskwarrior => Err(JudoError::InvalidInput(
+            "This format is only supported by export and import".to_string(),
+        )),
+    }
+}
+
+// Aligned columns under an underlined header
+fn write_table<R: Record, W: Write>(records: &[R], out: W) -> std::io::Result<()> {
+    let mut tw = TabWriter::new(out);
+    let underline: Vec<String> = R::TABLE_HEADER
+        .iter()
+        .map(|h| "-".repeat(h.len()))
+        .collect();
+
+    writeln!(tw, "{}", R::TABLE_HEADER.join("\t"))?;
+    writeln!(tw, "{}", underline.join("\t"))?;
+    for record in records {
+        writeln!(tw, "{}", record.table_row().join("\t"))?;
+    }
+    tw.flush()
+}
+
+// Header row of field names, then one row per record
+fn write_delimited<R: Record, W: Write>(records: &[R], out: W, delimiter: u8) -> Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(out);
+
+    writer.write_record(R::FIELDS).map_err(csv_error)?;
+    for record in records {
+        let value = serde_json::to_value(record).map_err(json_error)?;
+        let row = R::FIELDS.iter().map(|field| cell(&value[field]));
+        writer.write_record(row).map_err(csv_error)?;
+    }
+    writer.flush().map_err(JudoError::io("stdout"))
+}
+
+// Text of a field in a CSV cell, empty for missing values and comma separated for lists
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(cell).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+fn json_error(e: serde_json::Error) -> JudoError {
+    JudoError::io("stdout")(e.into())
+}
+
+fn csv_error(e: csv::Error) -> JudoError {
+    JudoError::io("stdout")(e.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::models::Priority;
+    use chrono::{TimeZone, Utc};
+
+    fn item() -> ItemRecord<'static> {
+        let time = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+        ItemRecord {
+            db: "dojo",
+            list_name: "Chores, weekly".to_string(),
+            item: TodoItem {
+                id: 3,
+                list_id: 1,
+                name: "Laundry".to_string(),
+                is_done: false,
+                priority: Some(Priority::High),
+                due_date: None,
+                ordering: 2,
+                created_at: time,
+                updated_at: time,
+            },
+        }
+    }
+
+    fn output(format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        write_records(format, &[item()], &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_fields_match_serialized_keys() {
+        let value = serde_json::to_value(item()).unwrap();
+        let keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let mut fields = ItemRecord::FIELDS.to_vec();
+        fields.sort();
+        assert_eq!(keys, fields);
+
+        let time = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+        let list = ListRecord {
+            db: "dojo",
+            list: TodoList {
+                id: 1,
+                name: "Chores".to_string(),
+                ordering: 1,
+                created_at: time,
+                updated_at: time,
+            },
+            item_count: 4,
+        };
+        let value = serde_json::to_value(list).unwrap();
+        let keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let mut fields = ListRecord::FIELDS.to_vec();
+        fields.sort();
+        assert_eq!(keys, fields);
+    }
+
+    #[test]
+    fn test_csv_and_tsv() {
+        assert_eq!(
+            output(OutputFormat::Csv),
+            "db,list_name,id,list_id,name,is_done,priority,due_date,ordering,created_at,updated_at\n\
+             dojo,\"Chores, weekly\",3,1,Laundry,false,high,,2,2025-01-02T03:04:05Z,2025-01-02T03:04:05Z\n"
+        );
+        assert!(
+            output(OutputFormat::Tsv)
+                .lines()
+                .nth(1)
+                .unwrap()
+                .starts_with("dojo\tChores, weekly\t3\t")
+        );
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let out = output(OutputFormat::Jsonl);
+        assert_eq!(out.lines().count(), 1);
+        let value: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(value["priority"], "high");
+        assert_eq!(value["due_date"], Value::Null);
+        assert_eq!(value["list_name"], "Chores, weekly");
+
+        let value: Value = serde_json::from_str(&output(OutputFormat::Json)).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            output(OutputFormat::Table),
+            "Name     ID  List name       List ID  DB    Done?\n\
+             ----     --  ---------       -------  --    -----\n\
+             Laundry  3   Chores, weekly  1        dojo  false\n"
+        );
+    }
+}
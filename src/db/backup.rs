@@ -0,0 +1,304 @@
+//! Copies of the database files, to go back to after a bad migration or an
+//! accidental delete.
+//!
+//! Backups are written with `VACUUM INTO`, which makes a consistent copy of a
+//! database while others keep using it, to the `backups/<file name>` folder
+//! next to the database file (`backups/judo` for `judo.db`). Their names tell
+//! when and why they were taken, e.g. `judo-20261018T093000.123Z-scheduled.db`.
+//! A backup is taken before migrating a database, at most every
+//! `interval_hours` (see [`BackupConfig`]) when judo starts, before restoring
+//! another backup or repairing the database, and with `judo backup create`.
+//! Only the latest `keep` backups of a database are kept.
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::instrument;
+
+use crate::db::connections::delete_db_sidecars;
+
+const DEFAULT_INTERVAL_HOURS: u64 = 24;
+const DEFAULT_KEEP: usize = 7;
+const BACKUPS_DIR: &str = "backups";
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// `[backup]` section of judo.toml
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Hours between scheduled backups, 0 to only take them on demand
+    pub interval_hours: u64,
+    /// Number of backups kept for every database before the oldest is deleted,
+    /// at least 1
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            interval_hours: DEFAULT_INTERVAL_HOURS,
+            keep: DEFAULT_KEEP,
+        }
+    }
+}
+
+impl BackupConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Why a backup was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupReason {
+    /// With `judo backup create`
+    Manual,
+    /// Because the latest backup was older than the interval
+    Scheduled,
+    /// Before migrating the database to a new version of judo
+    Migration,
+    /// Before restoring another backup
+    Restore,
+    /// Before repairing the database with `judo dbs doctor --fix`
+    Repair,
+}
+
+impl BackupReason {
+    const ALL: [BackupReason; 5] = [
+        BackupReason::Manual,
+        BackupReason::Scheduled,
+        BackupReason::Migration,
+        BackupReason::Restore,
+        BackupReason::Repair,
+    ];
+
+    /// Name of the reason, as in the file names of backups
+    pub fn name(self) -> &'static str {
+        match self {
+            BackupReason::Manual => "manual",
+            BackupReason::Scheduled => "scheduled",
+            BackupReason::Migration => "migration",
+            BackupReason::Restore => "restore",
+            BackupReason::Repair => "repair",
+        }
+    }
+}
+
+/// A backup file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub reason: BackupReason,
+}
+
+impl Backup {
+    /// File name of the backup, which identifies it
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    // Read the time and reason back from a file name written by `create`
+    fn parse(path: PathBuf, stem: &str) -> Option<Backup> {
+        let name = path.file_name()?.to_str()?;
+        let rest = name.strip_prefix(stem)?.strip_prefix('-')?;
+        let (time, reason) = rest.strip_suffix(".db")?.rsplit_once('-')?;
+        let created_at = NaiveDateTime::parse_from_str(time, TIME_FORMAT)
+            .ok()?
+            .and_utc();
+        let reason = BackupReason::ALL
+            .into_iter()
+            .find(|known| known.name() == reason)?;
+        Some(Backup {
+            path,
+            created_at,
+            reason,
+        })
+    }
+}
+
+/// Folder of the backups of a database file
+pub fn backup_dir(db_path: &Path) -> PathBuf {
+    let dir = db_path.parent().unwrap_or(Path::new("."));
+    dir.join(BACKUPS_DIR).join(file_stem(db_path))
+}
+
+/// Take a backup of the database open in `pool`, whose file is `db_path`
+#[instrument(skip(pool), err)]
+pub async fn create(pool: &SqlitePool, db_path: &Path, reason: BackupReason) -> Result<Backup> {
+    let dir = backup_dir(db_path);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create backup directory {}", dir.display()))?;
+
+    // VACUUM INTO refuses to overwrite, so a name taken in the same millisecond
+    // moves on to the next one
+    let mut created_at = Utc::now();
+    let path = loop {
+        let name = format!(
+            "{}-{}-{}.db",
+            file_stem(db_path),
+            created_at.format(TIME_FORMAT),
+            reason.name()
+        );
+        let path = dir.join(name);
+        if !path.exists() {
+            break path;
+        }
+        created_at += chrono::Duration::milliseconds(1);
+    };
+
+    sqlx::query("VACUUM INTO ?1")
+        .bind(path.to_string_lossy().into_owned())
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to back the database up to {}", path.display()))?;
+
+    // Read back from the name, to drop what it doesn't keep (nanoseconds)
+    Backup::parse(path, &file_stem(db_path)).with_context(|| "Failed to name the backup")
+}
+
+/// Backups of a database file, oldest first
+pub fn list(db_path: &Path) -> Result<Vec<Backup>> {
+    let dir = backup_dir(db_path);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", dir.display()));
+        }
+    };
+
+    let stem = file_stem(db_path);
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| Backup::parse(entry.path(), &stem))
+        .collect();
+    backups.sort_by_key(|backup| backup.created_at);
+    Ok(backups)
+}
+
+/// Delete the oldest backups of a database file beyond the latest `keep`,
+/// returning those deleted. The latest backup is always kept.
+#[instrument(err)]
+pub fn prune(db_path: &Path, keep: usize) -> Result<Vec<Backup>> {
+    let mut backups = list(db_path)?;
+    let excess = backups.len().saturating_sub(keep.max(1));
+    let removed: Vec<Backup> = backups.drain(..excess).collect();
+
+    for backup in &removed {
+        fs::remove_file(&backup.path)
+            .with_context(|| format!("Failed to delete backup {}", backup.path.display()))?;
+    }
+    Ok(removed)
+}
+
+/// Whether a scheduled backup of the database file is due
+pub fn is_due(db_path: &Path, config: &BackupConfig) -> Result<bool> {
+    if config.interval_hours == 0 || !db_path.exists() {
+        return Ok(false);
+    }
+    let interval = chrono::Duration::hours(config.interval_hours.min(i64::MAX as u64) as i64);
+
+    Ok(match list(db_path)?.last() {
+        Some(latest) => Utc::now() - latest.created_at >= interval,
+        None => true,
+    })
+}
+
+/// Take a scheduled backup of the database if one is due, then delete the
+/// backups beyond those kept
+#[instrument(skip(pool), err)]
+pub async fn run_schedule(
+    pool: &SqlitePool,
+    db_path: &Path,
+    config: &BackupConfig,
+) -> Result<Option<Backup>> {
+    if !is_due(db_path, config)? {
+        return Ok(None);
+    }
+    let backup = create(pool, db_path, BackupReason::Scheduled).await?;
+    prune(db_path, config.keep)?;
+    Ok(Some(backup))
+}
+
+/// Replace the database file with a backup. The pool is closed first, and other
+/// connections to the database must be closed too.
+#[instrument(skip(pool), err)]
+pub async fn restore(pool: SqlitePool, db_path: &Path, backup: &Backup) -> Result<()> {
+    if !backup.path.is_file() {
+        bail!("Backup {} not found", backup.path.display());
+    }
+
+    // Copy next to the database first, on the same file system as it
+    let mut staged = db_path.as_os_str().to_owned();
+    staged.push(".restoring");
+    let staged = PathBuf::from(staged);
+    fs::copy(&backup.path, &staged)
+        .with_context(|| format!("Failed to copy backup {}", backup.path.display()))?;
+
+    // Nothing of the current database may be left in its write-ahead log
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(&pool)
+        .await
+        .with_context(|| "Failed to checkpoint the database")?;
+    pool.close().await;
+
+    // Renaming over the database replaces it at once, so that a failure leaves
+    // either the old or the new one
+    fs::rename(&staged, db_path)
+        .with_context(|| format!("Failed to move the backup to {}", db_path.display()))?;
+    delete_db_sidecars(db_path)
+        .with_context(|| format!("Failed to delete the log of {}", db_path.display()))?;
+    Ok(())
+}
+
+fn file_stem(db_path: &Path) -> String {
+    db_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "db".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_name() {
+        let dir = Path::new("/data/backups/work");
+        let backup = Backup::parse(
+            dir.join("my-work-20261018T093000.123Z-migration.db"),
+            "my-work",
+        )
+        .unwrap();
+        assert_eq!(backup.reason, BackupReason::Migration);
+        assert_eq!(
+            backup.created_at.format(TIME_FORMAT).to_string(),
+            "20261018T093000.123Z"
+        );
+        assert_eq!(backup.name(), "my-work-20261018T093000.123Z-migration.db");
+
+        for name in [
+            "my-work-20261018T093000.123Z-lunch.db",
+            "my-work-yesterday-manual.db",
+            "other-20261018T093000.123Z-manual.db",
+            "my-work-20261018T093000.123Z-manual.db-wal",
+        ] {
+            assert_eq!(Backup::parse(dir.join(name), "my-work"), None);
+        }
+    }
+
+    #[test]
+    fn test_backup_dir() {
+        assert_eq!(
+            backup_dir(Path::new("/data/judo/work.db")),
+            Path::new("/data/judo/backups/work")
+        );
+    }
+}
@@ -1,7 +1,12 @@
-use anyhow::{Context, Result};
+use crate::db::backup::BackupConfig;
+use crate::db::query::Expr;
+use crate::error::{JudoError, Result};
+use crate::keymap::{KeyBinding, KeyMap};
+use crate::logging::LoggingConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // Default variables
 const DEFAULT_DB_NAME: &str = "dojo";
@@ -18,13 +23,56 @@ pub struct Config {
     pub dbs: Vec<DBConfig>,
     #[serde(default)]
     pub colours: Theme,
+    /// Preset of default key bindings ("default" or "vim"), the default one if
+    /// unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keymap: Option<String>,
+    /// Key binding overrides, by action name (both checked when reading judo.toml)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, KeyBinding>,
+    /// Log level and retention of the log files
+    #[serde(default, skip_serializing_if = "LoggingConfig::is_default")]
+    pub logging: LoggingConfig,
+    /// Schedule and retention of the database backups
+    #[serde(default, skip_serializing_if = "BackupConfig::is_default")]
+    pub backup: BackupConfig,
+    /// Saved filters shown as virtual lists
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub views: Vec<ViewConfig>,
 }
 
 /// Database configuration
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DBConfig {
     pub name: String,
     pub connection_str: String,
+    /// CalDAV collection synced by `judo sync caldav`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caldav: Option<CalDavConfig>,
+}
+
+/// `[dbs.caldav]` section of a database in judo.toml
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct CalDavConfig {
+    /// URL of the calendar collection holding the to-dos
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Password of the user, if not given by the JUDO_CALDAV_PASSWORD
+    /// environment variable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+/// A saved filter, shown as a virtual list of the matching items
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ViewConfig {
+    pub name: String,
+    /// Query the items have to match
+    pub query: String,
+    /// Databases the items are taken from, all of them if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dbs: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -44,13 +92,16 @@ impl Default for Theme {
     }
 }
 
-impl Default for DBConfig {
-    fn default() -> Self {
+impl Config {
+    /// Config of a first run, with the default database in the data directory
+    pub fn initial() -> Result<Self> {
         // Use data directory to standardize storage
-        let data_dir = dirs::data_dir().unwrap().join("judo");
+        let data_dir = dirs::data_dir()
+            .ok_or_else(|| JudoError::ConfigInvalid("Could not find data directory".to_string()))?
+            .join("judo");
 
         // Create directory
-        std::fs::create_dir_all(&data_dir).unwrap();
+        fs::create_dir_all(&data_dir).map_err(JudoError::io(&data_dir))?;
 
         // Create path to db
         let path = data_dir.join(DEFAULT_DB_FILE);
@@ -58,73 +109,89 @@ impl Default for DBConfig {
         // Create connection string (only SQLite is admissible)
         let connection_str = format!("sqlite:{}", path.display());
 
-        Self {
-            name: DEFAULT_DB_NAME.to_string(),
-            connection_str,
-        }
-    }
-}
-
-impl Default for Config {
-    /// By default, the name is the default name with default config
-    fn default() -> Self {
-        Self {
+        Ok(Self {
             default: DEFAULT_DB_NAME.to_string(),
-            dbs: vec![DBConfig::default()],
+            dbs: vec![DBConfig {
+                name: DEFAULT_DB_NAME.to_string(),
+                connection_str,
+                caldav: None,
+            }],
             colours: Theme::default(),
-        }
+            keymap: None,
+            keys: BTreeMap::new(),
+            logging: LoggingConfig::default(),
+            backup: BackupConfig::default(),
+            views: Vec::new(),
+        })
+    }
+
+    /// Path of the judo.toml file
+    pub fn path() -> Result<PathBuf> {
+        // Use config directory to standardize storage of config file
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| JudoError::ConfigInvalid("Could not find config directory".to_string()))?
+            .join("judo");
+
+        Ok(config_dir.join("judo.toml"))
     }
-}
 
-impl Config {
     /// Write config struct to judo.toml file
-    pub fn write(&self, config_path: &PathBuf) -> Result<()> {
+    pub fn write(&self, config_path: &Path) -> Result<()> {
         // Convert config to string to be written to config file
-        let toml_content =
-            toml::to_string_pretty(&self).with_context(|| "Failed to serialize judo.toml")?;
+        let toml_content = toml::to_string_pretty(&self).map_err(|e| {
+            JudoError::ConfigInvalid(format!("Failed to serialize judo.toml: {}", e))
+        })?;
 
         // Write string to file
-        fs::write(config_path, toml_content).with_context(|| {
-            format!(
-                "Failed to write yomo.toml file to {}",
-                config_path.display()
-            )
-        })?;
+        fs::write(config_path, toml_content).map_err(JudoError::io(config_path))?;
 
         Ok(())
     }
 
+    /// Write config struct to the judo.toml file it was read from
+    pub fn save(&self) -> Result<()> {
+        self.write(&Self::path()?)
+    }
+
     /// Read and serialize a judo.toml file
     pub fn read() -> Result<Self> {
-        // Use config directory to standardize storage of config file
-        let config_dir = dirs::config_dir().unwrap().join("judo");
-
         // Define the config file path
-        let config_path = config_dir.join("judo.toml");
+        let config_path = Self::path()?;
 
         // Create config if not existing
-        if !config_dir.exists() | !config_path.exists() {
+        if !config_path.exists() {
             // Create directory
-            std::fs::create_dir_all(&config_dir)
-                .with_context(|| "Failed to create config directory")?;
+            if let Some(config_dir) = config_path.parent() {
+                std::fs::create_dir_all(config_dir).map_err(JudoError::io(config_dir))?;
+            }
 
             // Create default config
-            let config = Self::default();
+            let config = Self::initial()?;
 
             // Create config file
-            config
-                .write(&config_path)
-                .with_context(|| "Failed to create config file")?;
+            config.write(&config_path)?;
 
-            // Create default config
-            return Ok(Self::default());
+            return Ok(config);
         }
 
-        // Serialize judo.toml into YomoProject struct
-        let judo_config: Config = toml::from_str(
-            &fs::read_to_string(config_path).with_context(|| "Failed to read into string")?,
-        )
-        .with_context(|| "Failed to serialize into struct")?;
+        // Serialize judo.toml into Config struct
+        let content = fs::read_to_string(&config_path).map_err(JudoError::io(&config_path))?;
+        let judo_config: Config = toml::from_str(&content)
+            .map_err(|e| JudoError::ConfigInvalid(format!("{}: {}", config_path.display(), e)))?;
+
+        // Catch an invalid log level before the logger needs it
+        judo_config
+            .logging
+            .filter()
+            .map_err(|e| JudoError::ConfigInvalid(format!("{:#}", e)))?;
+
+        // Catch unknown actions and keys, and conflicting key bindings
+        judo_config.key_map()?;
+
+        // Catch views with an invalid query or unknown databases
+        for view in &judo_config.views {
+            judo_config.check_view(view)?;
+        }
 
         Ok(judo_config)
     }
@@ -141,30 +208,256 @@ impl Config {
         &self.colours.background
     }
 
-    pub fn get_db_by_name(self, name: String) -> Result<DBConfig> {
-        let dbs = self.dbs;
-        for db in dbs {
-            if db.name == name {
-                return Ok(db);
+    /// Get config of the database with the given name
+    pub fn get_db_by_name(&self, name: &str) -> Result<DBConfig> {
+        self.find_db(name, format!("Database '{}'", name))
+    }
+
+    /// Get config of default database
+    pub fn get_default(&self) -> Result<DBConfig> {
+        self.find_db(
+            &self.default,
+            format!("Default database '{}'", self.default),
+        )
+    }
+
+    /// Make the database with the given name the default
+    pub fn set_default_db(&mut self, name: &str) -> Result<()> {
+        self.get_db_by_name(name)?;
+        self.default = name.to_string();
+        Ok(())
+    }
+
+    /// Rename a database, along with the references to it of the default and views.
+    /// The database file keeps its name.
+    pub fn rename_db(&mut self, name: &str, new_name: &str) -> Result<()> {
+        self.get_db_by_name(name)?;
+        if new_name.trim().is_empty() {
+            return Err(JudoError::InvalidInput(
+                "The name of a database can't be empty".to_string(),
+            ));
+        }
+        if self.dbs.iter().any(|db| db.name == new_name) {
+            return Err(JudoError::InvalidInput(format!(
+                "A database named '{}' already exists",
+                new_name
+            )));
+        }
+
+        for db in self.dbs.iter_mut().filter(|db| db.name == name) {
+            db.name = new_name.to_string();
+        }
+        if self.default == name {
+            self.default = new_name.to_string();
+        }
+        for db_name in self.views.iter_mut().flat_map(|view| view.dbs.iter_mut()) {
+            if db_name == name {
+                *db_name = new_name.to_string();
             }
         }
+        Ok(())
+    }
+
+    /// Remove a database from the config (its file is left alone), unless it's
+    /// the default or a view takes items from it
+    pub fn remove_db(&mut self, name: &str) -> Result<DBConfig> {
+        let db = self.get_db_by_name(name)?;
+        if self.default == name {
+            return Err(JudoError::InvalidInput(format!(
+                "'{}' is the default database, make another database the default first",
+                name
+            )));
+        }
+        if let Some(view) = self
+            .views
+            .iter()
+            .find(|view| view.dbs.iter().any(|db_name| db_name == name))
+        {
+            return Err(JudoError::InvalidInput(format!(
+                "View '{}' takes items from '{}', remove the database from the view first",
+                view.name, name
+            )));
+        }
 
-        eprintln!("ERROR: No database found called '{}'", name);
-        std::process::exit(exitcode::DATAERR)
+        self.dbs.retain(|db| db.name != name);
+        Ok(db)
     }
 
-    /// Get config of default database
-    pub fn get_default(&self) -> Result<DBConfig> {
-        let matching_dbs: Vec<_> = self
-            .dbs
+    /// Get the saved view with the given name
+    pub fn get_view_by_name(&self, name: &str) -> Result<&ViewConfig> {
+        self.views
             .iter()
-            .filter(|db| db.name == self.default)
-            .collect();
+            .find(|view| view.name == name)
+            .ok_or_else(|| JudoError::NotFound(format!("View '{}'", name)))
+    }
+
+    /// Databases the items of a view are taken from
+    pub fn view_dbs(&self, view: &ViewConfig) -> Result<Vec<DBConfig>> {
+        if view.dbs.is_empty() {
+            return Ok(self.dbs.clone());
+        }
+        view.dbs
+            .iter()
+            .map(|name| self.get_db_by_name(name))
+            .collect()
+    }
+
+    /// Key bindings of the `keymap` preset with the [keys] overrides applied
+    pub fn key_map(&self) -> Result<KeyMap> {
+        KeyMap::from_config(self.keymap.as_deref(), &self.keys)
+            .map_err(|e| JudoError::ConfigInvalid(format!("{:#}", e)))
+    }
+
+    /// Check that a view has a valid query and only names known databases
+    pub fn check_view(&self, view: &ViewConfig) -> Result<()> {
+        let invalid = |reason: String| {
+            JudoError::ConfigInvalid(format!("View '{}' in judo.toml: {}", view.name, reason))
+        };
+
+        Expr::parse(&view.query).map_err(|e| invalid(format!("invalid query, {}", e)))?;
+        self.view_dbs(view).map_err(|e| invalid(e.to_string()))?;
+        Ok(())
+    }
+
+    /// The only database with the given name, `what` describing it in errors
+    fn find_db(&self, name: &str, what: String) -> Result<DBConfig> {
+        let matching_dbs: Vec<_> = self.dbs.iter().filter(|db| db.name == name).collect();
 
         match matching_dbs.len() {
-            0 => anyhow::bail!("Default database '{}' not found", self.default),
+            0 => Err(JudoError::NotFound(what)),
             1 => Ok(matching_dbs[0].clone()),
-            _ => anyhow::bail!("Multiple databases with name '{}' found", self.default),
+            _ => Err(JudoError::AmbiguousName(
+                what,
+                "rename one of the databases in judo.toml".to_string(),
+            )),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config() -> Config {
+        let db = |name: &str| DBConfig {
+            name: name.to_string(),
+            connection_str: format!("sqlite:{}.db", name),
+            caldav: None,
+        };
+        Config {
+            default: "work".to_string(),
+            dbs: vec![db("work"), db("home"), db("home")],
+            colours: Theme::default(),
+            keymap: None,
+            keys: BTreeMap::new(),
+            logging: LoggingConfig::default(),
+            backup: BackupConfig::default(),
+            views: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_get_db_by_name() {
+        let config = config();
+        assert_eq!(config.get_default().unwrap().name, "work");
+        assert!(matches!(
+            config.get_db_by_name("nope"),
+            Err(JudoError::NotFound(_))
+        ));
+        assert!(matches!(
+            config.get_db_by_name("home"),
+            Err(JudoError::AmbiguousName(..))
+        ));
+    }
+
+    #[test]
+    fn test_rename_db() {
+        let mut config = config();
+        config.views.push(ViewConfig {
+            name: "jobs".to_string(),
+            query: "open".to_string(),
+            dbs: vec!["work".to_string()],
+        });
+
+        config.rename_db("work", "office").unwrap();
+        assert_eq!(config.dbs[0].name, "office");
+        assert_eq!(config.dbs[0].connection_str, "sqlite:work.db");
+        assert_eq!(config.default, "office");
+        assert_eq!(config.views[0].dbs, vec!["office"]);
+
+        assert!(matches!(
+            config.rename_db("office", "home"),
+            Err(JudoError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            config.rename_db("nope", "other"),
+            Err(JudoError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_remove_db() {
+        let mut config = config();
+        config.dbs.pop();
+        config.views.push(ViewConfig {
+            name: "chores".to_string(),
+            query: "open".to_string(),
+            dbs: vec!["home".to_string()],
+        });
+
+        // Neither the default nor a database of a view can be removed
+        assert!(matches!(
+            config.remove_db("work"),
+            Err(JudoError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            config.remove_db("home"),
+            Err(JudoError::InvalidInput(_))
+        ));
+
+        config.views.clear();
+        assert_eq!(config.remove_db("home").unwrap().name, "home");
+        assert_eq!(config.dbs.len(), 1);
+
+        config.set_default_db("work").unwrap();
+        assert!(config.set_default_db("home").is_err());
+    }
+
+    #[test]
+    fn test_check_view() {
+        let config = config();
+        let view = |query: &str, dbs: &[&str]| ViewConfig {
+            name: "urgent".to_string(),
+            query: query.to_string(),
+            dbs: dbs.iter().map(|db| db.to_string()).collect(),
+        };
+
+        assert!(config.check_view(&view("priority:high open", &[])).is_ok());
+        assert_eq!(config.view_dbs(&view("done", &[])).unwrap().len(), 3);
+        assert_eq!(
+            config.view_dbs(&view("done", &["work"])).unwrap()[0].name,
+            "work"
+        );
+        assert!(matches!(
+            config.check_view(&view("priority:urgent", &[])),
+            Err(JudoError::ConfigInvalid(_))
+        ));
+        assert!(matches!(
+            config.check_view(&view("done", &["nope"])),
+            Err(JudoError::ConfigInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_map() {
+        let mut config = config();
+        assert!(config.key_map().is_ok());
+
+        config
+            .keys
+            .insert("quit".to_string(), KeyBinding::One("hyper+x".to_string()));
+        let err = config.key_map().unwrap_err();
+        assert!(matches!(err, JudoError::ConfigInvalid(_)));
+        assert!(err.to_string().contains("Invalid [keys] section"), "{err}");
+    }
+}
@@ -1,14 +1,32 @@
-use anyhow::{Context, Result};
+use crate::db::backup::{self, BackupReason};
+use anyhow::{Context, Result, bail};
 use sqlx::migrate::Migrator;
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
+use tracing::instrument;
+
+/// How long a connection waits on a locked database before giving up with SQLITE_BUSY
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Migration files embedded into the binary
+static MIGRATOR: Migrator = sqlx::migrate!();
 
 /// Create connection to SQLite DB pool and create DB if not present
+///
+/// The database is opened in WAL mode with a busy timeout so that the TUI and
+/// CLI invocations can read and write the same file concurrently.
 pub async fn get_db_pool(db_connection_str: &str) -> Result<SqlitePool> {
     // Create connection options
     let opts = SqliteConnectOptions::from_str(db_connection_str)
         .with_context(|| "Failed to create options for DB")?
-        .create_if_missing(true);
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(BUSY_TIMEOUT)
+        .foreign_keys(true);
 
     // Connect in a pool
     let pool = SqlitePool::connect_with(opts)
@@ -20,9 +38,6 @@ pub async fn get_db_pool(db_connection_str: &str) -> Result<SqlitePool> {
 
 /// Run database migrations
 async fn run_migrations(pool: &SqlitePool) -> Result<()> {
-    // Embed the migration files into binary
-    static MIGRATOR: Migrator = sqlx::migrate!();
-
     MIGRATOR
         .run(pool)
         .await
@@ -31,17 +46,213 @@ async fn run_migrations(pool: &SqlitePool) -> Result<()> {
     Ok(())
 }
 
+/// Open a database without writing to it: no migrations, and no new file if
+/// there is none
+///
+/// This is how databases set up by a newer version of judo, which this one
+/// must not migrate, can still be read.
+pub async fn get_read_only_db_pool(db_connection_str: &str) -> Result<SqlitePool> {
+    let opts = SqliteConnectOptions::from_str(db_connection_str)
+        .with_context(|| "Failed to create options for DB")?
+        .read_only(true)
+        .busy_timeout(BUSY_TIMEOUT)
+        .foreign_keys(true);
+
+    SqlitePool::connect_with(opts)
+        .await
+        .with_context(|| "Failed to create DB pool")
+}
+
+/// Open a database without migrating it, read-only or refusing one set up by
+/// a newer version of judo
+pub async fn open_db(connection_str: &str, read_only: bool) -> Result<SqlitePool> {
+    if read_only {
+        return get_read_only_db_pool(connection_str).await;
+    }
+
+    let pool = get_db_pool(connection_str).await?;
+    if let Err(e) = migration_status(&pool).await?.ensure_known(connection_str) {
+        pool.close().await;
+        return Err(e);
+    }
+    Ok(pool)
+}
+
+/// Open a database only to read from it, refusing one that needs migrating
+/// rather than migrating it
+///
+/// Databases other than the current one are read this way, so that merely
+/// looking at them (in views, or the command palette) changes none of them.
+pub async fn open_db_to_read(connection_str: &str, read_only: bool) -> Result<SqlitePool> {
+    let pool = open_db(connection_str, read_only).await?;
+    if let Err(e) = migration_status(&pool)
+        .await
+        .and_then(|status| status.ensure_migrated(connection_str))
+    {
+        pool.close().await;
+        return Err(e);
+    }
+    Ok(pool)
+}
+
+/// A migration of this version of judo not yet applied to a database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMigration {
+    pub version: i64,
+    pub description: String,
+}
+
+/// How the migrations applied to a database compare to those of this version
+/// of judo
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Versions of the migrations applied, empty for a new database
+    pub applied: Vec<i64>,
+    /// Migrations of this version not applied yet
+    pub pending: Vec<PendingMigration>,
+    /// Versions of the migrations applied that this version doesn't know, i.e.
+    /// of a newer version of judo
+    pub unknown: Vec<i64>,
+}
+
+impl MigrationStatus {
+    /// Refuse a database set up by a newer version of judo, whose schema this
+    /// one may not understand and must not migrate
+    pub fn ensure_known(&self, connection_str: &str) -> Result<()> {
+        if self.unknown.is_empty() {
+            return Ok(());
+        }
+        let versions: Vec<String> = self.unknown.iter().map(|v| v.to_string()).collect();
+        bail!(
+            "The database at {} was set up by a newer version of judo (unknown migration(s) {}). \
+             Update judo to use it, or open it with --read-only",
+            connection_str,
+            versions.join(", ")
+        )
+    }
+
+    /// Refuse a database missing migrations of this version of judo, whose
+    /// schema this one may not be able to read
+    pub fn ensure_migrated(&self, connection_str: &str) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let versions: Vec<String> = self.pending.iter().map(|m| m.version.to_string()).collect();
+        bail!(
+            "The database at {} needs migrating (pending migration(s) {}). \
+             Switch to it, or run judo dbs migrate",
+            connection_str,
+            versions.join(", ")
+        )
+    }
+}
+
+/// Compare the migrations applied to the database with those of this version
+#[instrument(skip(pool), err)]
+pub async fn migration_status(pool: &SqlitePool) -> Result<MigrationStatus> {
+    let has_table: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await
+    .with_context(|| "Failed to read the database schema")?;
+
+    let applied: Vec<i64> = if has_table {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success ORDER BY version")
+            .fetch_all(pool)
+            .await
+            .with_context(|| "Failed to read the applied database migrations")?
+    } else {
+        Vec::new()
+    };
+
+    let pending = MIGRATOR
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .map(|migration| PendingMigration {
+            version: migration.version,
+            description: migration.description.to_string(),
+        })
+        .collect();
+    let unknown = applied
+        .iter()
+        .copied()
+        .filter(|version| {
+            MIGRATOR
+                .iter()
+                .all(|migration| migration.version != *version)
+        })
+        .collect();
+
+    Ok(MigrationStatus {
+        applied,
+        pending,
+        unknown,
+    })
+}
+
 /// Initialize database with connection and run migrations
 /// This is safe to call on every startup - migrations are idempotent
+///
+/// A database holding data of an older version of judo is backed up before it
+/// is migrated, so that a failed migration loses nothing. A database set up by
+/// a newer version is refused (see [`MigrationStatus::ensure_known`]).
 pub async fn init_db(connection_str: &str) -> Result<SqlitePool> {
     let pool = get_db_pool(connection_str).await?;
 
+    let status = migration_status(&pool).await?;
+    if let Err(e) = status.ensure_known(connection_str) {
+        pool.close().await;
+        return Err(e);
+    }
+    if let Some(path) = db_file_path(connection_str)
+        && !status.applied.is_empty()
+        && !status.pending.is_empty()
+    {
+        backup::create(&pool, &path, BackupReason::Migration)
+            .await
+            .with_context(|| "Failed to back the database up before migrating it")?;
+    }
+
     // Always run migrations on startup - they're idempotent and fast
     run_migrations(&pool).await?;
 
     Ok(pool)
 }
 
+/// Path of the database file of a connection string, None for in-memory databases
+pub fn db_file_path(connection_str: &str) -> Option<PathBuf> {
+    let path = connection_str
+        .strip_prefix("sqlite://")
+        .or_else(|| connection_str.strip_prefix("sqlite:"))
+        .unwrap_or(connection_str);
+    let path = path.split('?').next().unwrap_or_default();
+
+    match path {
+        "" | ":memory:" => None,
+        path => Some(PathBuf::from(path)),
+    }
+}
+
+/// Delete a database file along with its write-ahead log and shared memory files
+pub fn delete_db_file(path: &Path) -> io::Result<()> {
+    fs::remove_file(path)?;
+    delete_db_sidecars(path)
+}
+
+/// Delete the write-ahead log and shared memory files of a database file, if any
+pub fn delete_db_sidecars(path: &Path) -> io::Result<()> {
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        match fs::remove_file(&sidecar) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -61,4 +272,119 @@ mod test {
         run_migrations(&pool).await?;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_migration_status() -> Result<()> {
+        let pool = get_db_pool("sqlite::memory:").await?;
+        let status = migration_status(&pool).await?;
+        assert!(status.applied.is_empty());
+        assert_eq!(status.pending.len(), MIGRATOR.iter().count());
+        assert_eq!(status.pending[0].description, "initial schema");
+
+        run_migrations(&pool).await?;
+        let status = migration_status(&pool).await?;
+        assert_eq!(status.applied.len(), MIGRATOR.iter().count());
+        assert!(status.pending.is_empty() && status.unknown.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_newer_db_is_only_opened_read_only() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let connection_str = format!("sqlite:{}", dir.path().join("newer.db").display());
+        let pool = init_db(&connection_str).await?;
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations
+             (version, description, installed_on, success, checksum, execution_time)
+             VALUES (20990101000000, 'from the future', '2099-01-01', TRUE, x'00', 0)",
+        )
+        .execute(&pool)
+        .await?;
+        pool.close().await;
+
+        let error = init_db(&connection_str).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "The database at {} was set up by a newer version of judo (unknown migration(s) \
+                 20990101000000). Update judo to use it, or open it with --read-only",
+                connection_str
+            )
+        );
+        assert!(open_db(&connection_str, false).await.is_err());
+
+        let pool = open_db(&connection_str, true).await?;
+        let lists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM todo_lists")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(lists, 0);
+        let write = sqlx::query("DELETE FROM todo_lists").execute(&pool).await;
+        assert!(write.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_open_db_to_read_does_not_migrate() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let connection_str = format!("sqlite:{}", dir.path().join("older.db").display());
+        let pool = get_db_pool(&connection_str).await?;
+        pool.close().await;
+
+        let error = open_db_to_read(&connection_str, false).await.unwrap_err();
+        assert!(error.to_string().contains("needs migrating"));
+        let pool = get_db_pool(&connection_str).await?;
+        assert!(migration_status(&pool).await?.applied.is_empty());
+
+        run_migrations(&pool).await?;
+        pool.close().await;
+        let pool = open_db_to_read(&connection_str, false).await?;
+        let lists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM todo_lists")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(lists, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_db_uses_wal() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let connection_str = format!("sqlite:{}", dir.path().join("wal.db").display());
+        let pool = init_db(&connection_str).await?;
+
+        let mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(mode.to_lowercase(), "wal");
+
+        let timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(timeout, BUSY_TIMEOUT.as_millis() as i64);
+        Ok(())
+    }
+
+    #[test]
+    fn test_db_file_path() {
+        assert_eq!(
+            db_file_path("sqlite:/data/judo/work.db"),
+            Some(PathBuf::from("/data/judo/work.db"))
+        );
+        assert_eq!(
+            db_file_path("sqlite:///data/work.db?mode=rwc"),
+            Some(PathBuf::from("/data/work.db"))
+        );
+        assert_eq!(db_file_path("sqlite::memory:"), None);
+    }
+
+    #[tokio::test]
+    async fn test_delete_db_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("gone.db");
+        let pool = init_db(&format!("sqlite:{}", path.display())).await?;
+        pool.close().await;
+
+        delete_db_file(&path)?;
+        assert_eq!(fs::read_dir(dir.path())?.count(), 0);
+        Ok(())
+    }
 }
@@ -0,0 +1,294 @@
+//! Health checks of a database, and repairs of what they find, for
+//! `judo dbs doctor`.
+//!
+//! Besides SQLite's own integrity and foreign key checks, this looks for data
+//! judo itself can trip on:
+//!
+//! - Lists, or items of a list, sharing an ordering key. Moves look for the
+//!   neighbour with the next smaller or larger key, so they jump over rows with
+//!   the same key (as left behind by interrupted moves of older versions).
+//!   Gaps between keys are fine, they are left on purpose (see
+//!   [`ORDERING_GAP`](crate::db::ops::ORDERING_GAP)).
+//! - Orphaned items, whose list is gone, which no list shows.
+//! - Priorities other than `high`, `medium` and `low`, which fail to load.
+//!
+//! Repairs respace the keys of the lists and items sharing some, move orphans
+//! to a list of their own or delete them, and fix or clear invalid priorities.
+//! Damage found by the integrity check can't be repaired here, only by
+//! restoring a backup.
+use anyhow::{Context, Result, bail};
+use sqlx::SqlitePool;
+use std::str::FromStr;
+use tracing::instrument;
+
+use crate::db::models::{NewTodoList, TodoItem, TodoList};
+
+/// List the orphaned items are moved to
+pub const RECOVERED_LIST: &str = "Recovered items";
+
+const PRIORITIES: &[&str] = &["high", "medium", "low"];
+
+/// Message of the integrity check for rows failing a CHECK constraint of the
+/// items, whose only one is on the priority
+const ITEM_CHECK_FAILED: &str = "CHECK constraint failed in todo_items";
+
+/// What to do with items whose list is gone
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrphanFix {
+    /// Move them to a new list, "Recovered items"
+    #[default]
+    Reattach,
+    /// Delete them
+    Delete,
+}
+
+impl FromStr for OrphanFix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "reattach" => Ok(OrphanFix::Reattach),
+            "delete" => Ok(OrphanFix::Delete),
+            _ => bail!("Unknown fix '{}', expected reattach or delete", s),
+        }
+    }
+}
+
+/// Problems found in a database
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    /// Messages of `PRAGMA integrity_check`, empty if it found nothing (invalid
+    /// priorities, which it reports too, are in `invalid_priorities`)
+    pub integrity: Vec<String>,
+    /// Rows of `PRAGMA foreign_key_check` in tables other than the items,
+    /// whose violations are the orphans
+    pub foreign_keys: Vec<ForeignKeyViolation>,
+    /// Whether some lists share an ordering key
+    pub duplicate_list_orderings: bool,
+    /// IDs of the lists where some items share an ordering key
+    pub duplicate_item_orderings: Vec<i64>,
+    /// IDs of the items whose list is gone
+    pub orphans: Vec<i64>,
+    /// IDs and priorities of the items with an invalid priority
+    pub invalid_priorities: Vec<(i64, String)>,
+}
+
+/// A row referring to a missing row of another table
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub rowid: Option<i64>,
+    pub parent: String,
+}
+
+/// What a repair changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairSummary {
+    /// Number of scopes (all lists, or the items of a list) respaced
+    pub renormalized: usize,
+    pub reattached: usize,
+    pub deleted: usize,
+    pub priorities_fixed: usize,
+}
+
+impl Report {
+    /// Whether nothing was found
+    pub fn is_healthy(&self) -> bool {
+        *self == Report::default()
+    }
+
+    /// Whether a repair can fix all that was found
+    pub fn is_repairable(&self) -> bool {
+        self.integrity.is_empty() && self.foreign_keys.is_empty()
+    }
+
+    /// Descriptions of the problems, one per line
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .integrity
+            .iter()
+            .map(|message| format!("Integrity check: {}", message))
+            .collect();
+        problems.extend(self.foreign_keys.iter().map(|violation| {
+            format!(
+                "Row {} of {} refers to a missing row of {}",
+                violation
+                    .rowid
+                    .map(|rowid| rowid.to_string())
+                    .unwrap_or_default(),
+                violation.table,
+                violation.parent
+            )
+        }));
+        if self.duplicate_list_orderings {
+            problems.push("Some lists share an ordering key".to_string());
+        }
+        problems.extend(
+            self.duplicate_item_orderings
+                .iter()
+                .map(|list_id| format!("Some items of list {} share an ordering key", list_id)),
+        );
+        if !self.orphans.is_empty() {
+            problems.push(format!(
+                "{} item(s) belong to no list: {}",
+                self.orphans.len(),
+                join_ids(&self.orphans)
+            ));
+        }
+        problems.extend(
+            self.invalid_priorities
+                .iter()
+                .map(|(id, priority)| format!("Item {} has invalid priority '{}'", id, priority)),
+        );
+        problems
+    }
+}
+
+/// Look for problems in the database
+#[instrument(skip(pool), err)]
+pub async fn check(pool: &SqlitePool) -> Result<Report> {
+    let integrity: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await
+        .with_context(|| "Failed to check the integrity of the database")?;
+    let integrity = integrity
+        .into_iter()
+        .filter(|message| message != "ok" && message != ITEM_CHECK_FAILED)
+        .collect();
+
+    let foreign_keys = sqlx::query_as::<_, ForeignKeyViolation>(
+        r#"SELECT "table", rowid, parent FROM pragma_foreign_key_check WHERE "table" != 'todo_items'"#,
+    )
+    .fetch_all(pool)
+    .await
+    .with_context(|| "Failed to check the foreign keys of the database")?;
+
+    let duplicate_list_orderings: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM todo_lists GROUP BY ordering HAVING COUNT(*) > 1)",
+    )
+    .fetch_one(pool)
+    .await
+    .with_context(|| "Failed to check list orderings")?;
+
+    let duplicate_item_orderings = sqlx::query_scalar(
+        "SELECT DISTINCT list_id FROM todo_items
+         WHERE list_id IN (SELECT id FROM todo_lists)
+         GROUP BY list_id, ordering HAVING COUNT(*) > 1
+         ORDER BY list_id",
+    )
+    .fetch_all(pool)
+    .await
+    .with_context(|| "Failed to check item orderings")?;
+
+    let orphans = sqlx::query_scalar(
+        "SELECT id FROM todo_items WHERE list_id NOT IN (SELECT id FROM todo_lists) ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await
+    .with_context(|| "Failed to look for orphaned items")?;
+
+    let invalid_priorities = sqlx::query_as(
+        "SELECT id, CAST(priority AS TEXT) FROM todo_items
+         WHERE priority IS NOT NULL AND priority NOT IN ('high', 'medium', 'low')
+         ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await
+    .with_context(|| "Failed to check priorities")?;
+
+    Ok(Report {
+        integrity,
+        foreign_keys,
+        duplicate_list_orderings,
+        duplicate_item_orderings,
+        orphans,
+        invalid_priorities,
+    })
+}
+
+/// Fix the problems of the report that judo can fix
+#[instrument(skip(pool, report), err)]
+pub async fn repair(
+    pool: &SqlitePool,
+    report: &Report,
+    orphans: OrphanFix,
+) -> Result<RepairSummary> {
+    let mut summary = RepairSummary::default();
+
+    // Priorities first, items with an invalid one can't be loaded
+    for (id, priority) in &report.invalid_priorities {
+        let normalized = priority.trim().to_lowercase();
+        let fixed = PRIORITIES
+            .contains(&normalized.as_str())
+            .then_some(normalized);
+        sqlx::query("UPDATE todo_items SET priority = ?1 WHERE id = ?2")
+            .bind(fixed)
+            .bind(id)
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to fix the priority of item {}", id))?;
+        summary.priorities_fixed += 1;
+    }
+
+    if report.duplicate_list_orderings {
+        TodoList::renormalize(pool).await?;
+        summary.renormalized += 1;
+    }
+    for list_id in &report.duplicate_item_orderings {
+        TodoItem::renormalize(pool, *list_id).await?;
+        summary.renormalized += 1;
+    }
+
+    if !report.orphans.is_empty() {
+        match orphans {
+            OrphanFix::Reattach => {
+                let list = TodoList::create(
+                    pool,
+                    NewTodoList {
+                        name: RECOVERED_LIST.to_string(),
+                    },
+                )
+                .await?;
+                for id in &report.orphans {
+                    sqlx::query("UPDATE todo_items SET list_id = ?1 WHERE id = ?2")
+                        .bind(list.id)
+                        .bind(id)
+                        .execute(pool)
+                        .await
+                        .with_context(|| format!("Failed to reattach item {}", id))?;
+                }
+                // Their keys come from lists of their own
+                TodoItem::renormalize(pool, list.id).await?;
+                summary.reattached = report.orphans.len();
+            }
+            OrphanFix::Delete => {
+                for id in &report.orphans {
+                    sqlx::query("DELETE FROM todo_items WHERE id = ?1")
+                        .bind(id)
+                        .execute(pool)
+                        .await
+                        .with_context(|| format!("Failed to delete item {}", id))?;
+                }
+                summary.deleted = report.orphans.len();
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Rebuild the database file, leaving out its free pages
+#[instrument(skip(pool), err)]
+pub async fn vacuum(pool: &SqlitePool) -> Result<()> {
+    sqlx::query("VACUUM")
+        .execute(pool)
+        .await
+        .with_context(|| "Failed to vacuum the database")?;
+    Ok(())
+}
+
+fn join_ids(ids: &[i64]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,4 +1,8 @@
+pub mod backup;
 pub mod config;
 pub mod connections;
+pub mod doctor;
 pub mod models;
 pub mod ops;
+pub mod query;
+pub mod snapshot;
@@ -1,16 +1,46 @@
+use crate::db::query::Query;
+use anyhow::bail;
 use chrono::{DateTime, Utc};
 use ratatui::widgets::ListState;
-use sqlx::{FromRow, Type};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool, Type};
+use std::str::FromStr;
 
-#[derive(Debug, Clone, PartialEq, Eq, Type)]
+#[derive(Debug, Clone, PartialEq, Eq, Type, Serialize, Deserialize)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum Priority {
     High,
     Medium,
     Low,
 }
 
-#[derive(Debug, FromRow, Clone)]
+impl Priority {
+    /// Name of the priority, as stored in the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    /// Parse the name of a priority, ignoring case
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "high" => Ok(Priority::High),
+            "medium" => Ok(Priority::Medium),
+            "low" => Ok(Priority::Low),
+            _ => bail!("Unknown priority '{}', expected high, medium or low", s),
+        }
+    }
+}
+
+#[derive(Debug, FromRow, Clone, Serialize)]
 pub struct TodoList {
     pub id: i64,
     pub name: String,
@@ -19,7 +49,7 @@ pub struct TodoList {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, FromRow, Clone)]
+#[derive(Debug, FromRow, Clone, Serialize)]
 pub struct TodoItem {
     pub id: i64,
     pub list_id: i64,
@@ -32,6 +62,14 @@ pub struct TodoItem {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Item returned by a query, with the name of its list
+#[derive(Debug, FromRow, Clone)]
+pub struct ListedItem {
+    #[sqlx(flatten)]
+    pub item: TodoItem,
+    pub list_name: String,
+}
+
 // Structs for creating new records (without id and timestamps)
 #[derive(Debug)]
 pub struct NewTodoList {
@@ -52,10 +90,38 @@ pub struct UIList {
     pub list: TodoList,
     pub item_state: ListState,
     pub items: Vec<UIItem>,
+    /// Only the items matching this query are loaded
+    pub filter: Option<Query>,
+    /// Saved view whose items are shown instead of those of `list`
+    pub view: Option<SavedView>,
 }
 
 #[derive(Debug, Clone)]
 pub struct UIItem {
     pub item: TodoItem,
     pub state: ListState,
+    /// Where the item comes from, for items of a view
+    pub origin: Option<ItemOrigin>,
+}
+
+/// A saved filter with connections to the databases it reads from
+#[derive(Debug, Clone)]
+pub struct SavedView {
+    pub name: String,
+    pub query: Query,
+    pub sources: Vec<ItemSource>,
+}
+
+/// Database a view reads items from
+#[derive(Debug, Clone)]
+pub struct ItemSource {
+    pub db_name: String,
+    pub pool: SqlitePool,
+}
+
+/// Real list and database of an item shown in a view
+#[derive(Debug, Clone)]
+pub struct ItemOrigin {
+    pub source: ItemSource,
+    pub list_name: String,
 }
@@ -1,34 +1,105 @@
-use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use sqlx::SqlitePool;
-
-use crate::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList, UIItem, UIList};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::db::models::{
+    ItemOrigin, ListedItem, NewTodoItem, NewTodoList, Priority, SavedView, TodoItem, TodoList,
+    UIItem, UIList,
+};
+use crate::db::query::{Expr, Query};
 use ratatui::widgets::ListState;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use tracing::instrument;
+
+/// Distance between the ordering keys of consecutive rows.
+/// Leaving gaps lets a row be moved anywhere by rewriting only its own key.
+pub const ORDERING_GAP: i64 = 1024;
+
+/// Compute the ordering key that places a row at `position` among `siblings`
+/// (the other rows of the same scope, sorted by ordering).
+/// Returns `None` if the two neighbours are adjacent and the scope must be renormalized first.
+fn ordering_key_at(siblings: &[i64], position: usize) -> Option<i64> {
+    let position = position.min(siblings.len());
+    let before = position.checked_sub(1).map(|p| siblings[p]);
+    let after = siblings.get(position).copied();
+
+    match (before, after) {
+        (None, None) => Some(ORDERING_GAP),
+        (Some(before), None) => Some(before + ORDERING_GAP),
+        (None, Some(after)) => Some(after - ORDERING_GAP),
+        (Some(before), Some(after)) if after - before > 1 => Some(before + (after - before) / 2),
+        _ => None,
+    }
+}
+
+/// Rewrite the ordering keys of `ids` (already in the desired order) so they are evenly spaced
+#[instrument(level = "debug", skip(conn, ids), fields(rows = ids.len()), err)]
+async fn respace_orderings(conn: &mut SqliteConnection, table: &str, ids: &[i64]) -> Result<()> {
+    let query = format!("UPDATE {table} SET ordering = ?1 WHERE id = ?2");
+    for (i, id) in ids.iter().enumerate() {
+        sqlx::query(&query)
+            .bind((i as i64 + 1) * ORDERING_GAP)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to renormalize ordering in {table}"))?;
+    }
+    Ok(())
+}
+
+/// Move row `id` of `table` to `position` among `siblings` (the other rows of its scope,
+/// as `(id, ordering)` sorted by ordering). Usually a single UPDATE; if there is no room
+/// between the new neighbours the whole scope is renormalized instead.
+/// Returns the new ordering key of the row.
+#[instrument(level = "debug", skip(conn, siblings), err)]
+async fn move_row_to_position(
+    conn: &mut SqliteConnection,
+    table: &str,
+    id: i64,
+    siblings: &[(i64, i64)],
+    position: usize,
+) -> Result<i64> {
+    let keys: Vec<i64> = siblings.iter().map(|(_, ordering)| *ordering).collect();
+
+    if let Some(key) = ordering_key_at(&keys, position) {
+        sqlx::query(&format!("UPDATE {table} SET ordering = ?1 WHERE id = ?2"))
+            .bind(key)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to update ordering in {table}"))?;
+        return Ok(key);
+    }
+
+    // No gap left: lay out the whole scope again with the row in its new place
+    let position = position.min(siblings.len());
+    let mut ids: Vec<i64> = siblings.iter().map(|(id, _)| *id).collect();
+    ids.insert(position, id);
+    respace_orderings(conn, table, &ids).await?;
+
+    Ok((position as i64 + 1) * ORDERING_GAP)
+}
 
 impl TodoList {
     /// Create a new todo list
+    #[instrument(skip_all, err)]
     pub async fn create(pool: &SqlitePool, new_list: NewTodoList) -> Result<TodoList> {
         let now = Utc::now();
 
-        // Get the next ordering value (max + 1)
-        let next_ordering: i64 =
-            sqlx::query_scalar("SELECT COALESCE(MAX(ordering), 0) + 1 FROM todo_lists")
-                .fetch_one(pool)
-                .await
-                .with_context(|| "Failed to get next ordering value")?;
-
-        // Use query_as to map results to a struct
+        // Compute the next ordering value (max + gap) inside the INSERT itself, so that
+        // two concurrent writers can't both claim the same slot
         let row = sqlx::query_as::<_, TodoList>(
             r#"
             INSERT INTO todo_lists (name, ordering, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4)
+            VALUES (?1, (SELECT COALESCE(MAX(ordering), 0) + ?4 FROM todo_lists), ?2, ?3)
             RETURNING id, name, ordering, created_at, updated_at
             "#,
         )
         .bind(&new_list.name)
-        .bind(next_ordering)
         .bind(now)
         .bind(now)
+        .bind(ORDERING_GAP)
         .fetch_one(pool)
         .await
         .with_context(|| "Failed to create todo list")?;
@@ -37,6 +108,7 @@ impl TodoList {
     }
 
     /// Get all todo lists
+    #[instrument(level = "debug", skip_all, err)]
     pub async fn get_all(pool: &SqlitePool) -> Result<Vec<TodoList>> {
         let lists = sqlx::query_as::<_, TodoList>(
             "SELECT id, name, ordering, created_at, updated_at FROM todo_lists ORDER BY ordering",
@@ -49,6 +121,7 @@ impl TodoList {
     }
 
     /// Get a specific todo list by ID
+    #[instrument(level = "debug", skip(pool), err)]
     pub async fn get_by_id(pool: &SqlitePool, id: i64) -> Result<Option<TodoList>> {
         let list = sqlx::query_as::<_, TodoList>(
             "SELECT id, name, ordering, created_at, updated_at FROM todo_lists WHERE id = ?1",
@@ -62,6 +135,7 @@ impl TodoList {
     }
 
     /// Update todo list name
+    #[instrument(skip_all, fields(list_id = self.id), err)]
     pub async fn update_name(&mut self, pool: &SqlitePool, new_name: String) -> Result<()> {
         let now = Utc::now();
 
@@ -79,6 +153,7 @@ impl TodoList {
     }
 
     /// Delete todo list (and all its items due to CASCADE)
+    #[instrument(skip_all, fields(list_id = self.id), err)]
     pub async fn delete(self, pool: &SqlitePool) -> Result<()> {
         sqlx::query("DELETE FROM todo_lists WHERE id = ?1")
             .bind(self.id)
@@ -90,71 +165,175 @@ impl TodoList {
     }
 
     /// Move list up (decrease ordering, swap with previous)
+    #[instrument(skip_all, fields(list_id = self.id), err)]
     pub async fn move_up(&mut self, pool: &SqlitePool) -> Result<()> {
+        // Take the write lock up front so the read and both updates happen atomically
+        let mut tx = pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .with_context(|| "Failed to start transaction")?;
+
+        // Re-read our own ordering, as another connection may have moved this list
+        let current_ordering: i64 =
+            sqlx::query_scalar("SELECT ordering FROM todo_lists WHERE id = ?1")
+                .bind(self.id)
+                .fetch_one(&mut *tx)
+                .await
+                .with_context(|| "Failed to fetch current list ordering")?;
+
         // Find the list with the next lower ordering value
         let prev_list: Option<(i64, i64)> = sqlx::query_as(
-            "SELECT id, ordering FROM todo_lists WHERE ordering < ?1 ORDER BY ordering DESC LIMIT 1"
+            "SELECT id, ordering FROM todo_lists WHERE ordering < ?1 ORDER BY ordering DESC LIMIT 1",
         )
-        .bind(self.ordering)
-        .fetch_optional(pool)
+        .bind(current_ordering)
+        .fetch_optional(&mut *tx)
         .await
         .with_context(|| "Failed to find previous list")?;
 
         if let Some((prev_id, prev_ordering)) = prev_list {
             // Swap orderings
             sqlx::query("UPDATE todo_lists SET ordering = ?1 WHERE id = ?2")
-                .bind(self.ordering)
+                .bind(current_ordering)
                 .bind(prev_id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .with_context(|| "Failed to update previous list ordering")?;
 
             sqlx::query("UPDATE todo_lists SET ordering = ?1 WHERE id = ?2")
                 .bind(prev_ordering)
                 .bind(self.id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .with_context(|| "Failed to update current list ordering")?;
 
             self.ordering = prev_ordering;
+        } else {
+            self.ordering = current_ordering;
         }
 
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit list reordering")?;
+
         Ok(())
     }
 
     /// Move list down (increase ordering, swap with next)
+    #[instrument(skip_all, fields(list_id = self.id), err)]
     pub async fn move_down(&mut self, pool: &SqlitePool) -> Result<()> {
+        // Take the write lock up front so the read and both updates happen atomically
+        let mut tx = pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .with_context(|| "Failed to start transaction")?;
+
+        // Re-read our own ordering, as another connection may have moved this list
+        let current_ordering: i64 =
+            sqlx::query_scalar("SELECT ordering FROM todo_lists WHERE id = ?1")
+                .bind(self.id)
+                .fetch_one(&mut *tx)
+                .await
+                .with_context(|| "Failed to fetch current list ordering")?;
+
         // Find the list with the next higher ordering value
         let next_list: Option<(i64, i64)> = sqlx::query_as(
             "SELECT id, ordering FROM todo_lists WHERE ordering > ?1 ORDER BY ordering ASC LIMIT 1",
         )
-        .bind(self.ordering)
-        .fetch_optional(pool)
+        .bind(current_ordering)
+        .fetch_optional(&mut *tx)
         .await
         .with_context(|| "Failed to find next list")?;
 
         if let Some((next_id, next_ordering)) = next_list {
             // Swap orderings
             sqlx::query("UPDATE todo_lists SET ordering = ?1 WHERE id = ?2")
-                .bind(self.ordering)
+                .bind(current_ordering)
                 .bind(next_id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .with_context(|| "Failed to update next list ordering")?;
 
             sqlx::query("UPDATE todo_lists SET ordering = ?1 WHERE id = ?2")
                 .bind(next_ordering)
                 .bind(self.id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .with_context(|| "Failed to update current list ordering")?;
 
             self.ordering = next_ordering;
+        } else {
+            self.ordering = current_ordering;
         }
 
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit list reordering")?;
+
+        Ok(())
+    }
+
+    /// Move list to the given 0-based position among all lists (clamped to the end)
+    #[instrument(skip_all, fields(list_id = self.id, position), err)]
+    pub async fn move_to_position(&mut self, pool: &SqlitePool, position: usize) -> Result<()> {
+        let mut tx = pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .with_context(|| "Failed to start transaction")?;
+
+        let siblings: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT id, ordering FROM todo_lists WHERE id != ?1 ORDER BY ordering, id",
+        )
+        .bind(self.id)
+        .fetch_all(&mut *tx)
+        .await
+        .with_context(|| "Failed to fetch list orderings")?;
+
+        let new_ordering =
+            move_row_to_position(&mut tx, "todo_lists", self.id, &siblings, position).await?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit list reordering")?;
+
+        self.ordering = new_ordering;
+        Ok(())
+    }
+
+    /// Move list to the top of the lists
+    #[instrument(skip_all, fields(list_id = self.id), err)]
+    pub async fn move_to_top(&mut self, pool: &SqlitePool) -> Result<()> {
+        self.move_to_position(pool, 0).await
+    }
+
+    /// Move list to the bottom of the lists
+    #[instrument(skip_all, fields(list_id = self.id), err)]
+    pub async fn move_to_bottom(&mut self, pool: &SqlitePool) -> Result<()> {
+        self.move_to_position(pool, usize::MAX).await
+    }
+
+    /// Evenly respace the ordering keys of all lists, preserving their order
+    #[instrument(skip_all, err)]
+    pub async fn renormalize(pool: &SqlitePool) -> Result<()> {
+        let mut tx = pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .with_context(|| "Failed to start transaction")?;
+
+        let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM todo_lists ORDER BY ordering, id")
+            .fetch_all(&mut *tx)
+            .await
+            .with_context(|| "Failed to fetch list orderings")?;
+
+        respace_orderings(&mut tx, "todo_lists", &ids).await?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit list renormalization")?;
+
         Ok(())
     }
 
+    #[instrument(level = "debug", skip_all, fields(list_id = self.id), err)]
     pub async fn get_all_items(&self, pool: &SqlitePool) -> Result<Vec<TodoItem>> {
         TodoItem::get_by_list_id(pool, self.id).await
     }
@@ -162,22 +341,20 @@ impl TodoList {
 
 impl TodoItem {
     /// Create a new todo item
+    #[instrument(skip_all, fields(list_id = new_item.list_id), err)]
     pub async fn create(pool: &SqlitePool, new_item: NewTodoItem) -> Result<TodoItem> {
         let now = Utc::now();
 
-        // Get the next ordering value for this list (max + 1)
-        let next_ordering: i64 = sqlx::query_scalar(
-            "SELECT COALESCE(MAX(ordering), 0) + 1 FROM todo_items WHERE list_id = ?1",
-        )
-        .bind(new_item.list_id)
-        .fetch_one(pool)
-        .await
-        .with_context(|| "Failed to get next ordering value")?;
-
+        // Compute the next ordering value for this list (max + gap) inside the INSERT itself,
+        // so that two concurrent writers can't both claim the same slot
         let row = sqlx::query_as::<_, TodoItem>(
             r#"
             INSERT INTO todo_items (list_id, name, is_done, priority, due_date, ordering, created_at, updated_at)
-            VALUES (?1, ?2, FALSE, ?3, ?4, ?5, ?6, ?7)
+            VALUES (
+                ?1, ?2, FALSE, ?3, ?4,
+                (SELECT COALESCE(MAX(ordering), 0) + ?7 FROM todo_items WHERE list_id = ?1),
+                ?5, ?6
+            )
             RETURNING id, list_id, name, is_done, priority, due_date, ordering, created_at, updated_at
             "#,
         )
@@ -185,9 +362,9 @@ impl TodoItem {
         .bind(&new_item.name)
         .bind(&new_item.priority)
         .bind(new_item.due_date)
-        .bind(next_ordering)
         .bind(now)
         .bind(now)
+        .bind(ORDERING_GAP)
         .fetch_one(pool)
         .await
         .with_context(|| "Failed to create todo item")?;
@@ -196,6 +373,7 @@ impl TodoItem {
     }
 
     /// Get all items for a specific list
+    #[instrument(level = "debug", skip(pool), err)]
     pub async fn get_by_list_id(pool: &SqlitePool, list_id: i64) -> Result<Vec<TodoItem>> {
         let items = sqlx::query_as::<_, TodoItem>(
             r#"
@@ -214,6 +392,7 @@ impl TodoItem {
     }
 
     /// Get item with a specific id
+    #[instrument(level = "debug", skip(pool), err)]
     pub async fn get_by_id(pool: &SqlitePool, id: i64) -> Result<Option<TodoItem>> {
         let item = sqlx::query_as::<_, TodoItem>(
             r#"
@@ -231,6 +410,7 @@ impl TodoItem {
     }
 
     /// Update to-do item name
+    #[instrument(skip_all, fields(item_id = self.id), err)]
     pub async fn update_name(&mut self, pool: &SqlitePool, new_name: String) -> Result<()> {
         let now = Utc::now();
 
@@ -249,6 +429,7 @@ impl TodoItem {
     }
 
     /// Toggle item completion status (from false to true or from true to false)
+    #[instrument(skip_all, fields(item_id = self.id), err)]
     pub async fn toggle_done(&mut self, pool: &SqlitePool) -> Result<()> {
         let now = Utc::now();
         let new_status = !self.is_done;
@@ -268,6 +449,7 @@ impl TodoItem {
     }
 
     /// Update item priority
+    #[instrument(skip(self, pool), fields(item_id = self.id), err)]
     pub async fn update_priority(
         &mut self,
         pool: &SqlitePool,
@@ -290,6 +472,7 @@ impl TodoItem {
     }
 
     /// Update item due date
+    #[instrument(skip(self, pool), fields(item_id = self.id), err)]
     pub async fn update_due_date(
         &mut self,
         pool: &SqlitePool,
@@ -310,7 +493,42 @@ impl TodoItem {
         Ok(())
     }
 
+    /// Remove the priority of the item
+    #[instrument(skip_all, fields(item_id = self.id), err)]
+    pub async fn clear_priority(&mut self, pool: &SqlitePool) -> Result<()> {
+        let now = Utc::now();
+
+        sqlx::query("UPDATE todo_items SET priority = NULL, updated_at = ?1 WHERE id = ?2")
+            .bind(now)
+            .bind(self.id)
+            .execute(pool)
+            .await
+            .with_context(|| "Failed to clear todo item priority")?;
+
+        self.priority = None;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Remove the due date of the item
+    #[instrument(skip_all, fields(item_id = self.id), err)]
+    pub async fn clear_due_date(&mut self, pool: &SqlitePool) -> Result<()> {
+        let now = Utc::now();
+
+        sqlx::query("UPDATE todo_items SET due_date = NULL, updated_at = ?1 WHERE id = ?2")
+            .bind(now)
+            .bind(self.id)
+            .execute(pool)
+            .await
+            .with_context(|| "Failed to clear todo item due date")?;
+
+        self.due_date = None;
+        self.updated_at = now;
+        Ok(())
+    }
+
     /// Delete todo item
+    #[instrument(skip_all, fields(item_id = self.id), err)]
     pub async fn delete(self, pool: &SqlitePool) -> Result<()> {
         sqlx::query("DELETE FROM todo_items WHERE id = ?1")
             .bind(self.id)
@@ -322,77 +540,420 @@ impl TodoItem {
     }
 
     /// Move item up (decrease ordering, swap with previous in same list)
+    #[instrument(skip_all, fields(item_id = self.id), err)]
     pub async fn move_up(&mut self, pool: &SqlitePool) -> Result<()> {
+        // Take the write lock up front so the read and both updates happen atomically
+        let mut tx = pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .with_context(|| "Failed to start transaction")?;
+
+        // Re-read our own ordering, as another connection may have moved this item
+        let current_ordering: i64 =
+            sqlx::query_scalar("SELECT ordering FROM todo_items WHERE id = ?1")
+                .bind(self.id)
+                .fetch_one(&mut *tx)
+                .await
+                .with_context(|| "Failed to fetch current item ordering")?;
+
         // Find the item with the next lower ordering value in the same list
         let prev_item: Option<(i64, i64)> = sqlx::query_as(
             "SELECT id, ordering FROM todo_items WHERE list_id = ?1 AND ordering < ?2 ORDER BY ordering DESC LIMIT 1"
         )
         .bind(self.list_id)
-        .bind(self.ordering)
-        .fetch_optional(pool)
+        .bind(current_ordering)
+        .fetch_optional(&mut *tx)
         .await
         .with_context(|| "Failed to find previous item")?;
 
         if let Some((prev_id, prev_ordering)) = prev_item {
             // Swap orderings
             sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
-                .bind(self.ordering)
+                .bind(current_ordering)
                 .bind(prev_id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .with_context(|| "Failed to update previous item ordering")?;
 
             sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
                 .bind(prev_ordering)
                 .bind(self.id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .with_context(|| "Failed to update current item ordering")?;
 
             self.ordering = prev_ordering;
+        } else {
+            self.ordering = current_ordering;
         }
 
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit item reordering")?;
+
         Ok(())
     }
 
     /// Move item down (increase ordering, swap with next in same list)
+    #[instrument(skip_all, fields(item_id = self.id), err)]
     pub async fn move_down(&mut self, pool: &SqlitePool) -> Result<()> {
+        // Take the write lock up front so the read and both updates happen atomically
+        let mut tx = pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .with_context(|| "Failed to start transaction")?;
+
+        // Re-read our own ordering, as another connection may have moved this item
+        let current_ordering: i64 =
+            sqlx::query_scalar("SELECT ordering FROM todo_items WHERE id = ?1")
+                .bind(self.id)
+                .fetch_one(&mut *tx)
+                .await
+                .with_context(|| "Failed to fetch current item ordering")?;
+
         // Find the item with the next higher ordering value in the same list
         let next_item: Option<(i64, i64)> = sqlx::query_as(
             "SELECT id, ordering FROM todo_items WHERE list_id = ?1 AND ordering > ?2 ORDER BY ordering ASC LIMIT 1"
         )
         .bind(self.list_id)
-        .bind(self.ordering)
-        .fetch_optional(pool)
+        .bind(current_ordering)
+        .fetch_optional(&mut *tx)
         .await
         .with_context(|| "Failed to find next item")?;
 
         if let Some((next_id, next_ordering)) = next_item {
             // Swap orderings
             sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
-                .bind(self.ordering)
+                .bind(current_ordering)
                 .bind(next_id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .with_context(|| "Failed to update next item ordering")?;
 
             sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
                 .bind(next_ordering)
                 .bind(self.id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .with_context(|| "Failed to update current item ordering")?;
 
             self.ordering = next_ordering;
+        } else {
+            self.ordering = current_ordering;
         }
 
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit item reordering")?;
+
+        Ok(())
+    }
+
+    /// Move item to the given 0-based position within its list (clamped to the end)
+    #[instrument(skip_all, fields(item_id = self.id, position), err)]
+    pub async fn move_to_position(&mut self, pool: &SqlitePool, position: usize) -> Result<()> {
+        let mut tx = pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .with_context(|| "Failed to start transaction")?;
+
+        let siblings: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT id, ordering FROM todo_items WHERE list_id = ?1 AND id != ?2 ORDER BY ordering, id",
+        )
+        .bind(self.list_id)
+        .bind(self.id)
+        .fetch_all(&mut *tx)
+        .await
+        .with_context(|| "Failed to fetch item orderings")?;
+
+        let new_ordering =
+            move_row_to_position(&mut tx, "todo_items", self.id, &siblings, position).await?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit item reordering")?;
+
+        self.ordering = new_ordering;
         Ok(())
     }
+
+    /// Move item to the top of its list
+    #[instrument(skip_all, fields(item_id = self.id), err)]
+    pub async fn move_to_top(&mut self, pool: &SqlitePool) -> Result<()> {
+        self.move_to_position(pool, 0).await
+    }
+
+    /// Move item to the bottom of its list
+    #[instrument(skip_all, fields(item_id = self.id), err)]
+    pub async fn move_to_bottom(&mut self, pool: &SqlitePool) -> Result<()> {
+        self.move_to_position(pool, usize::MAX).await
+    }
+
+    /// Evenly respace the ordering keys of the items in a list, preserving their order
+    #[instrument(skip(pool), err)]
+    pub async fn renormalize(pool: &SqlitePool, list_id: i64) -> Result<()> {
+        let mut tx = pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .with_context(|| "Failed to start transaction")?;
+
+        let ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT id FROM todo_items WHERE list_id = ?1 ORDER BY ordering, id",
+        )
+        .bind(list_id)
+        .fetch_all(&mut *tx)
+        .await
+        .with_context(|| "Failed to fetch item orderings")?;
+
+        respace_orderings(&mut tx, "todo_items", &ids).await?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit item renormalization")?;
+
+        Ok(())
+    }
+}
+
+/// LIKE pattern matching names that contain the text, with `\` as escape character
+pub(crate) fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Value bound to a placeholder of an `ItemQuery`
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryParam {
+    Int(i64),
+    Text(String),
+    Time(DateTime<Utc>),
+}
+
+/// Order of the items returned by an `ItemQuery`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ItemSort {
+    /// As shown in the TUI: by list, then by position in the list
+    #[default]
+    Ordering,
+    /// Earliest due date first, items without one last
+    Due,
+    /// Highest priority first, items without one last
+    Priority,
+    /// Alphabetically, ignoring case
+    Name,
+    /// Oldest first
+    Created,
+}
+
+impl FromStr for ItemSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ordering" => Ok(ItemSort::Ordering),
+            "due" => Ok(ItemSort::Due),
+            "priority" => Ok(ItemSort::Priority),
+            "name" => Ok(ItemSort::Name),
+            "created" => Ok(ItemSort::Created),
+            _ => bail!(
+                "Unknown sort '{}', expected ordering, due, priority, name or created",
+                s
+            ),
+        }
+    }
+}
+
+impl ItemSort {
+    /// ORDER BY clause of the sort
+    fn sql(&self) -> &'static str {
+        match self {
+            ItemSort::Ordering => "l.ordering, i.ordering",
+            ItemSort::Due => "i.due_date IS NULL, julianday(i.due_date), l.ordering, i.ordering",
+            ItemSort::Priority => {
+                "CASE i.priority WHEN 'high' THEN 0 WHEN 'medium' THEN 1 WHEN 'low' THEN 2 ELSE 3 END, \
+                 l.ordering, i.ordering"
+            }
+            ItemSort::Name => "i.name COLLATE NOCASE, l.ordering, i.ordering",
+            ItemSort::Created => "i.created_at, i.id",
+        }
+    }
+
+    /// Compare two items the way the ORDER BY clause does, for merging the results
+    /// of several databases. Positions aren't comparable across databases, so items
+    /// that only differ by them compare equal
+    pub fn compare(&self, a: &TodoItem, b: &TodoItem) -> Ordering {
+        fn rank(priority: &Option<Priority>) -> u8 {
+            match priority {
+                Some(Priority::High) => 0,
+                Some(Priority::Medium) => 1,
+                Some(Priority::Low) => 2,
+                None => 3,
+            }
+        }
+
+        match self {
+            ItemSort::Ordering => Ordering::Equal,
+            ItemSort::Due => {
+                (a.due_date.is_none(), a.due_date).cmp(&(b.due_date.is_none(), b.due_date))
+            }
+            ItemSort::Priority => rank(&a.priority).cmp(&rank(&b.priority)),
+            ItemSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            ItemSort::Created => a.created_at.cmp(&b.created_at),
+        }
+    }
+}
+
+/// Builder of a filtered, sorted SELECT over the items of a database.
+///
+/// Every filter adds a condition to the WHERE clause, with its values bound as
+/// parameters, so nothing is filtered in Rust.
+#[derive(Debug, Clone, Default)]
+pub struct ItemQuery {
+    conditions: Vec<String>,
+    params: Vec<QueryParam>,
+    sort: ItemSort,
+    limit: Option<usize>,
+}
+
+impl ItemQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a condition on the columns of `todo_items i` and `todo_lists l`, with
+    /// one `?` placeholder per parameter
+    pub fn condition(mut self, sql: impl Into<String>, params: Vec<QueryParam>) -> Self {
+        self.conditions.push(format!("({})", sql.into()));
+        self.params.extend(params);
+        self
+    }
+
+    /// Only items of the list with this name
+    pub fn list_name(self, name: &str) -> Self {
+        self.condition("l.name = ?", vec![QueryParam::Text(name.to_string())])
+    }
+
+    /// Only done items, or only open ones
+    pub fn done(self, done: bool) -> Self {
+        self.condition("i.is_done = ?", vec![QueryParam::Int(done as i64)])
+    }
+
+    /// Only items with this priority
+    pub fn priority(self, priority: &Priority) -> Self {
+        self.condition(
+            "i.priority = ?",
+            vec![QueryParam::Text(priority.name().to_string())],
+        )
+    }
+
+    /// Only items due strictly before this time
+    pub fn due_before(self, time: DateTime<Utc>) -> Self {
+        self.condition(
+            "julianday(i.due_date) < julianday(?)",
+            vec![QueryParam::Time(time)],
+        )
+    }
+
+    /// Only items due strictly after this time
+    pub fn due_after(self, time: DateTime<Utc>) -> Self {
+        self.condition(
+            "julianday(i.due_date) > julianday(?)",
+            vec![QueryParam::Time(time)],
+        )
+    }
+
+    /// Only open items whose due date has passed
+    pub fn overdue(self, now: DateTime<Utc>) -> Self {
+        self.done(false).due_before(now)
+    }
+
+    /// Only items whose name contains the text, ignoring case
+    pub fn search(self, text: &str) -> Self {
+        self.condition(
+            "i.name LIKE ? ESCAPE '\\'",
+            vec![QueryParam::Text(like_pattern(text))],
+        )
+    }
+
+    /// Only items matching the query, with dates relative to `now`
+    pub fn matching<Tz: TimeZone>(self, expr: &Expr, now: &DateTime<Tz>) -> Self {
+        let (sql, params) = expr.to_sql(now);
+        self.condition(sql, params)
+    }
+
+    pub fn sort(mut self, sort: ItemSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Return at most this many items
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// SQL of the query and the parameters to bind, in order
+    pub fn to_sql(&self) -> (String, Vec<QueryParam>) {
+        let mut sql = String::from(
+            "SELECT i.id, i.list_id, i.name, i.is_done, i.priority, i.due_date, i.ordering, \
+             i.created_at, i.updated_at, l.name AS list_name \
+             FROM todo_items i JOIN todo_lists l ON l.id = i.list_id",
+        );
+        let mut params = self.params.clone();
+
+        if !self.conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&self.conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY ");
+        sql.push_str(self.sort.sql());
+        if let Some(limit) = self.limit {
+            sql.push_str(" LIMIT ?");
+            params.push(QueryParam::Int(limit.min(i64::MAX as usize) as i64));
+        }
+
+        (sql, params)
+    }
+
+    /// Run the query
+    #[instrument(level = "debug", skip(pool), err)]
+    pub async fn fetch(&self, pool: &SqlitePool) -> Result<Vec<ListedItem>> {
+        let (sql, params) = self.to_sql();
+
+        let mut query = sqlx::query_as::<_, ListedItem>(&sql);
+        for param in params {
+            query = match param {
+                QueryParam::Int(value) => query.bind(value),
+                QueryParam::Text(value) => query.bind(value),
+                QueryParam::Time(value) => query.bind(value),
+            };
+        }
+
+        let items = query
+            .fetch_all(pool)
+            .await
+            .with_context(|| "Failed to query todo items")?;
+
+        Ok(items)
+    }
 }
 
 impl UIList {
     /// Get all lists in db already attached to their items
+    #[instrument(level = "debug", skip_all, err)]
     pub async fn get_all(pool: &SqlitePool) -> Result<Vec<UIList>> {
+        UIList::get_all_filtered(pool, None).await
+    }
+
+    /// Get all lists in db attached to their items matching the filter
+    #[instrument(level = "debug", skip_all, err)]
+    pub async fn get_all_filtered(
+        pool: &SqlitePool,
+        filter: Option<&Query>,
+    ) -> Result<Vec<UIList>> {
         // Fetch all lists
         let lists = TodoList::get_all(pool)
             .await
@@ -402,39 +963,115 @@ impl UIList {
 
         // For each list, fetch its items and create a UIList
         for list in lists {
-            let items = TodoItem::get_by_list_id(pool, list.id)
-                .await
-                .with_context(|| format!("Failed to fetch items for list {}", list.id))?
-                .iter()
-                .map(|i| UIItem {
-                    item: i.clone(),
-                    state: ListState::default(),
-                })
-                .collect();
-
-            ui_lists.push(UIList {
+            let mut ui_list = UIList {
                 list,
                 item_state: ListState::default(),
-                items,
-            });
+                items: Vec::new(),
+                filter: filter.cloned(),
+                view: None,
+            };
+            ui_list.items = ui_list
+                .fetch_items(pool)
+                .await
+                .with_context(|| format!("Failed to fetch items for list {}", ui_list.list.id))?;
+
+            ui_lists.push(ui_list);
         }
 
         Ok(ui_lists)
     }
 
+    /// Virtual list of the items matching a saved view, in all of its databases.
+    ///
+    /// The list itself isn't stored anywhere, it has ID 0 and the name of the view.
+    #[instrument(level = "debug", skip_all, fields(view = view.name), err)]
+    pub async fn get_view(view: SavedView) -> Result<UIList> {
+        let now = Utc::now();
+        let mut ui_list = UIList {
+            list: TodoList {
+                id: 0,
+                name: view.name.clone(),
+                ordering: 0,
+                created_at: now,
+                updated_at: now,
+            },
+            item_state: ListState::default(),
+            items: Vec::new(),
+            filter: None,
+            view: Some(view),
+        };
+        ui_list.items = ui_list.fetch_view_items().await?;
+        Ok(ui_list)
+    }
+
+    /// Items of the list, only those matching the filter if there is one
+    async fn fetch_items(&self, pool: &SqlitePool) -> Result<Vec<UIItem>> {
+        if self.view.is_some() {
+            return self.fetch_view_items().await;
+        }
+
+        let items = match &self.filter {
+            None => TodoItem::get_by_list_id(pool, self.list.id).await?,
+            Some(filter) => ItemQuery::new()
+                .condition("i.list_id = ?", vec![QueryParam::Int(self.list.id)])
+                .matching(&filter.expr, &Local::now())
+                .fetch(pool)
+                .await?
+                .into_iter()
+                .map(|listed| listed.item)
+                .collect(),
+        };
+
+        Ok(items
+            .into_iter()
+            .map(|item| UIItem {
+                item,
+                state: ListState::default(),
+                origin: None,
+            })
+            .collect())
+    }
+
+    /// Items matching the view, database after database
+    async fn fetch_view_items(&self) -> Result<Vec<UIItem>> {
+        let Some(view) = &self.view else {
+            return Ok(Vec::new());
+        };
+
+        let query = ItemQuery::new().matching(&view.query.expr, &Local::now());
+        let mut items = Vec::new();
+        for source in &view.sources {
+            let listed_items = query.fetch(&source.pool).await.with_context(|| {
+                format!(
+                    "Failed to fetch items of view {} from {}",
+                    view.name, source.db_name
+                )
+            })?;
+
+            items.extend(listed_items.into_iter().map(|listed| UIItem {
+                item: listed.item,
+                state: ListState::default(),
+                origin: Some(ItemOrigin {
+                    source: source.clone(),
+                    list_name: listed.list_name,
+                }),
+            }));
+        }
+        Ok(items)
+    }
+
     /// Update items when something changes (new item, deleted item).
     /// Keeps the same list state instead of reinitializing it
+    #[instrument(level = "debug", skip_all, fields(list_id = self.list.id), err)]
     pub async fn update_items(&mut self, pool: &SqlitePool) -> Result<()> {
         // Re-fetch the items but don't change the list state
-        let items = TodoItem::get_by_list_id(pool, self.list.id)
+        let mut items = self
+            .fetch_items(pool)
             .await
-            .with_context(|| "Failed to fetch items for list")?
-            .iter()
-            .map(|i| UIItem {
-                item: i.clone(),
-                state: self.item_state.clone(),
-            })
-            .collect();
+            .with_context(|| "Failed to fetch items for list")?;
+        for ui_item in &mut items {
+            ui_item.state = self.item_state.clone();
+        }
 
         // Update the items
         self.items = items;
@@ -442,3 +1079,43 @@ impl UIList {
         Ok(())
     }
 }
+
+impl UIItem {
+    /// Pool of the database the item is stored in, `pool` unless it's the item of a view
+    pub fn pool<'a>(&'a self, pool: &'a SqlitePool) -> &'a SqlitePool {
+        match &self.origin {
+            Some(origin) => &origin.source.pool,
+            None => pool,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ordering_key_in_empty_scope() {
+        assert_eq!(ordering_key_at(&[], 0), Some(ORDERING_GAP));
+        assert_eq!(ordering_key_at(&[], 5), Some(ORDERING_GAP));
+    }
+
+    #[test]
+    fn test_ordering_key_at_edges() {
+        let siblings = [1024, 2048, 3072];
+        assert_eq!(ordering_key_at(&siblings, 0), Some(0));
+        assert_eq!(ordering_key_at(&siblings, 3), Some(3072 + ORDERING_GAP));
+        assert_eq!(
+            ordering_key_at(&siblings, usize::MAX),
+            Some(3072 + ORDERING_GAP)
+        );
+    }
+
+    #[test]
+    fn test_ordering_key_between_neighbours() {
+        let siblings = [1024, 2048, 2049];
+        assert_eq!(ordering_key_at(&siblings, 1), Some(1536));
+        // Adjacent keys leave no room
+        assert_eq!(ordering_key_at(&siblings, 2), None);
+    }
+}
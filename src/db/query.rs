@@ -0,0 +1,884 @@
+//! Query language for filtering items.
+//!
+//! A query is a list of conditions, all of which have to match, e.g.
+//! `priority:high due<fri not done list:work`. Conditions can be combined with
+//! `and`, `or`, `not` and parentheses; `and` binds tighter than `or`, and two
+//! conditions next to each other are joined with `and`.
+//!
+//! | Condition                     | Matches items                                 |
+//! |-------------------------------|-----------------------------------------------|
+//! | `word`, `"some words"`        | whose name contains the text, ignoring case   |
+//! | `done`, `open`, `overdue`     | done, not done, not done and past due         |
+//! | `done:yes`, `done:no`         | done, not done                                |
+//! | `name:text`, `name=text`      | whose name contains / is the text             |
+//! | `list:work`                   | of the list with this name, ignoring case     |
+//! | `priority:high`, `priority>low` | by priority (`high`, `medium`, `low`, `none`) |
+//! | `due<fri`, `created>=2025-01-01`, `updated:today` | by date (see below)       |
+//!
+//! Fields are compared with `:` (or `=`), `!=`, `<`, `<=`, `>` and `>=`. Dates are
+//! whole days in the local time zone: `YYYY-MM-DD`, `today`, `tomorrow`,
+//! `yesterday`, a weekday (`fri` or `friday`, the next one counting today) or an
+//! offset in days or weeks from today (`+3d`, `-2w`). `due:none` matches items
+//! without a due date.
+//!
+//! A query is parsed into an [`Expr`], which compiles to an SQL condition over
+//! `todo_items i` joined with `todo_lists l` with its values bound as parameters.
+use crate::db::models::Priority;
+use crate::db::ops::{QueryParam, like_pattern};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc, Weekday};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Error in a query, pointing at the offending characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    /// Character positions of the offending token in the query
+    pub span: Range<usize>,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// The query with the offending token underlined, on two lines
+    pub fn render(&self, query: &str) -> String {
+        format!("{}\n{}", query, self.underline())
+    }
+
+    /// Carets under the offending token, followed by the message
+    pub fn underline(&self) -> String {
+        let width = self.span.len().max(1);
+        format!(
+            "{}{} {}",
+            " ".repeat(self.span.start),
+            "^".repeat(width),
+            self.message
+        )
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (column {})", self.message, self.span.start + 1)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Comparison between a field and a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `:`, "contains" for names, "is" otherwise
+    Has,
+    /// `=`
+    Eq,
+    /// `!=`
+    NotEq,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+impl CompareOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Has => ":",
+            CompareOp::Eq => "=",
+            CompareOp::NotEq => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+
+    fn is_equality(&self) -> bool {
+        matches!(self, CompareOp::Has | CompareOp::Eq | CompareOp::NotEq)
+    }
+}
+
+/// Date column of an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Due,
+    Created,
+    Updated,
+}
+
+impl DateField {
+    fn column(&self) -> &'static str {
+        match self {
+            DateField::Due => "i.due_date",
+            DateField::Created => "i.created_at",
+            DateField::Updated => "i.updated_at",
+        }
+    }
+}
+
+/// Date in a query, resolved against the current day when compiled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateValue {
+    /// No date at all
+    None,
+    Date(NaiveDate),
+    /// Days from today
+    Offset(i64),
+    /// The next such day, counting today
+    Weekday(Weekday),
+}
+
+impl DateValue {
+    fn resolve(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            DateValue::None => None,
+            DateValue::Date(date) => Some(*date),
+            DateValue::Offset(days) => today.checked_add_signed(Duration::days(*days)),
+            DateValue::Weekday(weekday) => {
+                let ahead = (weekday.num_days_from_monday() + 7
+                    - today.weekday().num_days_from_monday())
+                    % 7;
+                today.checked_add_signed(Duration::days(ahead as i64))
+            }
+        }
+    }
+}
+
+/// A single condition of a query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    /// Name contains the text
+    Text(String),
+    Done(bool),
+    /// Not done and due before now
+    Overdue,
+    Name(CompareOp, String),
+    List(CompareOp, String),
+    Priority(CompareOp, Option<Priority>),
+    Date(DateField, CompareOp, DateValue),
+}
+
+/// Parsed query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Term(Term),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+/// A query together with the text it was parsed from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub text: String,
+    pub expr: Expr,
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            text: text.trim().to_string(),
+            expr: Expr::parse(text)?,
+        })
+    }
+}
+
+/// Rank of a priority in comparisons, higher is more important
+const PRIORITY_RANK: &str =
+    "CASE i.priority WHEN 'high' THEN 3 WHEN 'medium' THEN 2 WHEN 'low' THEN 1 ELSE 0 END";
+
+fn priority_rank(priority: &Option<Priority>) -> i64 {
+    match priority {
+        Some(Priority::High) => 3,
+        Some(Priority::Medium) => 2,
+        Some(Priority::Low) => 1,
+        None => 0,
+    }
+}
+
+impl Expr {
+    /// Parse a query
+    pub fn parse(input: &str) -> Result<Expr, QueryError> {
+        let tokens = tokenize(input)?;
+        let end = input.chars().count();
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end,
+        };
+
+        let expr = parser.parse_or()?;
+        match parser.peek() {
+            Some(token) => Err(QueryError::new("Unexpected ')'", token.span.clone())),
+            None => Ok(expr),
+        }
+    }
+
+    /// SQL condition of the query and the parameters to bind, in order. Dates are
+    /// whole days in the time zone of `now`.
+    ///
+    /// Every condition evaluates to true or false (never NULL), so that `not`
+    /// matches exactly the items its operand doesn't.
+    pub fn to_sql<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> (String, Vec<QueryParam>) {
+        let mut params = Vec::new();
+        let sql = self.compile(now, &mut params);
+        (sql, params)
+    }
+
+    fn compile<Tz: TimeZone>(&self, now: &DateTime<Tz>, params: &mut Vec<QueryParam>) -> String {
+        match self {
+            Expr::Term(term) => compile_term(term, now, params),
+            Expr::Not(expr) => format!("NOT {}", expr.compile(now, params)),
+            Expr::And(left, right) => {
+                let left = left.compile(now, params);
+                format!("({} AND {})", left, right.compile(now, params))
+            }
+            Expr::Or(left, right) => {
+                let left = left.compile(now, params);
+                format!("({} OR {})", left, right.compile(now, params))
+            }
+        }
+    }
+}
+
+fn compile_term<Tz: TimeZone>(
+    term: &Term,
+    now: &DateTime<Tz>,
+    params: &mut Vec<QueryParam>,
+) -> String {
+    // Equality with a text column, ignoring case
+    let mut equals = |column: &str, op: CompareOp, text: &str| {
+        params.push(QueryParam::Text(text.to_string()));
+        let sql_op = if op == CompareOp::NotEq { "<>" } else { "=" };
+        format!("({} {} ? COLLATE NOCASE)", column, sql_op)
+    };
+
+    match term {
+        Term::Text(text) | Term::Name(CompareOp::Has, text) => {
+            params.push(QueryParam::Text(like_pattern(text)));
+            "(i.name LIKE ? ESCAPE '\\')".to_string()
+        }
+        Term::Name(op, text) => equals("i.name", *op, text),
+        Term::List(op, name) => equals("l.name", *op, name),
+        Term::Done(done) => {
+            params.push(QueryParam::Int(*done as i64));
+            "(i.is_done = ?)".to_string()
+        }
+        Term::Overdue => {
+            params.push(QueryParam::Time(now.with_timezone(&Utc)));
+            "(i.is_done = 0 AND COALESCE(julianday(i.due_date) < julianday(?), 0))".to_string()
+        }
+        Term::Priority(op, priority) => {
+            params.push(QueryParam::Int(priority_rank(priority)));
+            let sql_op = match op {
+                CompareOp::Has | CompareOp::Eq => "=",
+                other => other.symbol(),
+            };
+            let sql_op = if sql_op == "!=" { "<>" } else { sql_op };
+            format!("({} {} ?)", PRIORITY_RANK, sql_op)
+        }
+        Term::Date(field, op, value) => {
+            let column = field.column();
+            let Some(date) = value.resolve(now.date_naive()) else {
+                // No date: only equality is allowed by the parser
+                return match op {
+                    CompareOp::NotEq => format!("({} IS NOT NULL)", column),
+                    _ => format!("({} IS NULL)", column),
+                };
+            };
+
+            let start = day_start(date, &now.timezone());
+            let end = day_start(date + Duration::days(1), &now.timezone());
+            let compare = |sql_op: &str| {
+                format!("COALESCE(julianday({}) {} julianday(?), 0)", column, sql_op)
+            };
+
+            match op {
+                CompareOp::Has | CompareOp::Eq | CompareOp::NotEq => {
+                    params.push(QueryParam::Time(start));
+                    params.push(QueryParam::Time(end));
+                    let within = format!("({} AND {})", compare(">="), compare("<"));
+                    if *op == CompareOp::NotEq {
+                        format!("NOT {}", within)
+                    } else {
+                        within
+                    }
+                }
+                CompareOp::Lt => {
+                    params.push(QueryParam::Time(start));
+                    compare("<")
+                }
+                CompareOp::Le => {
+                    params.push(QueryParam::Time(end));
+                    compare("<")
+                }
+                CompareOp::Gt => {
+                    params.push(QueryParam::Time(end));
+                    compare(">=")
+                }
+                CompareOp::Ge => {
+                    params.push(QueryParam::Time(start));
+                    compare(">=")
+                }
+            }
+        }
+    }
+}
+
+/// First instant of the day in the given time zone
+fn day_start<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        // Midnight skipped by a DST change: fall back to UTC midnight
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Quoted(String),
+    Op(CompareOp),
+    /// `!` on its own, same as `not`
+    Bang,
+    LParen,
+    RParen,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
+
+impl Token {
+    /// Word in lowercase, if this is a bare word
+    fn keyword(&self) -> Option<String> {
+        match &self.kind {
+            TokenKind::Word(word) => Some(word.to_lowercase()),
+            _ => None,
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | '"' | ':' | '=' | '!' | '<' | '>')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let next = chars.get(i + 1).copied();
+
+        let kind = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            ':' => TokenKind::Op(CompareOp::Has),
+            '=' => TokenKind::Op(CompareOp::Eq),
+            '!' if next == Some('=') => {
+                i += 1;
+                TokenKind::Op(CompareOp::NotEq)
+            }
+            '!' => TokenKind::Bang,
+            '<' | '>' => {
+                let or_equal = next == Some('=');
+                if or_equal {
+                    i += 1;
+                }
+                TokenKind::Op(match (c, or_equal) {
+                    ('<', false) => CompareOp::Lt,
+                    ('<', true) => CompareOp::Le,
+                    ('>', false) => CompareOp::Gt,
+                    _ => CompareOp::Ge,
+                })
+            }
+            '"' => {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(QueryError::new("Unterminated string", start..chars.len()));
+                        }
+                        Some('"') => break,
+                        Some('\\') if matches!(chars.get(i + 1), Some('"' | '\\')) => {
+                            text.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(c) => {
+                            text.push(*c);
+                            i += 1;
+                        }
+                    }
+                }
+                TokenKind::Quoted(text)
+            }
+            _ => {
+                while i + 1 < chars.len() && is_word_char(chars[i + 1]) {
+                    i += 1;
+                }
+                TokenKind::Word(chars[start..=i].iter().collect())
+            }
+        };
+
+        i += 1;
+        tokens.push(Token {
+            kind,
+            span: start..i,
+        });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Length of the query, where errors at the end point
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.peek()
+            .and_then(Token::keyword)
+            .is_some_and(|word| word == keyword)
+    }
+
+    /// Error at the end of the query
+    fn error_at_end(&self, message: &str) -> QueryError {
+        QueryError::new(message, self.end..self.end)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.next();
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            if self.peek_keyword("and") {
+                self.next();
+            } else if self.peek_keyword("or")
+                || matches!(
+                    self.peek(),
+                    None | Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    })
+                )
+            {
+                break;
+            }
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, QueryError> {
+        if self.peek_keyword("not")
+            || matches!(
+                self.peek(),
+                Some(Token {
+                    kind: TokenKind::Bang,
+                    ..
+                })
+            )
+        {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, QueryError> {
+        let Some(token) = self.next() else {
+            return Err(self.error_at_end("Expected a condition"));
+        };
+
+        match token.kind {
+            TokenKind::LParen => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => Ok(expr),
+                    _ => Err(QueryError::new("Unclosed parenthesis", token.span)),
+                }
+            }
+            TokenKind::RParen => Err(QueryError::new("Unexpected ')'", token.span)),
+            TokenKind::Op(op) => Err(QueryError::new(
+                format!("Expected a field before '{}'", op.symbol()),
+                token.span,
+            )),
+            TokenKind::Bang => Err(QueryError::new("Expected a condition", token.span)),
+            TokenKind::Quoted(text) => Ok(Expr::Term(Term::Text(text))),
+            TokenKind::Word(word) => {
+                if let Some(Token {
+                    kind: TokenKind::Op(op),
+                    span: op_span,
+                }) = self.peek().cloned()
+                {
+                    self.next();
+                    let (value, value_span) = match self.next() {
+                        Some(Token {
+                            kind: TokenKind::Word(value) | TokenKind::Quoted(value),
+                            span,
+                        }) => (value, span),
+                        Some(other) => {
+                            return Err(QueryError::new(
+                                format!("Expected a value after '{}'", op.symbol()),
+                                other.span,
+                            ));
+                        }
+                        None => {
+                            return Err(self.error_at_end(&format!(
+                                "Expected a value after '{}'",
+                                op.symbol()
+                            )));
+                        }
+                    };
+                    return field_term(&word, token.span, op, op_span, &value, value_span)
+                        .map(Expr::Term);
+                }
+
+                match word.to_lowercase().as_str() {
+                    "done" => Ok(Expr::Term(Term::Done(true))),
+                    "open" => Ok(Expr::Term(Term::Done(false))),
+                    "overdue" => Ok(Expr::Term(Term::Overdue)),
+                    keyword @ ("and" | "or") => Err(QueryError::new(
+                        format!("Expected a condition before '{}'", keyword),
+                        token.span,
+                    )),
+                    _ => Ok(Expr::Term(Term::Text(word))),
+                }
+            }
+        }
+    }
+}
+
+/// Condition `field op value`, checking that the value makes sense for the field
+fn field_term(
+    field: &str,
+    field_span: Range<usize>,
+    op: CompareOp,
+    op_span: Range<usize>,
+    value: &str,
+    value_span: Range<usize>,
+) -> Result<Term, QueryError> {
+    let field = field.to_lowercase();
+    let equality_only = |what: &str| {
+        if op.is_equality() {
+            Ok(())
+        } else {
+            Err(QueryError::new(
+                format!(
+                    "{} can't be compared with '{}', use ':' or '!='",
+                    what,
+                    op.symbol()
+                ),
+                op_span.clone(),
+            ))
+        }
+    };
+
+    match field.as_str() {
+        "name" => {
+            equality_only("A name")?;
+            Ok(Term::Name(op, value.to_string()))
+        }
+        "list" => {
+            equality_only("A list")?;
+            Ok(Term::List(op, value.to_string()))
+        }
+        "done" => {
+            equality_only("Done")?;
+            let done = match value.to_lowercase().as_str() {
+                "yes" | "true" => true,
+                "no" | "false" => false,
+                _ => {
+                    return Err(QueryError::new(
+                        format!("Invalid value '{}', expected yes or no", value),
+                        value_span,
+                    ));
+                }
+            };
+            Ok(Term::Done(done != (op == CompareOp::NotEq)))
+        }
+        "priority" => {
+            let priority = match value.to_lowercase().as_str() {
+                "high" => Some(Priority::High),
+                "medium" => Some(Priority::Medium),
+                "low" => Some(Priority::Low),
+                "none" => None,
+                _ => {
+                    return Err(QueryError::new(
+                        format!(
+                            "Unknown priority '{}', expected high, medium, low or none",
+                            value
+                        ),
+                        value_span,
+                    ));
+                }
+            };
+            Ok(Term::Priority(op, priority))
+        }
+        "due" | "created" | "updated" => {
+            let date_field = match field.as_str() {
+                "due" => DateField::Due,
+                "created" => DateField::Created,
+                _ => DateField::Updated,
+            };
+            let date = parse_date_value(value).ok_or_else(|| {
+                QueryError::new(
+                    format!(
+                        "Invalid date '{}', expected YYYY-MM-DD, today, tomorrow, yesterday, \
+                         a weekday or an offset like +3d",
+                        value
+                    ),
+                    value_span.clone(),
+                )
+            })?;
+            if date == DateValue::None && !op.is_equality() {
+                return Err(QueryError::new(
+                    format!("'none' can't be compared with '{}'", op.symbol()),
+                    value_span,
+                ));
+            }
+            Ok(Term::Date(date_field, op, date))
+        }
+        _ => Err(QueryError::new(
+            format!(
+                "Unknown field '{}', expected name, list, done, priority, due, created or updated",
+                field
+            ),
+            field_span,
+        )),
+    }
+}
+
+fn parse_date_value(value: &str) -> Option<DateValue> {
+    let value = value.to_lowercase();
+    let date = match value.as_str() {
+        "none" => DateValue::None,
+        "today" => DateValue::Offset(0),
+        "tomorrow" => DateValue::Offset(1),
+        "yesterday" => DateValue::Offset(-1),
+        _ => {
+            if let Ok(date) = NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+                return Some(DateValue::Date(date));
+            }
+            if let Ok(weekday) = value.parse::<Weekday>() {
+                return Some(DateValue::Weekday(weekday));
+            }
+
+            // Offset like +3d or -2w
+            let (sign, rest) = match value.strip_prefix('-') {
+                Some(rest) => (-1, rest),
+                None => (1, value.strip_prefix('+').unwrap_or(&value)),
+            };
+            let unit = match rest.chars().last()? {
+                'd' => 1,
+                'w' => 7,
+                _ => return None,
+            };
+            let count: i64 = rest[..rest.len() - 1].parse().ok()?;
+            DateValue::Offset(sign * count.checked_mul(unit)?)
+        }
+    };
+    Some(date)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(query: &str) -> Expr {
+        Expr::parse(query).unwrap()
+    }
+
+    fn error(query: &str) -> QueryError {
+        Expr::parse(query).unwrap_err()
+    }
+
+    fn term(term: Term) -> Box<Expr> {
+        Box::new(Expr::Term(term))
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            parse("priority:high and due<fri and not done and list:work"),
+            Expr::And(
+                Box::new(Expr::And(
+                    Box::new(Expr::And(
+                        term(Term::Priority(CompareOp::Has, Some(Priority::High))),
+                        term(Term::Date(
+                            DateField::Due,
+                            CompareOp::Lt,
+                            DateValue::Weekday(Weekday::Fri)
+                        )),
+                    )),
+                    Box::new(Expr::Not(term(Term::Done(true)))),
+                )),
+                term(Term::List(CompareOp::Has, "work".to_string())),
+            )
+        );
+
+        // Juxtaposition is "and", which binds tighter than "or"
+        assert_eq!(
+            parse("a b or c"),
+            Expr::Or(
+                Box::new(Expr::And(
+                    term(Term::Text("a".to_string())),
+                    term(Term::Text("b".to_string()))
+                )),
+                term(Term::Text("c".to_string())),
+            )
+        );
+        assert_eq!(
+            parse("!(a or \"b c\")"),
+            Expr::Not(Box::new(Expr::Or(
+                term(Term::Text("a".to_string())),
+                term(Term::Text("b c".to_string())),
+            )))
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eq!(parse("done:no"), *term(Term::Done(false)));
+        assert_eq!(parse("done!=no"), *term(Term::Done(true)));
+        assert_eq!(
+            parse("due:none"),
+            *term(Term::Date(DateField::Due, CompareOp::Has, DateValue::None))
+        );
+        assert_eq!(
+            parse("created>=-2w"),
+            *term(Term::Date(
+                DateField::Created,
+                CompareOp::Ge,
+                DateValue::Offset(-14)
+            ))
+        );
+        assert_eq!(
+            parse("updated<=2025-03-01"),
+            *term(Term::Date(
+                DateField::Updated,
+                CompareOp::Le,
+                DateValue::Date(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap())
+            ))
+        );
+        assert_eq!(
+            parse("list:\"side projects\""),
+            *term(Term::List(CompareOp::Has, "side projects".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_errors_point_at_token() {
+        let e = error("priority:urgent and done");
+        assert_eq!(e.span, 9..15);
+        assert_eq!(
+            e.render("priority:urgent and done"),
+            "priority:urgent and done\n         ^^^^^^ Unknown priority 'urgent', expected high, medium, low or none"
+        );
+
+        assert_eq!(error("prio:high").span, 0..4);
+        assert_eq!(error("due<someday").span, 4..11);
+        assert_eq!(error("list<work").span, 4..5);
+        assert_eq!(error("due>none").span, 4..8);
+        assert_eq!(error("(done or open").span, 0..1);
+        assert_eq!(error("done)").span, 4..5);
+        assert_eq!(error("done and").span, 8..8);
+        assert_eq!(error("name:").span, 5..5);
+        assert_eq!(error("and done").span, 0..3);
+        assert_eq!(error("\"open").span, 0..5);
+        assert_eq!(error("").span, 0..0);
+        // Positions count characters, not bytes
+        assert_eq!(error("café prio:x").span, 5..9);
+    }
+
+    #[test]
+    fn test_names_only_compare_for_equality() {
+        let e = error("name>milk");
+        assert_eq!(e.span, 4..5);
+        assert_eq!(
+            e.message,
+            "A name can't be compared with '>', use ':' or '!='"
+        );
+        assert_eq!(
+            parse("name=milk"),
+            *term(Term::Name(CompareOp::Eq, "milk".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_weekdays_count_today() {
+        // 2025-01-03 is a Friday
+        let friday = NaiveDate::from_ymd_opt(2025, 1, 3).unwrap();
+        assert_eq!(
+            DateValue::Weekday(Weekday::Fri).resolve(friday),
+            Some(friday)
+        );
+        assert_eq!(
+            DateValue::Weekday(Weekday::Mon).resolve(friday),
+            NaiveDate::from_ymd_opt(2025, 1, 6)
+        );
+    }
+
+    #[test]
+    fn test_sql_binds_values() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 3, 12, 0, 0).unwrap();
+        let (sql, params) = parse("list:\"x' OR 1=1\" and due:today").to_sql(&now);
+        assert!(!sql.contains("OR 1=1"));
+        assert_eq!(
+            params,
+            vec![
+                QueryParam::Text("x' OR 1=1".to_string()),
+                QueryParam::Time(Utc.with_ymd_and_hms(2025, 1, 3, 0, 0, 0).unwrap()),
+                QueryParam::Time(Utc.with_ymd_and_hms(2025, 1, 4, 0, 0, 0).unwrap()),
+            ]
+        );
+    }
+}
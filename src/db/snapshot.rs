@@ -0,0 +1,415 @@
+//! Whole-database snapshots, written by `judo export` and read by `judo import`.
+//!
+//! A snapshot is a versioned JSON document holding every list of a database
+//! with its items, all their fields and their ordering keys:
+//!
+//! ```json
+//! {
+//!   "format": "judo",
+//!   "version": 1,
+//!   "exported_at": "2025-10-18T09:30:00Z",
+//!   "lists": [
+//!     {
+//!       "id": 1, "name": "Chores", "ordering": 1024,
+//!       "created_at": "...", "updated_at": "...",
+//!       "items": [
+//!         {
+//!           "id": 4, "name": "Laundry", "is_done": false, "priority": "high",
+//!           "due_date": null, "ordering": 1024, "created_at": "...", "updated_at": "..."
+//!         }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! IDs are only kept for reference: importing always assigns new IDs, and
+//! reports how the IDs of the snapshot map to them. Snapshots read from formats
+//! without timestamps (see [`crate::formats`]) leave them out, and importing
+//! stamps the lists and items with the current time.
+use crate::db::models::{Priority, TodoItem, TodoList};
+use crate::db::ops::ORDERING_GAP;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::instrument;
+
+/// Value of the `format` field, telling snapshots from other JSON documents
+pub const SNAPSHOT_FORMAT: &str = "judo";
+
+/// Version of the snapshot document written by this version of judo.
+/// Bump it whenever the document changes, older snapshots must stay readable.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// All lists and items of a database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub lists: Vec<ListSnapshot>,
+}
+
+/// A list of a snapshot, with its items
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListSnapshot {
+    pub id: i64,
+    pub name: String,
+    pub ordering: i64,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    pub items: Vec<ItemSnapshot>,
+}
+
+/// An item of a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemSnapshot {
+    pub id: i64,
+    pub name: String,
+    pub is_done: bool,
+    pub priority: Option<Priority>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub ordering: i64,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// What happens to the lists and items already in the database when importing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Keep them. Lists of the snapshot are merged into the lists with the same
+    /// name, skipping the items they already have
+    #[default]
+    Merge,
+    /// Delete them, leaving only the lists and items of the snapshot
+    Replace,
+}
+
+impl FromStr for ImportMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "merge" => Ok(ImportMode::Merge),
+            "replace" => Ok(ImportMode::Replace),
+            _ => bail!("Unknown import mode '{}', expected merge or replace", s),
+        }
+    }
+}
+
+/// Outcome of an import
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Lists created
+    pub lists_created: usize,
+    /// Lists of the snapshot merged into an existing list with the same name
+    pub lists_merged: usize,
+    /// Items created
+    pub items_created: usize,
+    /// Items skipped because the list they were merged into already had them
+    pub items_skipped: usize,
+    /// New ID of each list of the snapshot, by its ID in the snapshot
+    pub list_ids: HashMap<i64, i64>,
+    /// New ID of each item created, by its ID in the snapshot
+    pub item_ids: HashMap<i64, i64>,
+}
+
+impl From<&TodoItem> for ItemSnapshot {
+    fn from(item: &TodoItem) -> Self {
+        ItemSnapshot {
+            id: item.id,
+            name: item.name.clone(),
+            is_done: item.is_done,
+            priority: item.priority.clone(),
+            due_date: item.due_date,
+            ordering: item.ordering,
+            created_at: Some(item.created_at),
+            updated_at: Some(item.updated_at),
+        }
+    }
+}
+
+impl ItemSnapshot {
+    /// An open item with nothing but a name, numbered and placed when added to a list
+    pub fn new(name: impl Into<String>) -> Self {
+        ItemSnapshot {
+            id: 0,
+            name: name.into(),
+            is_done: false,
+            priority: None,
+            due_date: None,
+            ordering: 0,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    /// Whether this is one of the given items. Items of the same origin share their
+    /// name and creation day, so an item imported twice is recognised however it
+    /// was changed in the meantime. Days rather than times are compared since some
+    /// formats only keep the day; without a creation time the name has to do.
+    fn is_among(&self, items: &[TodoItem]) -> bool {
+        let day = |time: DateTime<Utc>| time.with_timezone(&Local).date_naive();
+        items.iter().any(|item| {
+            item.name == self.name
+                && self
+                    .created_at
+                    .is_none_or(|time| day(time) == day(item.created_at))
+        })
+    }
+}
+
+impl Snapshot {
+    /// A snapshot of the given lists, taken now
+    pub fn new(lists: Vec<ListSnapshot>) -> Snapshot {
+        Snapshot {
+            format: SNAPSHOT_FORMAT.to_string(),
+            version: SNAPSHOT_VERSION,
+            exported_at: Utc::now(),
+            lists,
+        }
+    }
+
+    /// Take a snapshot of every list and item of the database
+    #[instrument(level = "debug", skip_all, err)]
+    pub async fn from_db(pool: &SqlitePool) -> Result<Snapshot> {
+        let mut lists = Vec::new();
+        for list in TodoList::get_all(pool).await? {
+            let items = list.get_all_items(pool).await?;
+            lists.push(ListSnapshot {
+                id: list.id,
+                name: list.name,
+                ordering: list.ordering,
+                created_at: Some(list.created_at),
+                updated_at: Some(list.updated_at),
+                items: items.iter().map(ItemSnapshot::from).collect(),
+            });
+        }
+
+        Ok(Snapshot::new(lists))
+    }
+
+    /// Pretty-printed JSON document of the snapshot
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).with_context(|| "Failed to serialize snapshot")
+    }
+
+    /// Read a snapshot from a JSON document, refusing documents of newer versions
+    pub fn from_json(json: &str) -> Result<Snapshot> {
+        // Look at the header first, a newer document may not match the fields below
+        #[derive(Deserialize)]
+        struct Header {
+            format: Option<String>,
+            version: Option<u32>,
+        }
+        let header: Header =
+            serde_json::from_str(json).with_context(|| "Not a judo export (invalid JSON)")?;
+
+        match (header.format.as_deref(), header.version) {
+            (Some(SNAPSHOT_FORMAT), Some(version)) if version > SNAPSHOT_VERSION => bail!(
+                "The export has version {} but this version of judo reads up to version {}, please upgrade judo",
+                version,
+                SNAPSHOT_VERSION
+            ),
+            (Some(SNAPSHOT_FORMAT), Some(_)) => {}
+            _ => bail!("Not a judo export (missing \"format\": \"judo\" or \"version\")"),
+        }
+
+        serde_json::from_str(json).with_context(|| "Invalid judo export")
+    }
+
+    /// Write the lists and items of the snapshot to the database, in a single transaction
+    #[instrument(level = "debug", skip(self, pool), fields(lists = self.lists.len()), err)]
+    pub async fn import(&self, pool: &SqlitePool, mode: ImportMode) -> Result<ImportSummary> {
+        let mut tx = pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .with_context(|| "Failed to begin import")?;
+
+        if mode == ImportMode::Replace {
+            sqlx::query("DELETE FROM todo_items")
+                .execute(&mut *tx)
+                .await
+                .with_context(|| "Failed to delete todo items")?;
+            sqlx::query("DELETE FROM todo_lists")
+                .execute(&mut *tx)
+                .await
+                .with_context(|| "Failed to delete todo lists")?;
+        }
+
+        let mut summary = ImportSummary::default();
+        let mut lists: Vec<&ListSnapshot> = self.lists.iter().collect();
+        lists.sort_by_key(|list| list.ordering);
+
+        for list in lists {
+            let existing: Option<i64> = match mode {
+                ImportMode::Merge => sqlx::query_scalar(
+                    "SELECT id FROM todo_lists WHERE name = ?1 ORDER BY ordering LIMIT 1",
+                )
+                .bind(&list.name)
+                .fetch_optional(&mut *tx)
+                .await
+                .with_context(|| "Failed to look up todo list")?,
+                ImportMode::Replace => None,
+            };
+
+            let mut items: Vec<&ItemSnapshot> = list.items.iter().collect();
+            items.sort_by_key(|item| item.ordering);
+
+            match existing {
+                Some(list_id) => {
+                    summary.lists_merged += 1;
+                    summary.list_ids.insert(list.id, list_id);
+                    merge_items(&mut tx, list_id, &items, &mut summary).await?;
+                }
+                None => {
+                    let list_id = insert_list(&mut tx, list, mode).await?;
+                    summary.lists_created += 1;
+                    summary.list_ids.insert(list.id, list_id);
+                    for item in items {
+                        let item_id = insert_item(&mut tx, list_id, item, item.ordering).await?;
+                        summary.items_created += 1;
+                        summary.item_ids.insert(item.id, item_id);
+                    }
+                }
+            }
+        }
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit import")?;
+
+        Ok(summary)
+    }
+}
+
+/// Insert a list of a snapshot, returning its new ID.
+/// Replacing keeps its ordering key, merging places it after the lists already there.
+async fn insert_list(
+    conn: &mut SqliteConnection,
+    list: &ListSnapshot,
+    mode: ImportMode,
+) -> Result<i64> {
+    let now = Utc::now();
+    let ordering = match mode {
+        ImportMode::Replace => list.ordering,
+        ImportMode::Merge => {
+            let max: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(ordering), 0) FROM todo_lists")
+                .fetch_one(&mut *conn)
+                .await
+                .with_context(|| "Failed to read list ordering")?;
+            max + ORDERING_GAP
+        }
+    };
+
+    let id = sqlx::query_scalar(
+        r#"
+        INSERT INTO todo_lists (name, ordering, created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4)
+        RETURNING id
+        "#,
+    )
+    .bind(&list.name)
+    .bind(ordering)
+    .bind(list.created_at.unwrap_or(now))
+    .bind(list.updated_at.unwrap_or(now))
+    .fetch_one(&mut *conn)
+    .await
+    .with_context(|| format!("Failed to import todo list {}", list.name))?;
+
+    Ok(id)
+}
+
+/// Insert an item of a snapshot into a list with the given ordering key, returning its new ID
+async fn insert_item(
+    conn: &mut SqliteConnection,
+    list_id: i64,
+    item: &ItemSnapshot,
+    ordering: i64,
+) -> Result<i64> {
+    let now = Utc::now();
+    let id = sqlx::query_scalar(
+        r#"
+        INSERT INTO todo_items (list_id, name, is_done, priority, due_date, ordering, created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        RETURNING id
+        "#,
+    )
+    .bind(list_id)
+    .bind(&item.name)
+    .bind(item.is_done)
+    .bind(&item.priority)
+    .bind(item.due_date)
+    .bind(ordering)
+    .bind(item.created_at.unwrap_or(now))
+    .bind(item.updated_at.unwrap_or(now))
+    .fetch_one(&mut *conn)
+    .await
+    .with_context(|| format!("Failed to import todo item {}", item.name))?;
+
+    Ok(id)
+}
+
+/// Append the items a list doesn't have yet after its own items
+async fn merge_items(
+    conn: &mut SqliteConnection,
+    list_id: i64,
+    items: &[&ItemSnapshot],
+    summary: &mut ImportSummary,
+) -> Result<()> {
+    let present: Vec<TodoItem> = sqlx::query_as(
+        r#"
+        SELECT id, list_id, name, is_done, priority, due_date, ordering, created_at, updated_at
+        FROM todo_items
+        WHERE list_id = ?1
+        "#,
+    )
+    .bind(list_id)
+    .fetch_all(&mut *conn)
+    .await
+    .with_context(|| "Failed to fetch todo items")?;
+
+    let mut ordering = present.iter().map(|item| item.ordering).max().unwrap_or(0);
+
+    for item in items {
+        if item.is_among(&present) {
+            summary.items_skipped += 1;
+            continue;
+        }
+        ordering += ORDERING_GAP;
+        let item_id = insert_item(conn, list_id, item, ordering).await?;
+        summary.items_created += 1;
+        summary.item_ids.insert(item.id, item_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_json_checks_the_header() {
+        let newer = r#"{"format": "judo", "version": 99, "lists": "changed"}"#;
+        let e = Snapshot::from_json(newer).unwrap_err();
+        assert!(e.to_string().contains("version 99"), "{e}");
+
+        let other = r#"{"name": "not an export"}"#;
+        let e = Snapshot::from_json(other).unwrap_err();
+        assert!(e.to_string().starts_with("Not a judo export"), "{e}");
+
+        assert!(Snapshot::from_json("lists:").is_err());
+
+        let empty = r#"{"format": "judo", "version": 1, "exported_at": "2025-10-18T09:30:00Z", "lists": []}"#;
+        let snapshot = Snapshot::from_json(empty).unwrap();
+        assert_eq!(snapshot.version, 1);
+        assert!(snapshot.lists.is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! Errors reported to the user by the command line interface.
+//!
+//! Every variant maps to its own process exit code, so scripts can tell a typo
+//! in a list name from a broken database:
+//!
+//! | Error            | Exit code | Meaning                                          |
+//! |------------------|-----------|--------------------------------------------------|
+//! | `InvalidInput`   | 64        | Arguments that can't be used together            |
+//! | `AmbiguousName`  | 65        | A name matches more than one list or database    |
+//! | `NotFound`       | 66        | No database, list or item with that name or ID   |
+//! | `Db`             | 69        | The database can't be opened, read or written    |
+//! | `Io`             | 74        | A file or folder can't be read or written        |
+//! | `Sync`           | 76        | A sync with a server failed                      |
+//! | `ConfigInvalid`  | 78        | judo.toml can't be parsed or is inconsistent     |
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub type Result<T, E = JudoError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum JudoError {
+    /// Arguments that can't be used together, or are missing
+    #[error("{0}")]
+    InvalidInput(String),
+
+    /// A name matching more than one thing, e.g. "List with name 'x' in database 'y'"
+    #[error("{0} is ambiguous, {1}")]
+    AmbiguousName(String, String),
+
+    /// Something that doesn't exist, e.g. "Item with ID '3' in database 'y'"
+    #[error("{0} not found")]
+    NotFound(String),
+
+    /// Invalid or inconsistent judo.toml
+    #[error("Invalid configuration: {0}")]
+    ConfigInvalid(String),
+
+    /// Failure of the database layer
+    #[error("{0:#}")]
+    Db(anyhow::Error),
+
+    /// Failure talking to a server a database is synced with
+    #[error("Sync failed: {0:#}")]
+    Sync(anyhow::Error),
+
+    /// Failure reading or writing a file or folder
+    #[error("Failed to access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl JudoError {
+    /// I/O error on the given path, for use with `map_err`
+    pub fn io(path: impl AsRef<Path>) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+        move |source| JudoError::Io { path, source }
+    }
+
+    /// Failure of the database layer working on files, an I/O error on the
+    /// given path if that's what it comes down to, for use with `map_err`
+    pub fn db_files(path: impl AsRef<Path>) -> impl FnOnce(anyhow::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+        move |e| match e.downcast::<std::io::Error>() {
+            Ok(source) => JudoError::Io { path, source },
+            Err(e) => JudoError::Db(e),
+        }
+    }
+
+    /// Exit code of the process when failing with this error
+    pub fn exit_code(&self) -> exitcode::ExitCode {
+        match self {
+            JudoError::InvalidInput(_) => exitcode::USAGE,
+            JudoError::AmbiguousName(..) => exitcode::DATAERR,
+            JudoError::NotFound(_) => exitcode::NOINPUT,
+            JudoError::Db(_) => exitcode::UNAVAILABLE,
+            JudoError::Io { .. } => exitcode::IOERR,
+            JudoError::Sync(_) => exitcode::PROTOCOL,
+            JudoError::ConfigInvalid(_) => exitcode::CONFIG,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let errors = [
+            JudoError::InvalidInput(String::new()),
+            JudoError::AmbiguousName(String::new(), String::new()),
+            JudoError::NotFound(String::new()),
+            JudoError::Db(anyhow::anyhow!("locked")),
+            JudoError::io("judo.toml")(std::io::Error::other("disk full")),
+            JudoError::Sync(anyhow::anyhow!("connection refused")),
+            JudoError::ConfigInvalid(String::new()),
+        ];
+        let mut codes: Vec<_> = errors.iter().map(JudoError::exit_code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    #[test]
+    fn test_db_files_tells_io_errors() {
+        let e = JudoError::db_files("backups")(
+            anyhow::Error::new(std::io::Error::other("disk full")).context("Failed to prune"),
+        );
+        assert_eq!(e.exit_code(), exitcode::IOERR);
+        assert_eq!(e.to_string(), "Failed to access backups: disk full");
+
+        let e = JudoError::db_files("backups")(anyhow::anyhow!("database is locked"));
+        assert_eq!(e.exit_code(), exitcode::UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_messages() {
+        let e = JudoError::NotFound("List with name 'Chores' in database 'dojo'".to_string());
+        assert_eq!(
+            e.to_string(),
+            "List with name 'Chores' in database 'dojo' not found"
+        );
+
+        let e = JudoError::Db(
+            anyhow::anyhow!("database is locked").context("Failed to create DB pool"),
+        );
+        assert_eq!(
+            e.to_string(),
+            "Failed to create DB pool: database is locked"
+        );
+
+        let e = JudoError::io("/tmp/judo.toml")(std::io::Error::other("disk full"));
+        assert_eq!(e.to_string(), "Failed to access /tmp/judo.toml: disk full");
+    }
+}
@@ -0,0 +1,567 @@
+//! iCalendar (RFC 5545) to-dos, the format calendar apps and CalDAV servers use.
+//!
+//! ```text
+//! BEGIN:VCALENDAR
+//! VERSION:2.0
+//! PRODID:-//judo//judo//EN
+//! BEGIN:VTODO
+//! UID:1760779800000000-4@judo
+//! DTSTAMP:20251018T093000Z
+//! SUMMARY:Laundry
+//! CATEGORIES:Chores
+//! PRIORITY:1
+//! STATUS:NEEDS-ACTION
+//! DUE;VALUE=DATE:20251101
+//! CREATED:20251018T093000Z
+//! LAST-MODIFIED:20251018T093000Z
+//! END:VTODO
+//! END:VCALENDAR
+//! ```
+//!
+//! Every item is a `VTODO` whose `CATEGORIES` is the name of its list, with a
+//! `UID` that stays the same as long as the item exists. Priorities 1 to 4 are
+//! high, 5 medium and 6 to 9 low, a due date at local midnight is written as a
+//! day, and done items are `COMPLETED` when they were last changed (judo doesn't
+//! record when an item was done). Reading, to-dos without categories go to the
+//! list named by the calendar's `X-WR-CALNAME`, or "Imported"; events, alarms
+//! and properties judo has no place for are skipped.
+use crate::db::models::Priority;
+use crate::db::snapshot::{ItemSnapshot, Snapshot};
+use crate::formats::{SnapshotBuilder, day_start};
+use anyhow::{Result, bail};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+/// List of the to-dos without categories, if the calendar has no name either
+const UNNAMED_LIST: &str = "Imported";
+
+/// Lines longer than this many bytes are folded
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Parameters of a property, e.g. `VALUE=DATE`
+type Params = Vec<(String, String)>;
+
+/// A to-do read from a calendar
+#[derive(Debug, Clone, PartialEq)]
+pub struct Todo {
+    pub uid: Option<String>,
+    /// First of the `CATEGORIES`
+    pub category: Option<String>,
+    pub item: ItemSnapshot,
+}
+
+/// The to-dos of a calendar
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Calendar {
+    /// The `X-WR-CALNAME` of the calendar
+    pub name: Option<String>,
+    pub todos: Vec<Todo>,
+}
+
+/// UID of an item, made of its ID and creation time so that it's unique
+/// across databases and never reused
+pub fn item_uid(item: &ItemSnapshot) -> String {
+    let created = item.created_at.map_or(0, |time| time.timestamp_micros());
+    format!("{}-{}@judo", created, item.id)
+}
+
+/// Write every item of a snapshot as a to-do of a calendar with the given name
+pub fn to_ical(snapshot: &Snapshot, name: Option<&str>) -> String {
+    let mut out = String::new();
+    begin_calendar(&mut out, name);
+    for list in &snapshot.lists {
+        for item in &list.items {
+            write_todo(&mut out, &item_uid(item), Some(&list.name), item);
+        }
+    }
+    end_calendar(&mut out);
+    out
+}
+
+/// A calendar holding a single to-do, as stored by CalDAV servers
+pub fn todo_to_ical(uid: &str, list_name: Option<&str>, item: &ItemSnapshot) -> String {
+    let mut out = String::new();
+    begin_calendar(&mut out, None);
+    write_todo(&mut out, uid, list_name, item);
+    end_calendar(&mut out);
+    out
+}
+
+/// Read the to-dos of an iCalendar file into lists
+pub fn from_ical(text: &str) -> Result<Snapshot> {
+    let calendar = parse_calendar(text)?;
+    let mut builder = SnapshotBuilder::default();
+    for todo in calendar.todos {
+        if todo.item.name.is_empty() {
+            continue;
+        }
+        let list_name = todo
+            .category
+            .or_else(|| calendar.name.clone())
+            .unwrap_or_else(|| UNNAMED_LIST.to_string());
+        let list = builder.list(&list_name);
+        builder.push_item(list, todo.item);
+    }
+    Ok(builder.build())
+}
+
+/// Read the to-dos of an iCalendar file
+pub fn parse_calendar(text: &str) -> Result<Calendar> {
+    let mut calendar = Calendar::default();
+    let mut seen_calendar = false;
+    // Components we are in, e.g. ["VCALENDAR", "VTODO", "VALARM"]
+    let mut components: Vec<String> = Vec::new();
+    let mut todo: Option<Todo> = None;
+
+    for line in unfold(text) {
+        let Some((name, params, value)) = parse_line(&line) else {
+            continue;
+        };
+
+        match name.as_str() {
+            "BEGIN" => {
+                let component = value.to_ascii_uppercase();
+                seen_calendar |= component == "VCALENDAR";
+                if component == "VTODO" && components.len() == 1 {
+                    todo = Some(Todo {
+                        uid: None,
+                        category: None,
+                        item: ItemSnapshot::new(""),
+                    });
+                }
+                components.push(component);
+            }
+            "END" => {
+                if components.pop().as_deref() == Some("VTODO")
+                    && components.len() == 1
+                    && let Some(todo) = todo.take()
+                {
+                    calendar.todos.push(todo);
+                }
+            }
+            _ => match components.len() {
+                1 if name == "X-WR-CALNAME" => calendar.name = Some(unescape(&value)),
+                2 => {
+                    if let Some(todo) = &mut todo {
+                        set_property(todo, &name, &params, &value);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    if !seen_calendar {
+        bail!("Not an iCalendar file (no BEGIN:VCALENDAR)");
+    }
+    Ok(calendar)
+}
+
+fn begin_calendar(out: &mut String, name: Option<&str>) {
+    write_line(out, "BEGIN:VCALENDAR");
+    write_line(out, "VERSION:2.0");
+    write_line(out, "PRODID:-//judo//judo//EN");
+    if let Some(name) = name {
+        write_line(out, &format!("X-WR-CALNAME:{}", escape(name)));
+    }
+}
+
+fn end_calendar(out: &mut String) {
+    write_line(out, "END:VCALENDAR");
+}
+
+fn write_todo(out: &mut String, uid: &str, list_name: Option<&str>, item: &ItemSnapshot) {
+    write_line(out, "BEGIN:VTODO");
+    write_line(out, &format!("UID:{}", escape(uid)));
+    write_line(out, &format!("DTSTAMP:{}", format_time(Utc::now())));
+    write_line(out, &format!("SUMMARY:{}", escape(&item.name)));
+    if let Some(list_name) = list_name {
+        write_line(out, &format!("CATEGORIES:{}", escape(list_name)));
+    }
+    if let Some(priority) = &item.priority {
+        let value = match priority {
+            Priority::High => 1,
+            Priority::Medium => 5,
+            Priority::Low => 9,
+        };
+        write_line(out, &format!("PRIORITY:{}", value));
+    }
+    if item.is_done {
+        write_line(out, "STATUS:COMPLETED");
+        if let Some(completed) = item.updated_at.or(item.created_at) {
+            write_line(out, &format!("COMPLETED:{}", format_time(completed)));
+        }
+    } else {
+        write_line(out, "STATUS:NEEDS-ACTION");
+    }
+    if let Some(due) = item.due_date {
+        let local = due.with_timezone(&Local);
+        if local.time() == NaiveTime::MIN {
+            write_line(out, &format!("DUE;VALUE=DATE:{}", local.format("%Y%m%d")));
+        } else {
+            write_line(out, &format!("DUE:{}", format_time(due)));
+        }
+    }
+    if let Some(created) = item.created_at {
+        write_line(out, &format!("CREATED:{}", format_time(created)));
+    }
+    if let Some(updated) = item.updated_at {
+        write_line(out, &format!("LAST-MODIFIED:{}", format_time(updated)));
+    }
+    write_line(out, "END:VTODO");
+}
+
+// Set the field of the item matching a property of its VTODO
+fn set_property(todo: &mut Todo, name: &str, params: &[(String, String)], value: &str) {
+    let item = &mut todo.item;
+    match name {
+        "UID" => todo.uid = Some(unescape(value)),
+        "SUMMARY" => item.name = unescape(value),
+        "CATEGORIES" => {
+            todo.category = split_list(value).into_iter().find(|c| !c.is_empty());
+        }
+        "PRIORITY" => {
+            item.priority = match value.trim().parse::<u8>() {
+                Ok(1..=4) => Some(Priority::High),
+                Ok(5) => Some(Priority::Medium),
+                Ok(6..=9) => Some(Priority::Low),
+                _ => None,
+            }
+        }
+        "STATUS" => item.is_done = value.trim().eq_ignore_ascii_case("COMPLETED"),
+        "COMPLETED" => {
+            item.is_done = true;
+            if item.updated_at.is_none() {
+                item.updated_at = parse_time(params, value);
+            }
+        }
+        "DUE" => item.due_date = parse_time(params, value),
+        "CREATED" => item.created_at = parse_time(params, value),
+        "LAST-MODIFIED" => item.updated_at = parse_time(params, value),
+        _ => {}
+    }
+}
+
+// Join folded lines, which continue on lines starting with a space or a tab
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+// Name (upper case), parameters and value of a content line, e.g.
+// "DUE;VALUE=DATE:20251101"
+fn parse_line(line: &str) -> Option<(String, Params, String)> {
+    // The value starts at the first colon outside of a quoted parameter value
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+
+    let mut parts = line[..colon].split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.to_ascii_uppercase(),
+                value.trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+    Some((name, params, line[colon + 1..].to_string()))
+}
+
+// A DATE (local midnight), DATE-TIME in UTC, or DATE-TIME in local time
+// (time zones other than UTC are read as the local one)
+fn parse_time(params: &[(String, String)], value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    let is_date = params
+        .iter()
+        .any(|(key, value)| key == "VALUE" && value.eq_ignore_ascii_case("DATE"));
+    if is_date || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .and_then(day_start);
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&time));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    time.and_local_timezone(Local)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// TEXT values escape backslashes, semicolons, commas and newlines
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+// The values of a comma separated list, unescaped
+fn split_list(text: &str) -> Vec<String> {
+    let mut values = vec![String::new()];
+    let mut escaped = false;
+    for c in text.chars() {
+        if c == ',' && !escaped {
+            values.push(String::new());
+            continue;
+        }
+        // Escapes are kept for unescape, only unescaped commas split values
+        escaped = c == '\\' && !escaped;
+        values.last_mut().unwrap().push(c);
+    }
+    values
+        .iter()
+        .map(|value| unescape(value).trim().to_string())
+        .collect()
+}
+
+// A content line, folded into lines of at most MAX_LINE_LENGTH bytes and ended with CRLF
+fn write_line(out: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            out.push_str("\r\n ");
+            length = 1;
+        }
+        out.push(c);
+        length += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ical_round_trip() {
+        let mut builder = SnapshotBuilder::default();
+        let chores = builder.list("Chores, home; etc");
+        let mut laundry = ItemSnapshot::new("Laundry\nwhites first, then colours; \\o/");
+        laundry.priority = Some(Priority::High);
+        laundry.due_date = NaiveDate::from_ymd_opt(2025, 11, 1).and_then(day_start);
+        laundry.created_at = Some(Utc.with_ymd_and_hms(2025, 10, 18, 9, 30, 0).unwrap());
+        laundry.updated_at = Some(Utc.with_ymd_and_hms(2025, 10, 19, 9, 30, 0).unwrap());
+        builder.push_item(chores, laundry);
+        let work = builder.list("Work");
+        let mut report = ItemSnapshot::new("Report ".repeat(30).trim_end().to_string() + " ✓✓✓");
+        report.is_done = true;
+        report.priority = Some(Priority::Low);
+        report.due_date = Some(Utc.with_ymd_and_hms(2025, 11, 3, 15, 45, 0).unwrap());
+        report.created_at = Some(Utc.with_ymd_and_hms(2025, 10, 1, 8, 0, 0).unwrap());
+        report.updated_at = Some(Utc.with_ymd_and_hms(2025, 10, 2, 8, 0, 0).unwrap());
+        builder.push_item(work, report);
+        let snapshot = builder.build();
+
+        let ical = to_ical(&snapshot, Some("dojo"));
+        assert!(ical.lines().all(|line| line.len() <= MAX_LINE_LENGTH + 1));
+        assert!(ical.contains("BEGIN:VTODO\r\nUID:1760779800000000-2@judo\r\n"));
+        assert!(ical.contains("STATUS:COMPLETED\r\nCOMPLETED:20251002T080000Z\r\n"));
+
+        let calendar = parse_calendar(&ical).unwrap();
+        assert_eq!(calendar.name.as_deref(), Some("dojo"));
+        assert_eq!(
+            calendar.todos[0].uid.as_deref(),
+            Some("1760779800000000-2@judo")
+        );
+        assert_eq!(from_ical(&ical).unwrap().lists, snapshot.lists);
+    }
+
+    #[test]
+    fn test_vtodo_round_trip() {
+        let mut item = ItemSnapshot::new("Call the plumber; ask about the boiler, too");
+        item.is_done = true;
+        item.priority = Some(Priority::Medium);
+        item.due_date = Some(Utc.with_ymd_and_hms(2025, 11, 3, 15, 45, 0).unwrap());
+        item.created_at = Some(Utc.with_ymd_and_hms(2025, 10, 1, 8, 0, 0).unwrap());
+        item.updated_at = Some(Utc.with_ymd_and_hms(2025, 10, 2, 8, 0, 0).unwrap());
+
+        let ical = todo_to_ical("1759305600000000-7@judo", Some("Home, garden"), &item);
+        assert_eq!(
+            parse_calendar(&ical).unwrap(),
+            Calendar {
+                name: None,
+                todos: vec![Todo {
+                    uid: Some("1759305600000000-7@judo".to_string()),
+                    category: Some("Home, garden".to_string()),
+                    item,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_text_escaping() {
+        let text = "Milk, eggs; bread\\butter\nand cheese";
+        let escaped = escape(text);
+        assert_eq!(escaped, "Milk\\, eggs\\; bread\\\\butter\\nand cheese");
+        assert_eq!(unescape(&escaped), text);
+
+        assert_eq!(escape("Windows\r\nline"), "Windows\\nline");
+        assert_eq!(unescape("Upper\\Ncase"), "Upper\ncase");
+        assert_eq!(unescape("Trailing\\"), "Trailing\\");
+        assert_eq!(
+            split_list("Deli\\, cheese,Weekly ,"),
+            ["Deli, cheese", "Weekly", ""]
+        );
+    }
+
+    #[test]
+    fn test_folding() {
+        for line in [
+            // Exactly as long as a line may be
+            format!("SUMMARY:{}", "a".repeat(MAX_LINE_LENGTH - 8)),
+            format!("SUMMARY:{}", "ab".repeat(100)),
+            // Characters of 2, 3 and 4 bytes, never split
+            format!("SUMMARY:{}", "é✓🦀".repeat(20)),
+        ] {
+            let mut out = String::new();
+            write_line(&mut out, &line);
+            let physical: Vec<&str> = out.split_terminator("\r\n").collect();
+            assert!(physical.iter().all(|l| l.len() <= MAX_LINE_LENGTH), "{out}");
+            assert!(physical[1..].iter().all(|l| l.starts_with(' ')), "{out}");
+            assert_eq!(physical.len() == 1, line.len() <= MAX_LINE_LENGTH);
+            assert_eq!(unfold(&out), [line]);
+        }
+
+        // Continuation lines may also start with a tab
+        assert_eq!(
+            unfold("SUMMARY:Mi\r\n\tlk\r\nEND:VTODO\r\n"),
+            ["SUMMARY:Milk", "END:VTODO"]
+        );
+    }
+
+    #[test]
+    fn test_due_dates() {
+        let day = NaiveDate::from_ymd_opt(2025, 11, 1).and_then(day_start);
+        let time = Some(Utc.with_ymd_and_hms(2025, 11, 3, 15, 45, 0).unwrap());
+
+        let mut item = ItemSnapshot::new("Laundry");
+        item.due_date = day;
+        let ical = todo_to_ical("a@judo", None, &item);
+        assert!(ical.contains("\r\nDUE;VALUE=DATE:20251101\r\n"), "{ical}");
+        item.due_date = time;
+        let ical = todo_to_ical("a@judo", None, &item);
+        assert!(ical.contains("\r\nDUE:20251103T154500Z\r\n"), "{ical}");
+
+        let date = [("VALUE".to_string(), "DATE".to_string())];
+        assert_eq!(parse_time(&date, "20251101"), day);
+        assert_eq!(parse_time(&[], "20251101"), day);
+        assert_eq!(parse_time(&[], "20251103T154500Z"), time);
+        assert_eq!(
+            parse_time(&[], "20251103T154500"),
+            NaiveDate::from_ymd_opt(2025, 11, 3)
+                .and_then(|date| date.and_hms_opt(15, 45, 0))
+                .and_then(|time| time.and_local_timezone(Local).earliest())
+                .map(|time| time.with_timezone(&Utc))
+        );
+        assert_eq!(parse_time(&date, "2025-11-01"), None);
+    }
+
+    #[test]
+    fn test_from_ical_of_other_apps() {
+        let ical = "BEGIN:VCALENDAR\n\
+                    VERSION:2.0\n\
+                    PRODID:-//Some App//EN\n\
+                    X-WR-CALNAME:Groceries\n\
+                    BEGIN:VEVENT\n\
+                    SUMMARY:Not a to-do\n\
+                    END:VEVENT\n\
+                    BEGIN:VTODO\n\
+                    UID:abc\n\
+                    summary;LANGUAGE=en:Milk\\, eggs\n\
+                    \x20 and bread\n\
+                    PRIORITY:3\n\
+                    DUE;TZID=\"Europe/Rome: the city\":20251101T180000\n\
+                    BEGIN:VALARM\n\
+                    SUMMARY:Alarm\n\
+                    END:VALARM\n\
+                    END:VTODO\n\
+                    BEGIN:VTODO\n\
+                    SUMMARY:Cheese\n\
+                    CATEGORIES:Deli,Weekly\n\
+                    COMPLETED:20251017T120000Z\n\
+                    PRIORITY:0\n\
+                    END:VTODO\n\
+                    END:VCALENDAR\n";
+
+        let snapshot = from_ical(ical).unwrap();
+        assert_eq!(snapshot.lists.len(), 2);
+
+        let groceries = &snapshot.lists[0];
+        assert_eq!(groceries.name, "Groceries");
+        let milk = &groceries.items[0];
+        assert_eq!(milk.name, "Milk, eggs and bread");
+        assert_eq!(milk.priority, Some(Priority::High));
+        assert!(!milk.is_done);
+        let six_pm = NaiveDate::from_ymd_opt(2025, 11, 1)
+            .unwrap()
+            .and_hms_opt(18, 0, 0)
+            .unwrap();
+        assert_eq!(
+            milk.due_date,
+            six_pm
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|time| time.with_timezone(&Utc))
+        );
+
+        let deli = &snapshot.lists[1];
+        assert_eq!(deli.name, "Deli");
+        let cheese = &deli.items[0];
+        assert!(cheese.is_done);
+        assert_eq!(cheese.priority, None);
+        assert_eq!(
+            cheese.updated_at,
+            Some(Utc.with_ymd_and_hms(2025, 10, 17, 12, 0, 0).unwrap())
+        );
+
+        assert!(from_ical("SUMMARY:Milk").is_err());
+    }
+}
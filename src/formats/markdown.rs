@@ -0,0 +1,290 @@
+//! GitHub-flavored Markdown checklists.
+//!
+//! ```markdown
+//! # dojo
+//!
+//! ## Chores
+//!
+//! - [ ] Laundry (priority: high, due: 2025-11-01)
+//! - [x] Dishes
+//! ```
+//!
+//! Every list is a `##` heading followed by its items as task list items,
+//! checked when done, with the priority and due date in parentheses after the
+//! name. Reading is lenient so that checklists written by hand can be imported:
+//! `*`, `+` and numbered bullets and `[X]` are accepted, other lines are skipped,
+//! and items before the first `##` heading go to a list named after the `#`
+//! heading (or "Imported" without one). Judo has no subtasks, so nested
+//! checkboxes become items of the same list, after their parent.
+use crate::db::models::Priority;
+use crate::db::snapshot::{ItemSnapshot, Snapshot};
+use crate::formats::{SnapshotBuilder, format_due, parse_due};
+use std::fmt::Write;
+
+/// List of the items found before any list heading, if there is no title either
+const UNTITLED_LIST: &str = "Imported";
+
+/// Write the lists of a snapshot as a checklist, under a `#` heading if given a title
+pub fn to_markdown(snapshot: &Snapshot, title: Option<&str>) -> String {
+    let mut out = String::new();
+    if let Some(title) = title {
+        let _ = writeln!(out, "# {}\n", title);
+    }
+
+    for (i, list) in snapshot.lists.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "## {}\n", list.name);
+        for item in &list.items {
+            let check = if item.is_done { 'x' } else { ' ' };
+            let _ = writeln!(out, "- [{}] {}{}", check, item.name, metadata(item));
+        }
+    }
+    out
+}
+
+/// Read the lists and items of a checklist
+pub fn from_markdown(text: &str) -> Snapshot {
+    let mut builder = SnapshotBuilder::default();
+    let mut title: Option<String> = None;
+    let mut list: Option<usize> = None;
+    let mut in_code = false;
+
+    for line in text.lines() {
+        let line = line.trim_start();
+        if line.starts_with("```") || line.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+
+        if let Some((level, heading)) = parse_heading(line) {
+            if level == 1 {
+                title = Some(heading.to_string());
+                list = None;
+            } else {
+                list = Some(builder.list(heading));
+            }
+        } else if let Some(item) = parse_item(line) {
+            let list = *list
+                .get_or_insert_with(|| builder.list(title.as_deref().unwrap_or(UNTITLED_LIST)));
+            builder.push_item(list, item);
+        }
+    }
+    builder.build()
+}
+
+// " (priority: high, due: 2025-11-01)" after the name, or nothing
+fn metadata(item: &ItemSnapshot) -> String {
+    let mut fields = Vec::new();
+    if let Some(priority) = &item.priority {
+        fields.push(format!("priority: {}", priority.name()));
+    }
+    if let Some(due) = item.due_date {
+        fields.push(format!("due: {}", format_due(due)));
+    }
+
+    if fields.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", fields.join(", "))
+    }
+}
+
+// Level and text of an ATX heading, e.g. "## Chores"
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = line[level..].strip_prefix(' ')?;
+    let text = rest.trim().trim_end_matches('#').trim_end();
+    (1..=6).contains(&level).then_some((level, text))
+}
+
+// A task list item, e.g. "- [x] Dishes (priority: low)"
+fn parse_item(line: &str) -> Option<ItemSnapshot> {
+    let rest = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            line[digits..]
+                .strip_prefix(['.', ')'])
+                .filter(|_| digits > 0)?
+        }
+    };
+    let rest = rest.strip_prefix(' ')?.trim_start();
+
+    let is_done = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let rest = &rest[3..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+
+    let mut item = ItemSnapshot::new(rest.trim());
+    item.is_done = is_done;
+    parse_metadata(&mut item);
+    (!item.name.is_empty()).then_some(item)
+}
+
+// Move a trailing "(priority: high, due: 2025-11-01)" from the name to the fields.
+// Parentheses holding anything else are part of the name.
+fn parse_metadata(item: &mut ItemSnapshot) {
+    let Some(open) = item.name.rfind(" (") else {
+        return;
+    };
+    let Some(inner) = item.name[open + 2..].strip_suffix(')') else {
+        return;
+    };
+
+    let mut priority = None;
+    let mut due_date = None;
+    for field in inner.split(',') {
+        let Some((key, value)) = field.split_once(':') else {
+            return;
+        };
+        match (key.trim(), value.trim()) {
+            ("priority", value) => match value.parse::<Priority>() {
+                Ok(value) => priority = Some(value),
+                Err(_) => return,
+            },
+            ("due", value) => match parse_due(value) {
+                Some(value) => due_date = Some(value),
+                None => return,
+            },
+            _ => return,
+        }
+    }
+
+    item.name.truncate(open);
+    item.priority = priority;
+    item.due_date = due_date;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Name, check and priority of an item
+    type Item = (String, bool, Option<Priority>);
+
+    /// Names of the lists with their items
+    fn contents(snapshot: &Snapshot) -> Vec<(String, Vec<Item>)> {
+        snapshot
+            .lists
+            .iter()
+            .map(|list| {
+                let items = list
+                    .items
+                    .iter()
+                    .map(|item| (item.name.clone(), item.is_done, item.priority.clone()))
+                    .collect();
+                (list.name.clone(), items)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_markdown() {
+        let snapshot = from_markdown(
+            "# dojo\n\
+             \n\
+             ## Chores\n\
+             \n\
+             - [ ] Laundry (priority: high, due: 2025-11-01)\n\
+             - [x] Dishes\n\
+             \n\
+             ## Work ##\n\
+             * [X] Call Bob (work)\n\
+             1. [ ] Report (priority: urgent)\n",
+        );
+
+        assert_eq!(
+            contents(&snapshot),
+            vec![
+                (
+                    "Chores".to_string(),
+                    vec![
+                        ("Laundry".to_string(), false, Some(Priority::High)),
+                        ("Dishes".to_string(), true, None),
+                    ]
+                ),
+                (
+                    "Work".to_string(),
+                    vec![
+                        ("Call Bob (work)".to_string(), true, None),
+                        ("Report (priority: urgent)".to_string(), false, None),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(snapshot.lists[0].items[0].due_date, parse_due("2025-11-01"));
+    }
+
+    #[test]
+    fn test_from_markdown_is_lenient() {
+        let snapshot = from_markdown(
+            "Notes from the meeting\n\
+             - [ ] Before any list\n\
+             - a plain bullet\n\
+             - [ ] Plan release\n  \
+               - [x] Write changelog\n    \
+                 + [ ] Ask for review\n\
+             ```\n\
+             - [ ] In a code block\n\
+             ```\n\
+             -[ ] No space\n\
+             - [ ]\n",
+        );
+
+        assert_eq!(
+            contents(&snapshot),
+            vec![(
+                "Imported".to_string(),
+                vec![
+                    ("Before any list".to_string(), false, None),
+                    ("Plan release".to_string(), false, None),
+                    ("Write changelog".to_string(), true, None),
+                    ("Ask for review".to_string(), false, None),
+                ]
+            )]
+        );
+
+        let snapshot = from_markdown("# Sprint 12\n- [ ] Demo\n");
+        assert_eq!(snapshot.lists[0].name, "Sprint 12");
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let mut builder = SnapshotBuilder::default();
+        let chores = builder.list("Chores");
+        let mut laundry = ItemSnapshot::new("Laundry");
+        laundry.priority = Some(Priority::Low);
+        laundry.due_date = parse_due("2025-11-01");
+        builder.push_item(chores, laundry);
+        let mut dishes = ItemSnapshot::new("Dishes (all of them)");
+        dishes.is_done = true;
+        builder.push_item(chores, dishes);
+        builder.list("Empty");
+        let snapshot = builder.build();
+
+        let markdown = to_markdown(&snapshot, Some("dojo"));
+        assert_eq!(
+            markdown,
+            "# dojo\n\
+             \n\
+             ## Chores\n\
+             \n\
+             - [ ] Laundry (priority: low, due: 2025-11-01)\n\
+             - [x] Dishes (all of them)\n\
+             \n\
+             ## Empty\n\
+             \n"
+        );
+        assert_eq!(from_markdown(&markdown).lists, snapshot.lists);
+    }
+}
@@ -0,0 +1,130 @@
+//! Lists and items in the file formats of other tools.
+//!
+//! Every format converts to and from a [`Snapshot`], so that `judo export` and
+//! `judo import` handle them like judo's own JSON documents (including merging).
+pub mod ical;
+pub mod markdown;
+pub mod taskwarrior;
+pub mod todotxt;
+
+use crate::db::ops::ORDERING_GAP;
+use crate::db::snapshot::{ItemSnapshot, ListSnapshot, Snapshot};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, SecondsFormat, Utc};
+
+/// Collects the lists and items read from a file, in the order they are read
+#[derive(Debug, Default)]
+pub struct SnapshotBuilder {
+    lists: Vec<ListSnapshot>,
+    next_id: i64,
+}
+
+impl SnapshotBuilder {
+    /// Index of the list with this name, added after the others if it's new
+    pub fn list(&mut self, name: &str) -> usize {
+        if let Some(index) = self.lists.iter().position(|list| list.name == name) {
+            return index;
+        }
+
+        self.next_id += 1;
+        self.lists.push(ListSnapshot {
+            id: self.next_id,
+            name: name.to_string(),
+            ordering: (self.lists.len() as i64 + 1) * ORDERING_GAP,
+            created_at: None,
+            updated_at: None,
+            items: Vec::new(),
+        });
+        self.lists.len() - 1
+    }
+
+    /// Add an item after the others of a list
+    pub fn push_item(&mut self, list: usize, mut item: ItemSnapshot) {
+        let items = &mut self.lists[list].items;
+        self.next_id += 1;
+        item.id = self.next_id;
+        item.ordering = (items.len() as i64 + 1) * ORDERING_GAP;
+        items.push(item);
+    }
+
+    pub fn build(self) -> Snapshot {
+        Snapshot::new(self.lists)
+    }
+}
+
+/// Due date as written to files: the day alone for local midnight (which is how
+/// days are read back), an RFC 3339 time otherwise
+pub fn format_due(due: DateTime<Utc>) -> String {
+    let local = due.with_timezone(&Local);
+    if local.time() == NaiveTime::MIN {
+        local.format("%Y-%m-%d").to_string()
+    } else {
+        due.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+}
+
+/// Local midnight starting the day, which is how days are stored
+pub fn day_start(date: NaiveDate) -> Option<DateTime<Utc>> {
+    date.and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Parse a YYYY-MM-DD day (its local midnight) or an RFC 3339 time
+pub fn parse_date(text: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").map_err(|_| {
+        anyhow!(
+            "'{}' is neither a YYYY-MM-DD date nor an RFC 3339 time",
+            text
+        )
+    })?;
+    day_start(date).ok_or_else(|| anyhow!("'{}' has no midnight in the local time zone", text))
+}
+
+/// Due date written by [`format_due`], a YYYY-MM-DD day or an RFC 3339 time
+pub fn parse_due(text: &str) -> Option<DateTime<Utc>> {
+    parse_date(text).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_due_round_trip() {
+        let midnight = Local
+            .with_ymd_and_hms(2025, 11, 1, 0, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(format_due(midnight), "2025-11-01");
+        assert_eq!(parse_due("2025-11-01"), Some(midnight));
+
+        let afternoon = Utc.with_ymd_and_hms(2025, 11, 1, 15, 30, 0).unwrap();
+        if afternoon.with_timezone(&Local).time() != NaiveTime::MIN {
+            assert_eq!(parse_due(&format_due(afternoon)), Some(afternoon));
+        }
+        assert_eq!(parse_due("soon"), None);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("2025-11-01T15:30:00+02:00").unwrap(),
+            Utc.with_ymd_and_hms(2025, 11, 1, 13, 30, 0).unwrap()
+        );
+        assert_eq!(
+            parse_date("2025-11-01").unwrap(),
+            day_start(NaiveDate::from_ymd_opt(2025, 11, 1).unwrap()).unwrap()
+        );
+        assert_eq!(
+            parse_date("01/11/2025").unwrap_err().to_string(),
+            "'01/11/2025' is neither a YYYY-MM-DD date nor an RFC 3339 time"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! The JSON written by [Taskwarrior](https://taskwarrior.org)'s `task export`,
+//! for moving over to judo:
+//!
+//! ```json
+//! [
+//!   {"id":1,"description":"Fix the fence","entry":"20251001T080000Z","modified":"20251002T080000Z",
+//!    "due":"20251031T230000Z","project":"Home","priority":"H","status":"pending",
+//!    "tags":["garden"],"annotations":[{"entry":"20251002T080000Z","description":"Ask Bob for a ladder"}],
+//!    "uuid":"5e2f6a2c-...","urgency":9.2}
+//! ]
+//! ```
+//!
+//! The project of a task is its list (tasks without one go to "Inbox"),
+//! priorities `H`, `M` and `L` are high, medium and low, `entry` is when the
+//! item was created and `end` (or else `modified`) when it was last changed.
+//! Completed tasks are done, deleted tasks and the templates of recurring ones
+//! (whose instances are tasks of their own) are skipped. Judo has no tags or
+//! notes, so tags are added to the name as `+tag` and annotations after it in
+//! parentheses. The other fields of a task, such as `depends`, `scheduled` or
+//! user defined attributes, are left out and reported in [`TaskwarriorReport`].
+//! Older versions of Taskwarrior export one task per line instead of an array,
+//! which is read too.
+use crate::db::models::Priority;
+use crate::db::snapshot::{ItemSnapshot, Snapshot};
+use crate::formats::SnapshotBuilder;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// List of the tasks without a project
+const NO_PROJECT_LIST: &str = "Inbox";
+
+/// Fields nothing is lost without: IDs and values Taskwarrior computes
+const IGNORED_FIELDS: &[&str] = &["id", "uuid", "urgency", "modified", "imask", "mask"];
+
+/// A task of `task export`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Task {
+    pub description: String,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub due: Option<String>,
+    #[serde(default)]
+    pub entry: Option<String>,
+    #[serde(default)]
+    pub end: Option<String>,
+    #[serde(default)]
+    pub modified: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// Every other field
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Annotation {
+    #[serde(default)]
+    pub entry: Option<String>,
+    pub description: String,
+}
+
+/// What an import left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskwarriorReport {
+    /// Deleted tasks
+    pub deleted: usize,
+    /// Templates of recurring tasks
+    pub recurring: usize,
+    /// Number of tasks with each field judo has no place for
+    pub unmapped: BTreeMap<String, usize>,
+}
+
+impl Task {
+    /// The list and item of the task
+    pub fn to_item(&self) -> Result<(String, ItemSnapshot)> {
+        let mut name = self.description.trim().to_string();
+        for tag in &self.tags {
+            name.push_str(" +");
+            name.push_str(tag);
+        }
+        let notes: Vec<&str> = self
+            .annotations
+            .iter()
+            .map(|annotation| annotation.description.trim())
+            .filter(|note| !note.is_empty())
+            .collect();
+        if !notes.is_empty() {
+            name.push_str(&format!(" ({})", notes.join("; ")));
+        }
+
+        let mut item = ItemSnapshot::new(name);
+        item.is_done = self.status.as_deref() == Some("completed");
+        item.priority = self.priority.as_deref().and_then(parse_priority);
+        item.due_date = self.time("due", &self.due)?;
+        item.created_at = self.time("entry", &self.entry)?;
+        item.updated_at = match self.time("end", &self.end)? {
+            Some(end) => Some(end),
+            None => self.time("modified", &self.modified)?,
+        };
+
+        let list_name = match self.project.as_deref().map(str::trim) {
+            Some(project) if !project.is_empty() => project.to_string(),
+            _ => NO_PROJECT_LIST.to_string(),
+        };
+        Ok((list_name, item))
+    }
+
+    /// Names of the fields of the task judo has no place for
+    pub fn unmapped_fields(&self) -> impl Iterator<Item = &str> {
+        let priority = self
+            .priority
+            .as_deref()
+            .filter(|priority| parse_priority(priority).is_none())
+            .map(|_| "priority");
+        self.other
+            .keys()
+            .map(String::as_str)
+            .filter(|field| !IGNORED_FIELDS.contains(field))
+            .chain(priority)
+    }
+
+    fn time(&self, field: &str, value: &Option<String>) -> Result<Option<DateTime<Utc>>> {
+        value
+            .as_deref()
+            .map(|value| {
+                parse_time(value).with_context(|| {
+                    format!(
+                        "Invalid {} '{}' of task '{}'",
+                        field, value, self.description
+                    )
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Read the tasks of `task export` into lists
+pub fn from_taskwarrior(text: &str) -> Result<(Snapshot, TaskwarriorReport)> {
+    let mut builder = SnapshotBuilder::default();
+    let mut report = TaskwarriorReport::default();
+
+    for task in parse_tasks(text)? {
+        match task.status.as_deref() {
+            Some("deleted") => report.deleted += 1,
+            Some("recurring") => report.recurring += 1,
+            _ => {
+                for field in task.unmapped_fields() {
+                    *report.unmapped.entry(field.to_string()).or_default() += 1;
+                }
+                let (list_name, item) = task.to_item()?;
+                let list = builder.list(&list_name);
+                builder.push_item(list, item);
+            }
+        }
+    }
+    Ok((builder.build(), report))
+}
+
+/// Tasks of a JSON array, or of one JSON object per line
+fn parse_tasks(text: &str) -> Result<Vec<Task>> {
+    if text.trim_start().starts_with('[') {
+        return serde_json::from_str(text).with_context(|| "Not a Taskwarrior export");
+    }
+
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim().trim_end_matches(',')))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Not a Taskwarrior task on line {}", i + 1))
+        })
+        .collect()
+}
+
+fn parse_priority(priority: &str) -> Option<Priority> {
+    match priority {
+        "H" => Some(Priority::High),
+        "M" => Some(Priority::Medium),
+        "L" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+// Taskwarrior writes times in UTC as 20251018T093000Z, RFC 3339 is read too
+fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Ok(time.and_utc());
+    }
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(time) => Ok(time.with_timezone(&Utc)),
+        Err(_) => bail!("expected a time like 20251018T093000Z"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_to_item() {
+        let task: Task = serde_json::from_str(
+            r#"{"description":"Fix the fence","status":"completed","project":"Home.Garden",
+                "priority":"M","entry":"20251001T080000Z","end":"2025-10-03T08:00:00+02:00",
+                "modified":"20251004T080000Z","tags":["weekend","diy"],
+                "annotations":[{"entry":"20251002T080000Z","description":"Ask Bob"},
+                               {"description":"Buy nails"}],
+                "depends":"abc","wait":"20251001T080000Z","urgency":3.2,"uuid":"abc"}"#,
+        )
+        .unwrap();
+
+        let (list_name, item) = task.to_item().unwrap();
+        assert_eq!(list_name, "Home.Garden");
+        assert_eq!(
+            item.name,
+            "Fix the fence +weekend +diy (Ask Bob; Buy nails)"
+        );
+        assert!(item.is_done);
+        assert_eq!(item.priority, Some(Priority::Medium));
+        assert_eq!(item.due_date, None);
+        assert_eq!(
+            item.created_at,
+            Some(Utc.with_ymd_and_hms(2025, 10, 1, 8, 0, 0).unwrap())
+        );
+        assert_eq!(
+            item.updated_at,
+            Some(Utc.with_ymd_and_hms(2025, 10, 3, 6, 0, 0).unwrap())
+        );
+        assert_eq!(
+            task.unmapped_fields().collect::<Vec<_>>(),
+            ["depends", "wait"]
+        );
+    }
+
+    #[test]
+    fn test_from_taskwarrior_reads_lines() {
+        let (snapshot, report) = from_taskwarrior(
+            "{\"description\":\"Call mum\",\"status\":\"pending\",\"priority\":\"urgent\"},\n\
+             {\"description\":\"Old\",\"status\":\"deleted\"}\n",
+        )
+        .unwrap();
+        assert_eq!(snapshot.lists[0].name, "Inbox");
+        assert_eq!(snapshot.lists[0].items[0].name, "Call mum");
+        assert_eq!(snapshot.lists[0].items[0].priority, None);
+        assert_eq!(report.deleted, 1);
+        assert_eq!(
+            report.unmapped,
+            BTreeMap::from([("priority".to_string(), 1)])
+        );
+
+        let error = from_taskwarrior(r#"[{"description":"x","due":"tomorrow"}]"#).unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "Invalid due 'tomorrow' of task 'x': expected a time like 20251018T093000Z"
+        );
+        assert!(from_taskwarrior("- [ ] Not JSON").is_err());
+    }
+}
@@ -0,0 +1,413 @@
+//! The [todo.txt](https://github.com/todotxt/todo.txt) format, one task per line:
+//!
+//! ```text
+//! (A) 2025-10-01 Call the plumber @phone +Home due:2025-11-01
+//! x 2025-10-18 2025-10-02 Renew passport +Errands pri:B
+//! ```
+//!
+//! [`Task`] reads and writes single lines. As judo items, the first `+project`
+//! of a task is its list (tasks without one go to "Inbox"), priorities `A` and `B`
+//! are high and medium and any lower one is low, `due:` is the due date, the
+//! creation date is when the item was created and the completion date when it
+//! was last changed. Contexts, other projects and other `key:value` tags stay in
+//! the name. Done tasks keep their priority in a `pri:` tag, as is customary.
+//! Projects can't contain spaces, so spaces in list names are written as `_`
+//! and `_` in projects is read as a space. Items are always written with a
+//! creation date (the day they are written if they have none), since without
+//! one a name starting with `x `, `(A) ` or a date would be read back as those.
+use crate::db::models::Priority;
+use crate::db::snapshot::{ItemSnapshot, Snapshot};
+use crate::formats::{SnapshotBuilder, day_start, format_due, parse_due};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use std::fmt;
+
+/// List of the tasks without a project, which are written without one
+const NO_PROJECT_LIST: &str = "Inbox";
+
+/// A line of a todo.txt file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Task {
+    /// Whether the line starts with `x`
+    pub done: bool,
+    /// Priority letter, `A` to `Z`
+    pub priority: Option<char>,
+    /// Completion date, only for done tasks
+    pub completed: Option<NaiveDate>,
+    /// Creation date. Done tasks need a completion date to have one
+    pub created: Option<NaiveDate>,
+    /// The rest of the line, with its projects, contexts and tags
+    pub description: String,
+}
+
+impl Task {
+    /// Read a line. Any text is a task, whatever doesn't fit the format is description
+    pub fn parse(line: &str) -> Task {
+        let mut task = Task::default();
+        let mut rest = line.trim_end_matches(['\r', '\n']);
+
+        if let Some(after) = rest.strip_prefix("x ") {
+            task.done = true;
+            rest = after;
+        }
+        if let [b'(', letter @ b'A'..=b'Z', b')', b' ', ..] = rest.as_bytes() {
+            task.priority = Some(*letter as char);
+            rest = &rest[4..];
+        }
+        if let Some((date, after)) = take_date(rest) {
+            rest = after;
+            if !task.done {
+                task.created = Some(date);
+            } else {
+                task.completed = Some(date);
+                if let Some((created, after)) = take_date(rest) {
+                    task.created = Some(created);
+                    rest = after;
+                }
+            }
+        }
+
+        task.description = rest.to_string();
+        task
+    }
+
+    /// Projects of the task, without their `+`
+    pub fn projects(&self) -> impl Iterator<Item = &str> {
+        self.words().filter_map(|word| word.strip_prefix('+'))
+    }
+
+    /// Contexts of the task, without their `@`
+    pub fn contexts(&self) -> impl Iterator<Item = &str> {
+        self.words().filter_map(|word| word.strip_prefix('@'))
+    }
+
+    /// Value of the first `key:value` tag with this key
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.words()
+            .filter_map(|word| word.split_once(':'))
+            .find(|(k, value)| *k == key && !value.is_empty())
+            .map(|(_, value)| value)
+    }
+
+    fn words(&self) -> impl Iterator<Item = &str> {
+        self.description
+            .split_whitespace()
+            .filter(|word| word.len() > 1)
+    }
+
+    /// The task of an item of the given list
+    pub fn from_item(list_name: &str, item: &ItemSnapshot) -> Task {
+        let local_day = |time: DateTime<Utc>| time.with_timezone(&Local).date_naive();
+        let letter = item.priority.as_ref().map(|priority| match priority {
+            Priority::High => 'A',
+            Priority::Medium => 'B',
+            Priority::Low => 'C',
+        });
+
+        let mut words = vec![item.name.clone()];
+        if list_name != NO_PROJECT_LIST {
+            words.push(format!(
+                "+{}",
+                list_name.split_whitespace().collect::<Vec<_>>().join("_")
+            ));
+        }
+        if let Some(due) = item.due_date {
+            words.push(format!("due:{}", format_due(due)));
+        }
+
+        let created = item
+            .created_at
+            .map(local_day)
+            .unwrap_or_else(|| Local::now().date_naive());
+        let mut task = Task {
+            done: item.is_done,
+            created: Some(created),
+            ..Task::default()
+        };
+        if item.is_done {
+            if let Some(letter) = letter {
+                words.push(format!("pri:{}", letter));
+            }
+            task.completed = Some(item.updated_at.map(local_day).unwrap_or(created));
+        } else {
+            task.priority = letter;
+        }
+        task.description = words.join(" ");
+        task
+    }
+
+    /// Name of the list of the task, and the task as an item of it
+    pub fn to_item(&self) -> (String, ItemSnapshot) {
+        let project = self.projects().next();
+        let due_date = self.tag("due").and_then(parse_due);
+        let priority_letter = self
+            .tag("pri")
+            .filter(|_| self.done)
+            .and_then(|value| value.parse::<char>().ok())
+            .or(self.priority);
+
+        // Leave out of the name what made it into other fields
+        let mut list_word = project.map(|project| format!("+{}", project));
+        let mut due_word = self
+            .tag("due")
+            .filter(|_| due_date.is_some())
+            .map(|d| format!("due:{}", d));
+        let mut pri_word = self
+            .tag("pri")
+            .filter(|_| self.done)
+            .map(|p| format!("pri:{}", p));
+        let name = self
+            .description
+            .split_whitespace()
+            .filter(|word| {
+                for field in [&mut list_word, &mut due_word, &mut pri_word] {
+                    if field.as_deref() == Some(*word) {
+                        *field = None;
+                        return false;
+                    }
+                }
+                true
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut item = ItemSnapshot::new(name);
+        item.is_done = self.done;
+        item.priority = priority_letter.map(|letter| match letter {
+            'A' => Priority::High,
+            'B' => Priority::Medium,
+            _ => Priority::Low,
+        });
+        item.due_date = due_date;
+        item.created_at = self.created.and_then(day_start);
+        item.updated_at = self.completed.and_then(day_start);
+
+        let list_name = project.map_or(NO_PROJECT_LIST.to_string(), |project| {
+            project.replace('_', " ")
+        });
+        (list_name, item)
+    }
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.done {
+            write!(f, "x ")?;
+        }
+        if let Some(letter) = self.priority {
+            write!(f, "({}) ", letter)?;
+        }
+        for date in [self.completed, self.created].into_iter().flatten() {
+            write!(f, "{} ", date.format("%Y-%m-%d"))?;
+        }
+        write!(f, "{}", self.description)
+    }
+}
+
+/// Write every item of a snapshot as a task
+pub fn to_todotxt(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+    for list in &snapshot.lists {
+        for item in &list.items {
+            out += &Task::from_item(&list.name, item).to_string();
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Read the tasks of a todo.txt file, skipping blank lines
+pub fn from_todotxt(text: &str) -> Snapshot {
+    let mut builder = SnapshotBuilder::default();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let (list_name, item) = Task::parse(line).to_item();
+        let list = builder.list(&list_name);
+        builder.push_item(list, item);
+    }
+    builder.build()
+}
+
+// A YYYY-MM-DD date at the start of the text, and the text after it
+fn take_date(text: &str) -> Option<(NaiveDate, &str)> {
+    let (word, rest) = text.split_once(' ').unwrap_or((text, ""));
+    if word.len() != 10 {
+        return None;
+    }
+    let date = NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()?;
+    Some((date, rest))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse() {
+        let task = Task::parse("(A) 2025-10-01 Call the plumber @phone +Home due:2025-11-01");
+        assert_eq!(task.priority, Some('A'));
+        assert_eq!(task.created, NaiveDate::from_ymd_opt(2025, 10, 1));
+        assert_eq!(task.projects().collect::<Vec<_>>(), vec!["Home"]);
+        assert_eq!(task.contexts().collect::<Vec<_>>(), vec!["phone"]);
+        assert_eq!(task.tag("due"), Some("2025-11-01"));
+
+        let task = Task::parse("x 2025-10-18 2025-10-02 Renew passport +Errands pri:B");
+        assert!(task.done);
+        assert_eq!(task.completed, NaiveDate::from_ymd_opt(2025, 10, 18));
+        assert_eq!(task.created, NaiveDate::from_ymd_opt(2025, 10, 2));
+        assert_eq!(task.description, "Renew passport +Errands pri:B");
+
+        // Not quite the format, so all description
+        for line in [
+            "xylophone lessons",
+            "(a) lower case",
+            "2025-13-01 no such month",
+            "x",
+        ] {
+            assert_eq!(Task::parse(line).description, line);
+        }
+    }
+
+    #[test]
+    fn test_to_item() {
+        let (list, item) =
+            Task::parse("x 2025-10-18 2025-10-02 Renew passport @town +Errands +Paperwork pri:B")
+                .to_item();
+        assert_eq!(list, "Errands");
+        assert_eq!(item.name, "Renew passport @town +Paperwork");
+        assert!(item.is_done);
+        assert_eq!(item.priority, Some(Priority::Medium));
+        assert_eq!(
+            item.created_at,
+            NaiveDate::from_ymd_opt(2025, 10, 2).and_then(day_start)
+        );
+        assert_eq!(
+            item.updated_at,
+            NaiveDate::from_ymd_opt(2025, 10, 18).and_then(day_start)
+        );
+
+        let (list, item) = Task::parse("(D) Water the plants due:someday").to_item();
+        assert_eq!(list, NO_PROJECT_LIST);
+        assert_eq!(item.name, "Water the plants due:someday");
+        assert_eq!(item.priority, Some(Priority::Low));
+        assert_eq!(item.due_date, None);
+
+        let (list, item) = Task::parse("Laundry +Home_Chores").to_item();
+        assert_eq!(list, "Home Chores");
+        assert_eq!(item.name, "Laundry");
+    }
+
+    #[test]
+    fn test_names_like_fields_survive_without_dates() {
+        for name in ["x marks the spot", "(A) team", "2025-10-01 report"] {
+            for is_done in [false, true] {
+                let mut item = ItemSnapshot::new(name.to_string());
+                item.is_done = is_done;
+                let line = Task::from_item(NO_PROJECT_LIST, &item).to_string();
+
+                let (_, read) = Task::parse(&line).to_item();
+                assert_eq!(read.name, name, "{line}");
+                assert_eq!(read.is_done, is_done, "{line}");
+                assert_eq!(read.priority, None, "{line}");
+            }
+        }
+    }
+
+    fn date() -> impl Strategy<Value = NaiveDate> {
+        (0..20_000u64)
+            .prop_map(|days| NaiveDate::from_ymd_opt(2000, 1, 1).unwrap() + chrono::Days::new(days))
+    }
+
+    /// Words that can't be mistaken for the fields at the start of a line
+    fn description() -> impl Strategy<Value = String> {
+        let word = prop_oneof![
+            "[a-z]{2,8}",
+            "\\+[A-Za-z]{1,8}",
+            "@[a-z]{1,8}",
+            "[a-z]{1,5}:[a-z0-9]{1,5}",
+        ];
+        ("[a-z]{2,8}", prop::collection::vec(word, 0..6)).prop_map(|(first, rest)| {
+            std::iter::once(first)
+                .chain(rest)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    }
+
+    fn task() -> impl Strategy<Value = Task> {
+        (
+            any::<bool>(),
+            prop::option::of(prop::char::range('A', 'Z')),
+            date(),
+            prop::option::of(date()),
+            description(),
+        )
+            .prop_map(|(done, priority, completed, created, description)| Task {
+                done,
+                priority,
+                completed: done.then_some(completed),
+                created,
+                description,
+            })
+    }
+
+    /// Lists with distinct names, holding items with names of plain words,
+    /// possibly starting with what looks like a done mark, priority or date
+    fn snapshot() -> impl Strategy<Value = Snapshot> {
+        let prefix = prop::option::of(prop_oneof![
+            Just("x".to_string()),
+            "\\([A-Z]\\)",
+            date().prop_map(|date| date.format("%Y-%m-%d").to_string()),
+        ]);
+        let name = (prefix, prop::collection::vec("[a-z]{1,8}", 1..4))
+            .prop_map(|(prefix, words)| prefix.into_iter().chain(words).collect::<Vec<_>>());
+        let item = (
+            name,
+            any::<bool>(),
+            prop::option::of(prop_oneof![
+                Just(Priority::High),
+                Just(Priority::Medium),
+                Just(Priority::Low)
+            ]),
+            prop::option::of(date()),
+            date(),
+            date(),
+        )
+            .prop_map(|(words, is_done, priority, due, created, updated)| {
+                let mut item = ItemSnapshot::new(words.join(" "));
+                item.is_done = is_done;
+                item.priority = priority;
+                item.due_date = due.and_then(day_start);
+                item.created_at = day_start(created);
+                item.updated_at = day_start(updated).filter(|_| is_done);
+                item
+            });
+        let list = (
+            "[A-Z][a-z]{0,8}( [a-z]{1,5})?",
+            prop::collection::vec(item, 1..5),
+        );
+
+        prop::collection::vec(list, 0..4).prop_map(|lists| {
+            let mut builder = SnapshotBuilder::default();
+            for (name, items) in lists {
+                let list = builder.list(&name);
+                for item in items {
+                    builder.push_item(list, item);
+                }
+            }
+            builder.build()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_task_round_trip(task in task()) {
+            prop_assert_eq!(Task::parse(&task.to_string()), task);
+        }
+
+        #[test]
+        fn test_todotxt_round_trip(snapshot in snapshot()) {
+            let read = from_todotxt(&to_todotxt(&snapshot));
+            prop_assert_eq!(read.lists, snapshot.lists);
+        }
+    }
+}
@@ -0,0 +1,1034 @@
+//! Key bindings of the TUI: the actions, the `keymap` presets and the `[keys]`
+//! overrides of judo.toml, checked when the config is read.
+use anyhow::{Context, Result, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Keys for an action in judo.toml: either a single key sequence or a list of them
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    /// Key sequences of the binding, as written
+    pub fn sequences(&self) -> Vec<&str> {
+        match self {
+            KeyBinding::One(sequence) => vec![sequence.as_str()],
+            KeyBinding::Many(sequences) => sequences.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// Context in which a key binding is active
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    /// Main screen showing lists and items
+    Main,
+    /// Database selection pop-up
+    Database,
+    /// Help overlay
+    Help,
+    /// Message log screen
+    Messages,
+}
+
+/// Built-in set of default key bindings, selected with `keymap` in judo.toml
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeymapPreset {
+    /// The classic judo bindings
+    #[default]
+    Default,
+    /// Vim-style modal navigation with key sequences, counts and repeat
+    Vim,
+}
+
+impl FromStr for KeymapPreset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "default" => Ok(KeymapPreset::Default),
+            "vim" => Ok(KeymapPreset::Vim),
+            _ => bail!("Unknown keymap '{}', expected 'default' or 'vim'", s),
+        }
+    }
+}
+
+/// Every user action that can be bound to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    NextList,
+    PreviousList,
+    AddList,
+    ModifyList,
+    DeleteList,
+    MoveListUp,
+    MoveListDown,
+    MoveListToTop,
+    MoveListToBottom,
+    NextItem,
+    PreviousItem,
+    SelectItem,
+    DeselectItem,
+    AddItem,
+    ModifyItem,
+    DeleteItem,
+    ToggleDone,
+    MoveItemUp,
+    MoveItemDown,
+    MoveItemToTop,
+    MoveItemToBottom,
+    FocusLists,
+    FocusItems,
+    ToggleFocus,
+    CursorDown,
+    CursorUp,
+    CursorTop,
+    CursorBottom,
+    DeleteSelected,
+    AddItemBelow,
+    AddItemAbove,
+    FilterItems,
+    SaveView,
+    RepeatLast,
+    ChangeDb,
+    NextDb,
+    PreviousDb,
+    SwitchDb,
+    AddDb,
+    RenameDb,
+    RemoveDb,
+    SetDefaultDb,
+    CloseDbPopup,
+    ShowHelp,
+    ScrollHelpDown,
+    ScrollHelpUp,
+    CloseHelp,
+    OpenPalette,
+    ShowMessages,
+    ScrollMessagesDown,
+    ScrollMessagesUp,
+    CloseMessages,
+}
+
+impl Action {
+    /// All actions, in the order they are presented to the user
+    pub const ALL: [Action; 53] = [
+        Action::Quit,
+        Action::NextList,
+        Action::PreviousList,
+        Action::AddList,
+        Action::ModifyList,
+        Action::DeleteList,
+        Action::MoveListUp,
+        Action::MoveListDown,
+        Action::MoveListToTop,
+        Action::MoveListToBottom,
+        Action::NextItem,
+        Action::PreviousItem,
+        Action::SelectItem,
+        Action::DeselectItem,
+        Action::AddItem,
+        Action::ModifyItem,
+        Action::DeleteItem,
+        Action::ToggleDone,
+        Action::MoveItemUp,
+        Action::MoveItemDown,
+        Action::MoveItemToTop,
+        Action::MoveItemToBottom,
+        Action::FocusLists,
+        Action::FocusItems,
+        Action::ToggleFocus,
+        Action::CursorDown,
+        Action::CursorUp,
+        Action::CursorTop,
+        Action::CursorBottom,
+        Action::DeleteSelected,
+        Action::AddItemBelow,
+        Action::AddItemAbove,
+        Action::FilterItems,
+        Action::SaveView,
+        Action::RepeatLast,
+        Action::ChangeDb,
+        Action::NextDb,
+        Action::PreviousDb,
+        Action::SwitchDb,
+        Action::AddDb,
+        Action::RenameDb,
+        Action::RemoveDb,
+        Action::SetDefaultDb,
+        Action::CloseDbPopup,
+        Action::ShowHelp,
+        Action::ScrollHelpDown,
+        Action::ScrollHelpUp,
+        Action::CloseHelp,
+        Action::OpenPalette,
+        Action::ShowMessages,
+        Action::ScrollMessagesDown,
+        Action::ScrollMessagesUp,
+        Action::CloseMessages,
+    ];
+
+    /// Contexts in which the action can be triggered
+    pub fn contexts(&self) -> &'static [KeyContext] {
+        match self {
+            Action::NextDb
+            | Action::PreviousDb
+            | Action::SwitchDb
+            | Action::AddDb
+            | Action::RenameDb
+            | Action::RemoveDb
+            | Action::SetDefaultDb
+            | Action::CloseDbPopup => &[KeyContext::Database],
+            Action::ShowHelp => &[KeyContext::Main, KeyContext::Database],
+            Action::ScrollHelpDown | Action::ScrollHelpUp | Action::CloseHelp => {
+                &[KeyContext::Help]
+            }
+            Action::ScrollMessagesDown | Action::ScrollMessagesUp | Action::CloseMessages => {
+                &[KeyContext::Messages]
+            }
+            _ => &[KeyContext::Main],
+        }
+    }
+
+    /// Heading the action is listed under in the help overlay
+    pub fn group(&self) -> &'static str {
+        match self {
+            Action::NextList
+            | Action::PreviousList
+            | Action::AddList
+            | Action::ModifyList
+            | Action::DeleteList
+            | Action::MoveListUp
+            | Action::MoveListDown
+            | Action::MoveListToTop
+            | Action::MoveListToBottom => "Lists",
+            Action::NextItem
+            | Action::PreviousItem
+            | Action::SelectItem
+            | Action::DeselectItem
+            | Action::AddItem
+            | Action::ModifyItem
+            | Action::DeleteItem
+            | Action::ToggleDone
+            | Action::MoveItemUp
+            | Action::MoveItemDown
+            | Action::MoveItemToTop
+            | Action::MoveItemToBottom
+            | Action::AddItemBelow
+            | Action::AddItemAbove
+            | Action::FilterItems
+            | Action::SaveView => "Items",
+            Action::FocusLists
+            | Action::FocusItems
+            | Action::ToggleFocus
+            | Action::CursorDown
+            | Action::CursorUp
+            | Action::CursorTop
+            | Action::CursorBottom
+            | Action::DeleteSelected => "Focused pane",
+            Action::ChangeDb
+            | Action::NextDb
+            | Action::PreviousDb
+            | Action::SwitchDb
+            | Action::AddDb
+            | Action::RenameDb
+            | Action::RemoveDb
+            | Action::SetDefaultDb
+            | Action::CloseDbPopup => "Databases",
+            Action::ScrollHelpDown | Action::ScrollHelpUp | Action::CloseHelp => "Help",
+            Action::ShowMessages
+            | Action::ScrollMessagesDown
+            | Action::ScrollMessagesUp
+            | Action::CloseMessages => "Messages",
+            Action::Quit | Action::RepeatLast | Action::ShowHelp | Action::OpenPalette => "General",
+        }
+    }
+
+    /// Whether `.` may repeat the action (only actions that change data and
+    /// don't need further input from the user)
+    pub fn is_repeatable(&self) -> bool {
+        matches!(
+            self,
+            Action::DeleteList
+                | Action::DeleteItem
+                | Action::DeleteSelected
+                | Action::ToggleDone
+                | Action::MoveListUp
+                | Action::MoveListDown
+                | Action::MoveListToTop
+                | Action::MoveListToBottom
+                | Action::MoveItemUp
+                | Action::MoveItemDown
+                | Action::MoveItemToTop
+                | Action::MoveItemToBottom
+        )
+    }
+
+    /// Key sequences bound to the action by a preset when judo.toml doesn't override them
+    pub fn default_keys(&self, preset: KeymapPreset) -> &'static [&'static str] {
+        if preset == KeymapPreset::Vim
+            && let Some(keys) = self.vim_keys()
+        {
+            return keys;
+        }
+
+        match self {
+            Action::Quit => &["q"],
+            Action::NextList => &["s"],
+            Action::PreviousList => &["w"],
+            Action::AddList => &["A"],
+            Action::ModifyList => &["M"],
+            Action::DeleteList => &["D"],
+            Action::MoveListUp => &["ctrl+w"],
+            Action::MoveListDown => &["ctrl+s"],
+            Action::MoveListToTop => &["alt+w"],
+            Action::MoveListToBottom => &["alt+s"],
+            Action::NextItem => &["down"],
+            Action::PreviousItem => &["up"],
+            Action::SelectItem => &["right"],
+            Action::DeselectItem => &["left"],
+            Action::AddItem => &["a"],
+            Action::ModifyItem => &["m"],
+            Action::DeleteItem => &["d"],
+            Action::ToggleDone => &["enter"],
+            Action::MoveItemUp => &["ctrl+up"],
+            Action::MoveItemDown => &["ctrl+down"],
+            Action::MoveItemToTop => &["alt+up"],
+            Action::MoveItemToBottom => &["alt+down"],
+            Action::FilterItems => &["/"],
+            Action::SaveView => &["V"],
+            Action::FocusLists
+            | Action::FocusItems
+            | Action::ToggleFocus
+            | Action::CursorDown
+            | Action::CursorUp
+            | Action::CursorTop
+            | Action::CursorBottom
+            | Action::DeleteSelected
+            | Action::AddItemBelow
+            | Action::AddItemAbove
+            | Action::RepeatLast => &[],
+            Action::ChangeDb => &["C"],
+            Action::NextDb => &["down"],
+            Action::PreviousDb => &["up"],
+            Action::SwitchDb => &["enter"],
+            Action::AddDb => &["A"],
+            Action::RenameDb => &["R"],
+            Action::RemoveDb => &["D"],
+            Action::SetDefaultDb => &["S"],
+            Action::CloseDbPopup => &["esc"],
+            Action::ShowHelp => &["?"],
+            Action::ScrollHelpDown => &["down", "j"],
+            Action::ScrollHelpUp => &["up", "k"],
+            Action::CloseHelp => &["esc", "?", "q"],
+            Action::OpenPalette => &[":", "ctrl+p"],
+            Action::ShowMessages => &["L"],
+            Action::ScrollMessagesDown => &["down", "j"],
+            Action::ScrollMessagesUp => &["up", "k"],
+            Action::CloseMessages => &["esc", "q", "L"],
+        }
+    }
+
+    /// Bindings of the vim preset that differ from the classic ones
+    fn vim_keys(&self) -> Option<&'static [&'static str]> {
+        let keys: &'static [&'static str] = match self {
+            Action::FocusLists => &["K", "h"],
+            Action::FocusItems => &["J", "l"],
+            Action::ToggleFocus => &["tab"],
+            Action::CursorDown => &["j"],
+            Action::CursorUp => &["k"],
+            Action::CursorTop => &["g g"],
+            Action::CursorBottom => &["G"],
+            Action::DeleteSelected => &["d d"],
+            Action::AddItemBelow => &["o"],
+            Action::AddItemAbove => &["O"],
+            Action::RepeatLast => &["."],
+            // "d" now starts "dd", which deletes in the focused pane
+            Action::DeleteItem => &[],
+            Action::ToggleDone => &["x", "enter"],
+            Action::ModifyItem => &["m", "c c"],
+            Action::NextDb => &["down", "j"],
+            Action::PreviousDb => &["up", "k"],
+            _ => return None,
+        };
+        Some(keys)
+    }
+
+    /// Human readable description of the action
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit application",
+            Action::NextList => "Move down in lists",
+            Action::PreviousList => "Move up in lists",
+            Action::AddList => "Add new list",
+            Action::ModifyList => "Modify selected list",
+            Action::DeleteList => "Delete selected list",
+            Action::MoveListUp => "Move list up in ordering",
+            Action::MoveListDown => "Move list down in ordering",
+            Action::MoveListToTop => "Move list to the top",
+            Action::MoveListToBottom => "Move list to the bottom",
+            Action::NextItem => "Move down in items",
+            Action::PreviousItem => "Move up in items",
+            Action::SelectItem => "Select first item in list",
+            Action::DeselectItem => "Deselect current item",
+            Action::AddItem => "Add new item to selected list",
+            Action::ModifyItem => "Modify selected item",
+            Action::DeleteItem => "Delete selected item",
+            Action::ToggleDone => "Toggle item completion status",
+            Action::MoveItemUp => "Move item up in ordering",
+            Action::MoveItemDown => "Move item down in ordering",
+            Action::MoveItemToTop => "Move item to the top of its list",
+            Action::MoveItemToBottom => "Move item to the bottom of its list",
+            Action::FocusLists => "Focus the lists pane",
+            Action::FocusItems => "Focus the items pane",
+            Action::ToggleFocus => "Switch focus between lists and items",
+            Action::CursorDown => "Move down in the focused pane",
+            Action::CursorUp => "Move up in the focused pane",
+            Action::CursorTop => "Go to the first entry of the focused pane",
+            Action::CursorBottom => "Go to the last entry of the focused pane",
+            Action::DeleteSelected => "Delete selected list or item in the focused pane",
+            Action::AddItemBelow => "Insert new item below the selected one",
+            Action::AddItemAbove => "Insert new item above the selected one",
+            Action::FilterItems => "Filter items with a query",
+            Action::SaveView => "Save the filter as a view",
+            Action::RepeatLast => "Repeat the last change",
+            Action::ChangeDb => "Change/switch database",
+            Action::NextDb => "Move down in database list",
+            Action::PreviousDb => "Move up in database list",
+            Action::SwitchDb => "Switch to selected database",
+            Action::AddDb => "Add new database",
+            Action::RenameDb => "Rename selected database",
+            Action::RemoveDb => "Remove selected database",
+            Action::SetDefaultDb => "Set selected database as default",
+            Action::CloseDbPopup => "Return to main screen",
+            Action::ShowHelp => "Show key bindings",
+            Action::ScrollHelpDown => "Scroll help down",
+            Action::ScrollHelpUp => "Scroll help up",
+            Action::CloseHelp => "Close help",
+            Action::OpenPalette => "Open command palette",
+            Action::ShowMessages => "Show message log",
+            Action::ScrollMessagesDown => "Scroll messages down",
+            Action::ScrollMessagesUp => "Scroll messages up",
+            Action::CloseMessages => "Close message log",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    /// Use the same snake_case name as in the [keys] section of judo.toml
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = toml::Value::try_from(self)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    /// Parse the snake_case name of the [keys] section of judo.toml
+    fn from_str(s: &str) -> Result<Self> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.to_string() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown action '{}'", s))
+    }
+}
+
+/// A key together with its modifiers, e.g. `ctrl+w` or `A`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Build a chord from a key event, normalising Shift into the character
+    /// so that `A` and `shift+a` are the same chord regardless of the terminal
+    pub fn from_event(key: KeyEvent) -> Self {
+        Self::normalised(key.code, key.modifiers)
+    }
+
+    fn normalised(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            code => code,
+        };
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Plain character of the chord, if it has no modifiers
+    pub fn as_plain_char(&self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() && c != ' ' => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // A lone "+" is the plus key, otherwise "+" separates modifiers
+        let (modifier_names, key_name) = match s.rsplit_once('+') {
+            Some((mods, "")) if mods.ends_with('+') || mods.is_empty() => {
+                (mods.trim_end_matches('+'), "+")
+            }
+            Some((mods, key)) => (mods, key),
+            None => ("", s),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => bail!("Unknown modifier '{}' in key '{}'", name, s),
+            };
+        }
+
+        let mut chars = key_name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key_name.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => bail!("Unknown key '{}'", s),
+                },
+            },
+        };
+
+        Ok(Self::normalised(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            code => write!(f, "{}", code),
+        }
+    }
+}
+
+/// One or more chords pressed in succession, e.g. `g g` (shown as "gg")
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyChord>);
+
+impl KeySequence {
+    /// Plain character of a single-chord sequence without modifiers
+    pub fn as_plain_char(&self) -> Option<char> {
+        match self.0.as_slice() {
+            [chord] => chord.as_plain_char(),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = anyhow::Error;
+
+    /// Chords of a sequence are separated by spaces, e.g. "g g" or "ctrl+x d"
+    fn from_str(s: &str) -> Result<Self> {
+        let chords = s
+            .split_whitespace()
+            .map(KeyChord::from_str)
+            .collect::<Result<Vec<_>>>()?;
+        if chords.is_empty() {
+            bail!("Empty key binding");
+        }
+        Ok(Self(chords))
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Runs of plain characters read best glued together, like vim's "gg"
+        let separator = if self.0.iter().all(|c| c.as_plain_char().is_some()) {
+            ""
+        } else {
+            " "
+        };
+        let labels: Vec<String> = self.0.iter().map(KeyChord::to_string).collect();
+        write!(f, "{}", labels.join(separator))
+    }
+}
+
+/// Outcome of looking up the keys typed so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// The keys are bound to an action
+    Action(Action),
+    /// The keys start one or more longer sequences
+    Prefix,
+    /// Nothing is bound to the keys
+    Unbound,
+}
+
+/// Active key bindings: the preset defaults with the overrides from judo.toml applied
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    /// Preset the bindings are based on
+    preset: KeymapPreset,
+    /// Sequences bound to each action, in the order they were declared
+    bindings: HashMap<Action, Vec<KeySequence>>,
+    /// Reverse lookup of the action bound to a sequence, per context
+    lookup: HashMap<(KeyContext, KeySequence), Action>,
+    /// Proper prefixes of the bound sequences, per context
+    prefixes: HashSet<(KeyContext, Vec<KeyChord>)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::new(KeymapPreset::Default, &BTreeMap::new())
+            .expect("Default key bindings must not conflict")
+    }
+}
+
+impl KeyMap {
+    /// Build the key map of the `keymap` preset and [keys] overrides of judo.toml
+    pub fn from_config(preset: Option<&str>, keys: &BTreeMap<String, KeyBinding>) -> Result<Self> {
+        let preset = match preset {
+            Some(name) => name
+                .parse()
+                .with_context(|| "Invalid keymap in judo.toml")?,
+            None => KeymapPreset::default(),
+        };
+
+        keys.iter()
+            .map(|(name, binding)| Ok((name.parse()?, binding.clone())))
+            .collect::<Result<BTreeMap<Action, KeyBinding>>>()
+            .and_then(|overrides| Self::new(preset, &overrides))
+            .with_context(|| "Invalid [keys] section in judo.toml")
+    }
+
+    /// Build the key map from a preset and the [keys] overrides, failing on unknown
+    /// keys or on two actions of the same context sharing a sequence (or a prefix of it)
+    pub fn new(preset: KeymapPreset, overrides: &BTreeMap<Action, KeyBinding>) -> Result<Self> {
+        let mut bindings = HashMap::new();
+        let mut lookup = HashMap::new();
+
+        for action in Action::ALL {
+            let sequences = match overrides.get(&action) {
+                Some(binding) => binding
+                    .sequences()
+                    .into_iter()
+                    .map(KeySequence::from_str)
+                    .collect::<Result<Vec<_>>>()?,
+                None => action
+                    .default_keys(preset)
+                    .iter()
+                    .map(|k| KeySequence::from_str(k))
+                    .collect::<Result<Vec<_>>>()?,
+            };
+
+            for sequence in &sequences {
+                for context in action.contexts() {
+                    if let Some(other) = lookup.insert((*context, sequence.clone()), action)
+                        && other != action
+                    {
+                        bail!(
+                            "Key '{}' is bound to both '{}' and '{}'",
+                            sequence,
+                            other,
+                            action
+                        );
+                    }
+                }
+            }
+
+            bindings.insert(action, sequences);
+        }
+
+        // A sequence can't also be the start of a longer one, or the longer one
+        // could never be typed
+        let mut prefixes = HashSet::new();
+        for ((context, sequence), action) in &lookup {
+            for len in 1..sequence.0.len() {
+                let prefix = KeySequence(sequence.0[..len].to_vec());
+                if let Some(other) = lookup.get(&(*context, prefix.clone())) {
+                    bail!(
+                        "Key '{}' of '{}' shadows '{}' of '{}'",
+                        prefix,
+                        other,
+                        sequence,
+                        action
+                    );
+                }
+                prefixes.insert((*context, prefix.0));
+            }
+        }
+
+        Ok(Self {
+            preset,
+            bindings,
+            lookup,
+            prefixes,
+        })
+    }
+
+    /// Look up the chords typed so far in the given context
+    pub fn resolve(&self, context: KeyContext, chords: &[KeyChord]) -> Resolution {
+        if let Some(action) = self.lookup.get(&(context, KeySequence(chords.to_vec()))) {
+            Resolution::Action(*action)
+        } else if self.prefixes.contains(&(context, chords.to_vec())) {
+            Resolution::Prefix
+        } else {
+            Resolution::Unbound
+        }
+    }
+
+    /// Whether a number typed before a command repeats it (vim style counts)
+    pub fn counts_enabled(&self) -> bool {
+        self.preset == KeymapPreset::Vim
+    }
+
+    /// Whether any binding moves the focus between the lists and items panes
+    pub fn uses_focus(&self) -> bool {
+        [Action::FocusLists, Action::FocusItems, Action::ToggleFocus]
+            .iter()
+            .any(|action| !self.keys(*action).is_empty())
+    }
+
+    /// Sequences bound to an action
+    pub fn keys(&self, action: Action) -> &[KeySequence] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Label of the first chord bound to an action (empty if unbound)
+    pub fn key_label(&self, action: Action) -> String {
+        self.keys(action)
+            .first()
+            .map(|sequence| sequence.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Split a command hint into its highlighted key and plain label, e.g.
+    /// ("[A]", "dd") if `A` is bound and the label is "Add", or ("[x]", " Del") otherwise
+    pub fn hint(&self, action: Action, label: &str) -> (String, String) {
+        match self.keys(action).first() {
+            Some(sequence) => {
+                let mut label_chars = label.chars();
+                match (sequence.as_plain_char(), label_chars.next()) {
+                    (Some(key), Some(first)) if key.eq_ignore_ascii_case(&first) => {
+                        (format!("[{}]", key), label_chars.collect())
+                    }
+                    (_, None) => (format!("[{}]", sequence), String::new()),
+                    _ => (format!("[{}]", sequence), format!(" {}", label)),
+                }
+            }
+            None => (String::new(), String::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    /// Resolve a single key press
+    fn action(key_map: &KeyMap, context: KeyContext, key: KeyEvent) -> Option<Action> {
+        match key_map.resolve(context, &[KeyChord::from_event(key)]) {
+            Resolution::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_chords() -> Result<()> {
+        let chord: KeyChord = "ctrl+w".parse()?;
+        assert_eq!(chord.code, KeyCode::Char('w'));
+        assert_eq!(chord.modifiers, KeyModifiers::CONTROL);
+
+        let chord: KeyChord = "shift+a".parse()?;
+        assert_eq!(chord, "A".parse()?);
+
+        let chord: KeyChord = "alt+Down".parse()?;
+        assert_eq!(chord.code, KeyCode::Down);
+        assert_eq!(chord.modifiers, KeyModifiers::ALT);
+
+        let chord: KeyChord = "+".parse()?;
+        assert_eq!(chord.code, KeyCode::Char('+'));
+
+        let chord: KeyChord = "ctrl++".parse()?;
+        assert_eq!(chord.code, KeyCode::Char('+'));
+        assert_eq!(chord.modifiers, KeyModifiers::CONTROL);
+
+        assert_eq!("f5".parse::<KeyChord>()?.code, KeyCode::F(5));
+        assert!("hyper+x".parse::<KeyChord>().is_err());
+        assert!("bogus".parse::<KeyChord>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_bindings_dispatch() {
+        let key_map = KeyMap::default();
+
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Main,
+                key(KeyCode::Char('q'), KeyModifiers::NONE)
+            ),
+            Some(Action::Quit)
+        );
+        // Terminals report uppercase letters with Shift
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Main,
+                key(KeyCode::Char('A'), KeyModifiers::SHIFT)
+            ),
+            Some(Action::AddList)
+        );
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Database,
+                key(KeyCode::Char('A'), KeyModifiers::SHIFT)
+            ),
+            Some(Action::AddDb)
+        );
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Main,
+                key(KeyCode::Up, KeyModifiers::CONTROL)
+            ),
+            Some(Action::MoveItemUp)
+        );
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Main,
+                key(KeyCode::Char('z'), KeyModifiers::NONE)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_overrides_replace_defaults() -> Result<()> {
+        let overrides = BTreeMap::from([
+            (
+                Action::Quit,
+                KeyBinding::Many(vec!["x".into(), "ctrl+c".into()]),
+            ),
+            (Action::DeleteItem, KeyBinding::One("q".into())),
+        ]);
+        let key_map = KeyMap::new(KeymapPreset::Default, &overrides)?;
+
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Main,
+                key(KeyCode::Char('c'), KeyModifiers::CONTROL)
+            ),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Main,
+                key(KeyCode::Char('q'), KeyModifiers::NONE)
+            ),
+            Some(Action::DeleteItem)
+        );
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Main,
+                key(KeyCode::Char('d'), KeyModifiers::NONE)
+            ),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflicting_bindings_are_rejected() {
+        let overrides = BTreeMap::from([(Action::AddItem, KeyBinding::One("q".into()))]);
+        let err = KeyMap::new(KeymapPreset::Default, &overrides)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'quit'"), "{err}");
+        assert!(err.contains("'add_item'"), "{err}");
+
+        // The same chord may be reused across contexts
+        let overrides = BTreeMap::from([(Action::AddDb, KeyBinding::One("a".into()))]);
+        assert!(KeyMap::new(KeymapPreset::Default, &overrides).is_ok());
+    }
+
+    #[test]
+    fn test_hints_follow_bindings() -> Result<()> {
+        let key_map = KeyMap::default();
+        assert_eq!(
+            key_map.hint(Action::AddList, "Add"),
+            ("[A]".into(), "dd".into())
+        );
+
+        let overrides = BTreeMap::from([(Action::AddList, KeyBinding::One("n".into()))]);
+        let key_map = KeyMap::new(KeymapPreset::Default, &overrides)?;
+        assert_eq!(
+            key_map.hint(Action::AddList, "Add"),
+            ("[n]".into(), " Add".into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sequences_and_prefixes() -> Result<()> {
+        let key_map = KeyMap::new(KeymapPreset::Vim, &BTreeMap::new())?;
+        let g: KeyChord = "g".parse()?;
+        let d: KeyChord = "d".parse()?;
+
+        assert_eq!(key_map.resolve(KeyContext::Main, &[g]), Resolution::Prefix);
+        assert_eq!(
+            key_map.resolve(KeyContext::Main, &[g, g]),
+            Resolution::Action(Action::CursorTop)
+        );
+        assert_eq!(
+            key_map.resolve(KeyContext::Main, &[d, d]),
+            Resolution::Action(Action::DeleteSelected)
+        );
+        assert_eq!(
+            key_map.resolve(KeyContext::Main, &[g, d]),
+            Resolution::Unbound
+        );
+        assert_eq!(key_map.key_label(Action::CursorTop), "gg");
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_conflicts_are_rejected() {
+        // "d" would make "d d" unreachable
+        let overrides = BTreeMap::from([(Action::DeleteItem, KeyBinding::One("d".into()))]);
+        let err = KeyMap::new(KeymapPreset::Vim, &overrides)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("delete_item"), "{err}");
+        assert!(err.contains("delete_selected"), "{err}");
+    }
+
+    #[test]
+    fn test_vim_preset_keeps_classic_bindings_it_does_not_replace() -> Result<()> {
+        let key_map = KeyMap::new(KeymapPreset::Vim, &BTreeMap::new())?;
+        assert!(key_map.counts_enabled());
+        assert!(key_map.uses_focus());
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Main,
+                key(KeyCode::Char('A'), KeyModifiers::SHIFT)
+            ),
+            Some(Action::AddList)
+        );
+        assert_eq!(
+            action(
+                &key_map,
+                KeyContext::Main,
+                key(KeyCode::Char('x'), KeyModifiers::NONE)
+            ),
+            Some(Action::ToggleDone)
+        );
+
+        let key_map = KeyMap::default();
+        assert!(!key_map.counts_enabled());
+        assert!(!key_map.uses_focus());
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_names() -> Result<()> {
+        assert_eq!(Action::MoveListToTop.to_string(), "move_list_to_top");
+
+        assert_eq!("quit".parse::<Action>()?, Action::Quit);
+        assert!("fly".parse::<Action>().is_err());
+        assert_eq!("vim".parse::<KeymapPreset>()?, KeymapPreset::Vim);
+        assert!("emacs".parse::<KeymapPreset>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_config() -> Result<()> {
+        let keys = BTreeMap::from([(
+            "quit".to_string(),
+            KeyBinding::Many(vec!["Q".to_string(), "ctrl+c".to_string()]),
+        )]);
+        let key_map = KeyMap::from_config(Some("vim"), &keys)?;
+        assert!(key_map.counts_enabled());
+        assert_eq!(key_map.key_label(Action::Quit), "Q");
+
+        for (keymap, keys, message) in [
+            ("emacs", None, "Invalid keymap in judo.toml"),
+            ("vim", Some(("fly", "x")), "Unknown action 'fly'"),
+            ("vim", Some(("quit", "hyper+x")), "Unknown modifier 'hyper'"),
+        ] {
+            let keys = keys
+                .map(|(action, key)| (action.to_string(), KeyBinding::One(key.to_string())))
+                .into_iter()
+                .collect();
+            let err = KeyMap::from_config(Some(keymap), &keys).unwrap_err();
+            assert!(format!("{:#}", err).contains(message), "{err:#}");
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,9 @@
 pub mod app;
 pub mod db;
 pub mod ui;
-pub mod cli;
\ No newline at end of file
+pub mod cli;
+pub mod error;
+pub mod formats;
+pub mod keymap;
+pub mod logging;
+pub mod sync;
\ No newline at end of file
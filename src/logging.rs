@@ -0,0 +1,122 @@
+//! File logging for diagnosing bug reports.
+//!
+//! Logs are written to daily rotating files in the `logs` folder of the judo data
+//! directory, never to the terminal (which belongs to the TUI). The level comes from
+//! the `JUDO_LOG` environment variable if set, otherwise from `[logging]` in judo.toml.
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// Environment variable overriding the configured log filter
+pub const LOG_ENV_VAR: &str = "JUDO_LOG";
+
+const DEFAULT_LEVEL: &str = "info";
+const DEFAULT_MAX_FILES: usize = 7;
+const LOG_FILE_PREFIX: &str = "judo";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// `[logging]` section of judo.toml
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Filter in `tracing` syntax: a level ("debug") or per-module
+    /// directives ("warn,judo::db=trace")
+    pub level: String,
+    /// Number of daily log files kept before the oldest is deleted
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_LEVEL.to_string(),
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Filter built from the configured level
+    pub fn filter(&self) -> Result<EnvFilter> {
+        parse_filter(&self.level)
+            .with_context(|| format!("Invalid log level '{}' in judo.toml", self.level))
+    }
+}
+
+/// Parse a filter in `tracing` syntax, rejecting anything it can't make sense of
+fn parse_filter(directives: &str) -> Result<EnvFilter> {
+    Ok(EnvFilter::builder().parse(directives)?)
+}
+
+/// Folder the log files are written to
+pub fn log_dir() -> Result<PathBuf> {
+    Ok(dirs::data_dir()
+        .with_context(|| "Could not find data directory")?
+        .join("judo")
+        .join("logs"))
+}
+
+/// Install the global subscriber writing to rotating files in `dir`.
+///
+/// The returned guard flushes buffered lines when dropped, so it must be kept
+/// alive until the application exits.
+pub fn init(config: &LoggingConfig, dir: &Path) -> Result<WorkerGuard> {
+    let filter = match std::env::var(LOG_ENV_VAR) {
+        Ok(directives) => parse_filter(&directives)
+            .with_context(|| format!("Invalid {} '{}'", LOG_ENV_VAR, directives))?,
+        Err(_) => config.filter()?,
+    };
+
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(config.max_files.max(1))
+        .build(dir)
+        .with_context(|| "Failed to create log file")?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(false)
+        // Closing spans log how long each DB operation and key press took
+        .with_span_events(FmtSpan::CLOSE)
+        .try_init()
+        .map_err(|e| anyhow::anyhow!("Failed to install logger: {}", e))?;
+
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_levels() {
+        assert!(LoggingConfig::default().filter().is_ok());
+
+        let config = LoggingConfig {
+            level: "warn,judo::db=trace".to_string(),
+            ..LoggingConfig::default()
+        };
+        assert!(config.filter().is_ok());
+
+        let config = LoggingConfig {
+            level: "judo=loud".to_string(),
+            ..LoggingConfig::default()
+        };
+        assert!(config.filter().is_err());
+    }
+}
@@ -1,97 +1,223 @@
 //! Judo - A terminal-based todo list application
 use clap::Parser;
+use std::process::ExitCode;
 use color_eyre::Result;
-use judo::{app::App, cli::{ops, args::{Cli, Commands, DbCommands, ItemCommands, ListCommands}}};
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::execute;
+use judo::{app::App, cli::{ops, args::{BackupCommands, Cli, Commands, DbCommands, ItemCommands, ListCommands, Move, SyncCommands, ViewCommands}}, db::config::Config, error::JudoError, logging};
 
 /// Application entry point
 ///
 /// Initializes the terminal, creates the application instance, runs the main loop,
 /// and properly restores the terminal on exit.
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     let cli = Cli::parse();
-    
-    // Set up the app
-    let app = App::new().await;
 
-    //Handle CLI arguments
-    match cli.command {
-        //Database commands
-        Some(Commands::Dbs { command }) => {
-            match command {
-                Some(DbCommands::Show) => {
-                    ops::list_dbs(&app);
-                    return Ok(())
+    // Set up file logging. Failing to log is no reason not to run, so only warn
+    // (an invalid config is reported when setting the app up below)
+    let _log_guard = match Config::read() {
+        Ok(config) => {
+            match logging::log_dir().and_then(|dir| logging::init(&config.logging, &dir)) {
+                Ok(guard) => Some(guard),
+                Err(e) => {
+                    eprintln!("Warning: logging disabled: {:#}", e);
+                    None
                 }
-                Some(DbCommands::Add { name }) => {
-                    ops::add_db(app, name).await;
-                    return Ok(())
-                }
-                None => {}
             }
         }
-        //List commands
-        Some(Commands::Lists { command }) => {
-            match command {
-                Some(ListCommands::Show) => {
-                    ops::list_lists(&app).await;
-                    return Ok(());
-                }
-                Some(ListCommands::Add {name, db }) => {
-                    ops::add_list(&app, name, &db).await;
-                    return Ok(());
+        Err(_) => None,
+    };
+
+    // Errors are reported here rather than by exiting, so that the log guard
+    // is dropped and flushes the log
+    match run_command(cli).await {
+        Ok(None) => Ok(ExitCode::SUCCESS),
+        Ok(Some(app)) => run_tui(app).await.map(|()| ExitCode::SUCCESS),
+        Err(e) => Ok(fail(e)),
+    }
+}
+
+/// Run the command given on the command line, or set the app up for the TUI
+/// when there is none
+async fn run_command(cli: Cli) -> judo::error::Result<Option<App>> {
+    // Checking, repairing, migrating and restoring databases can't wait for the
+    // app, which fails to set up on the very databases they are there for
+    let command = match cli.command {
+        Some(Commands::Dbs { command: Some(DbCommands::Doctor { name, fix, orphans }) }) => {
+            let config = Config::read()?;
+            ops::doctor_dbs(&config, &name, fix, orphans).await?;
+            return Ok(None);
+        }
+        Some(Commands::Dbs { command: Some(DbCommands::Migrate { name, dry_run }) }) => {
+            let config = Config::read()?;
+            ops::migrate_dbs(&config, &name, dry_run).await?;
+            return Ok(None);
+        }
+        Some(Commands::Backup { command: Some(command) }) => {
+            let config = Config::read()?;
+            let result = match command {
+                BackupCommands::Create { db, all } => ops::create_backup(&config, &db, all).await,
+                BackupCommands::List { db } => {
+                    ops::list_backups(&config, &db, cli.format.unwrap_or_default())
                 }
-                Some(ListCommands::Delete {name, id, db }) => {
-                    ops::delete_list(&app, name, id, &db).await;
-                    return Ok(());
+                BackupCommands::Restore { db, name } => {
+                    ops::restore_backup(&config, &db, name).await
                 }
-                None => {}
-            }
+            };
+            result?;
+            return Ok(None);
         }
+        command => command,
+    };
+
+    // Set up the app
+    let app = App::new(cli.read_only).await?;
+
+    // Without a subcommand fall through to the TUI, which reports failed
+    // backups in its messages
+    if !command.as_ref().is_some_and(Commands::is_complete) {
+        return Ok(Some(app));
+    }
+    for warning in app.backup_databases().await {
+        eprintln!("Warning: {}", warning);
+    }
+
+    //Handle CLI arguments
+    let format = cli.format.unwrap_or_default();
+    let result = match command {
+        //Database commands
+        Some(Commands::Dbs { command: Some(command) }) => match command {
+            DbCommands::Show => ops::list_dbs(&app, format),
+            DbCommands::Add { name } => ops::add_db(app, name).await,
+            DbCommands::Remove { name, delete_file } => ops::remove_db(app, name, delete_file),
+            DbCommands::Rename { name, new_name } => ops::rename_db(app, name, new_name),
+            DbCommands::Default { name } => ops::set_default_db(app, name),
+            DbCommands::Doctor { .. } | DbCommands::Migrate { .. } => {
+                unreachable!("run before setting up the app")
+            }
+        },
+        //List commands
+        Some(Commands::Lists { command: Some(command) }) => match command {
+            ListCommands::Show => ops::list_lists(&app, format).await,
+            ListCommands::Add { name, db } => ops::add_list(&app, name, &db).await,
+            ListCommands::Delete { name, id, db } => ops::delete_list(&app, name, id, &db).await,
+            ListCommands::Rename {
+                name,
+                id,
+                new_name,
+                db,
+            } => ops::rename_list(&app, name, id, new_name, &db).await,
+            ListCommands::Move {
+                name,
+                id,
+                movement,
+                db,
+            } => ops::move_list(&app, name, id, (&movement).into(), &db).await,
+        },
         //Item commands
-        Some(Commands::Items { command }) => {
-            match command {
-                Some(ItemCommands::Show) => {
-                    ops::list_items(&app).await;
-                    return Ok(());
-                }
-                Some(ItemCommands::Add {
-                    name, 
-                    db, 
-                    list_name, 
-                    list_id 
-                }) => {
-                    ops::add_item(&app, name, &db, list_id, list_name).await;
-                    return Ok(());
-                }
-                Some(ItemCommands::Delete {
-                    id, 
-                    db,
-                }) => {
-                    ops::delete_item(&app, id, &db).await;
-                    return Ok(());
-                }
-                Some(ItemCommands::ToggleDone { 
-                    id, 
-                    db 
-                }) => {
-                    ops::toggle_done_item(&app, id, &db).await;
-                    return Ok(());
-                }
-                None => {}
+        Some(Commands::Items { command: Some(command) }) => match command {
+            ItemCommands::Show { filters } => ops::list_items(&app, &filters, format).await,
+            ItemCommands::Add {
+                name,
+                db,
+                list_name,
+                list_id,
+                priority,
+                due,
+            } => ops::add_item(&app, name, &db, list_id, list_name, priority, due).await,
+            ItemCommands::Edit {
+                id,
+                db,
+                name,
+                priority,
+                no_priority,
+                due,
+                no_due,
+            } => {
+                // Some(None) removes the field
+                let priority = if no_priority { Some(None) } else { priority.map(Some) };
+                let due = if no_due { Some(None) } else { due.map(Some) };
+                ops::edit_item(&app, id, &db, name, priority, due).await
             }
+            ItemCommands::MoveUp { id, db } => ops::move_item(&app, id, Move::Up, &db).await,
+            ItemCommands::MoveDown { id, db } => ops::move_item(&app, id, Move::Down, &db).await,
+            ItemCommands::MoveTo { id, position, db } => {
+                ops::move_item(&app, id, Move::To(position), &db).await
+            }
+            ItemCommands::Delete { id, db } => ops::delete_item(&app, id, &db).await,
+            ItemCommands::ToggleDone { id, db } => ops::toggle_done_item(&app, id, &db).await,
+        },
+        //View commands
+        Some(Commands::Views { command: Some(command) }) => match command {
+            ViewCommands::Show => ops::list_views(&app, format),
+            ViewCommands::Add { name, query, db } => ops::add_view(app, name, query, db),
+            ViewCommands::Remove { name } => ops::remove_view(app, name),
+        },
+        //Import and export
+        Some(Commands::Export {
+            db,
+            list_name,
+            list_id,
+        }) => ops::export_db(&app, &db, list_name, list_id, cli.format).await,
+        Some(Commands::Import { file, db, mode }) => {
+            ops::import_db(&app, &file, &db, mode, cli.format).await
         }
-        None => {}
-    }
+        //Sync
+        Some(Commands::Sync { command: Some(command) }) => match command {
+            SyncCommands::Caldav { db, url, username } => {
+                ops::sync_caldav(&app, &db, url, username).await
+            }
+        },
+        _ => unreachable!("the TUI is run without a command"),
+    };
+
+    result?;
+    Ok(None)
+}
 
+/// Run the TUI until the user quits
+async fn run_tui(app: App) -> Result<()> {
     // Set the terminal up
     let mut terminal = ratatui::init();
+    let mouse_capture = match MouseCapture::enable() {
+        Ok(mouse_capture) => mouse_capture,
+        Err(e) => {
+            ratatui::restore();
+            return Err(e.into());
+        }
+    };
 
     // Create and run the app
     let app_result = app.run(&mut terminal).await;
 
     // Restore terminal to original state
+    drop(mouse_capture);
     ratatui::restore();
 
     app_result
-}
\ No newline at end of file
+}
+
+/// Mouse capture of the terminal, released when dropped, so that it is also
+/// released when the app fails or panics
+struct MouseCapture;
+
+impl MouseCapture {
+    fn enable() -> std::io::Result<Self> {
+        execute!(std::io::stdout(), EnableMouseCapture)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for MouseCapture {
+    fn drop(&mut self) {
+        // Nothing left to do if the terminal is gone
+        let _ = execute!(std::io::stdout(), DisableMouseCapture);
+    }
+}
+
+/// Report the error and return its exit code (see `judo::error`)
+fn fail(e: JudoError) -> ExitCode {
+    eprintln!("Error: {}", e);
+    ExitCode::from(e.exit_code() as u8)
+}
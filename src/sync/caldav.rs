@@ -0,0 +1,432 @@
+//! Two-way sync of a database with the to-dos of a CalDAV collection.
+//!
+//! The `caldav_sync_state` table records, for every item synced with a
+//! collection, the resource holding it on the server, the ETag that resource had
+//! and the `updated_at` the item had at the last sync. A sync compares both sides
+//! with that state:
+//!
+//! - changed on one side only: the change is copied to the other side
+//! - changed on both sides: a conflict, the side changed last (`updated_at` of
+//!   the item against `LAST-MODIFIED` of the to-do) wins
+//! - deleted on one side: deleted on the other too, unless it was changed
+//!   there, in which case it's copied back
+//! - new on one side: created on the other
+//!
+//! Writes to the server are conditional on the ETag read at the start of the
+//! sync, so a to-do changed meanwhile is left alone (and synced next time).
+//! The list of an item is the category of its to-do, to-dos without one go to
+//! the "Inbox" list.
+use crate::db::models::{ListedItem, NewTodoList, TodoList};
+use crate::db::ops::{ItemQuery, ORDERING_GAP};
+use crate::db::snapshot::ItemSnapshot;
+use crate::formats::ical;
+use crate::sync::webdav::{CalDavClient, Resource, Written};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use tracing::{instrument, warn};
+
+/// Environment variable with the password of the CalDAV user
+pub const PASSWORD_ENV_VAR: &str = "JUDO_CALDAV_PASSWORD";
+
+/// List of the to-dos without categories
+const DEFAULT_LIST: &str = "Inbox";
+
+/// What a sync did
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub created_local: usize,
+    pub updated_local: usize,
+    pub deleted_local: usize,
+    pub created_remote: usize,
+    pub updated_remote: usize,
+    pub deleted_remote: usize,
+    /// Items changed on both sides since the last sync
+    pub conflicts: usize,
+    /// To-dos that couldn't be read, or changed on the server during the sync
+    pub skipped: usize,
+}
+
+/// An item as it was at the last sync
+#[derive(Debug, FromRow)]
+struct SyncState {
+    item_id: i64,
+    href: String,
+    uid: String,
+    etag: Option<String>,
+    synced_at: DateTime<Utc>,
+}
+
+/// A to-do of the collection
+#[derive(Debug)]
+struct RemoteTodo {
+    href: String,
+    etag: Option<String>,
+    uid: Option<String>,
+    category: Option<String>,
+    item: ItemSnapshot,
+}
+
+impl RemoteTodo {
+    fn parse(resource: Resource) -> Option<Self> {
+        let calendar = ical::parse_calendar(&resource.data).ok()?;
+        let todo = calendar.todos.into_iter().next()?;
+        (!todo.item.name.is_empty()).then_some(Self {
+            href: resource.href,
+            etag: resource.etag,
+            uid: todo.uid,
+            category: todo.category,
+            item: todo.item,
+        })
+    }
+
+    /// Whether the to-do was changed after the item
+    fn is_newer_than(&self, item: &ListedItem) -> bool {
+        self.item
+            .updated_at
+            .is_some_and(|modified| modified > item.item.updated_at)
+    }
+}
+
+/// Sync a database with a CalDAV collection
+#[instrument(skip_all, fields(collection = %client.url()), err)]
+pub async fn sync(pool: &SqlitePool, client: &CalDavClient) -> Result<SyncSummary> {
+    let mut syncer = Syncer {
+        pool,
+        client,
+        collection: client.url().to_string(),
+        summary: SyncSummary::default(),
+    };
+
+    // To-dos of the server, by path, and the order they came in. Unreadable
+    // ones are left alone, and so are the items they were synced with.
+    let mut remote = HashMap::new();
+    let mut remote_order = Vec::new();
+    let mut skipped = HashSet::new();
+    for resource in client.todos().await? {
+        let href = resource.href.clone();
+        match RemoteTodo::parse(resource) {
+            Some(todo) => {
+                remote_order.push(href.clone());
+                remote.insert(href, todo);
+            }
+            None => {
+                warn!(href, "Skipping unreadable to-do");
+                syncer.summary.skipped += 1;
+                skipped.insert(href);
+            }
+        }
+    }
+
+    // Items of the database, by ID, and their order
+    let items = ItemQuery::new().fetch(pool).await?;
+    let local_order: Vec<i64> = items.iter().map(|item| item.item.id).collect();
+    let mut local: HashMap<i64, ListedItem> =
+        items.into_iter().map(|item| (item.item.id, item)).collect();
+
+    // Items synced before
+    for state in syncer.states().await? {
+        let item = local.remove(&state.item_id);
+        if skipped.contains(&state.href) {
+            continue;
+        }
+        let todo = remote.remove(&state.href);
+        let local_changed = item
+            .as_ref()
+            .is_some_and(|item| item.item.updated_at != state.synced_at);
+        let remote_changed = todo.as_ref().is_some_and(|todo| todo.etag != state.etag);
+
+        match (item, todo) {
+            (Some(item), Some(todo)) => match (local_changed, remote_changed) {
+                (false, false) => {}
+                (true, false) => {
+                    syncer
+                        .push(&item, &state.uid, &state.href, todo.etag.as_deref())
+                        .await?
+                }
+                (false, true) => syncer.pull(&todo, Some(item.item.id)).await?,
+                (true, true) => {
+                    syncer.summary.conflicts += 1;
+                    if todo.is_newer_than(&item) {
+                        syncer.pull(&todo, Some(item.item.id)).await?
+                    } else {
+                        syncer
+                            .push(&item, &state.uid, &state.href, todo.etag.as_deref())
+                            .await?
+                    }
+                }
+            },
+            // Deleted on the server after being changed here: put it back
+            (Some(item), None) if local_changed => {
+                syncer.push(&item, &state.uid, &state.href, None).await?
+            }
+            (Some(item), None) => syncer.delete_local(item.item.id).await?,
+            // Deleted here after being changed on the server: take it back
+            (None, Some(todo)) if remote_changed => {
+                syncer.forget(state.item_id).await?;
+                syncer.pull(&todo, None).await?;
+            }
+            (None, Some(_)) => syncer.delete_remote(&state).await?,
+            (None, None) => syncer.forget(state.item_id).await?,
+        }
+    }
+
+    // To-dos new on the server. One with the UID of an item was uploaded
+    // from this database before its sync state was lost, and is linked to it.
+    for href in remote_order {
+        let Some(todo) = remote.remove(&href) else {
+            continue;
+        };
+        let twin = local
+            .values()
+            .find(|item| todo.uid == Some(ical::item_uid(&ItemSnapshot::from(&item.item))))
+            .map(|item| item.item.id)
+            .and_then(|id| local.remove(&id));
+
+        match (twin, &todo.uid) {
+            (Some(item), Some(uid)) if !todo.is_newer_than(&item) => {
+                syncer
+                    .push(&item, uid, &todo.href, todo.etag.as_deref())
+                    .await?
+            }
+            (Some(item), _) => syncer.pull(&todo, Some(item.item.id)).await?,
+            (None, _) => syncer.pull(&todo, None).await?,
+        }
+    }
+
+    // Items new in this database
+    for id in local_order {
+        if let Some(item) = local.remove(&id) {
+            let uid = ical::item_uid(&ItemSnapshot::from(&item.item));
+            let href = client.new_href(&uid);
+            syncer.push(&item, &uid, &href, None).await?;
+        }
+    }
+
+    Ok(syncer.summary)
+}
+
+/// State of a sync in progress
+struct Syncer<'a> {
+    pool: &'a SqlitePool,
+    client: &'a CalDavClient,
+    /// URL of the collection, identifying its rows of the sync state
+    collection: String,
+    summary: SyncSummary,
+}
+
+impl Syncer<'_> {
+    /// Sync state of the items of the collection
+    async fn states(&self) -> Result<Vec<SyncState>> {
+        sqlx::query_as::<_, SyncState>(
+            r#"
+            SELECT item_id, href, uid, etag, synced_at
+            FROM caldav_sync_state
+            WHERE collection = ?1
+            ORDER BY item_id
+            "#,
+        )
+        .bind(&self.collection)
+        .fetch_all(self.pool)
+        .await
+        .with_context(|| "Failed to fetch CalDAV sync state")
+    }
+
+    /// Record that an item was synced, replacing its previous state and any other
+    /// item's state for the same resource
+    async fn remember(
+        &self,
+        item_id: i64,
+        href: &str,
+        uid: &str,
+        etag: Option<&str>,
+        synced_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO caldav_sync_state (collection, item_id, href, uid, etag, synced_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+        )
+        .bind(&self.collection)
+        .bind(item_id)
+        .bind(href)
+        .bind(uid)
+        .bind(etag)
+        .bind(synced_at)
+        .execute(self.pool)
+        .await
+        .with_context(|| "Failed to save CalDAV sync state")?;
+
+        Ok(())
+    }
+
+    /// Drop the sync state of an item
+    async fn forget(&self, item_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM caldav_sync_state WHERE collection = ?1 AND item_id = ?2")
+            .bind(&self.collection)
+            .bind(item_id)
+            .execute(self.pool)
+            .await
+            .with_context(|| "Failed to delete CalDAV sync state")?;
+
+        Ok(())
+    }
+
+    /// Write an item to the server, over the resource with this ETag or as a new one
+    async fn push(
+        &mut self,
+        item: &ListedItem,
+        uid: &str,
+        href: &str,
+        etag: Option<&str>,
+    ) -> Result<()> {
+        let data = ical::todo_to_ical(uid, Some(&item.list_name), &ItemSnapshot::from(&item.item));
+
+        match self.client.put(href, data, etag).await? {
+            // Without a new ETag the next sync sees the to-do as changed on the
+            // server, and copies it back unchanged
+            Written::Done(new_etag) => {
+                self.remember(
+                    item.item.id,
+                    href,
+                    uid,
+                    new_etag.as_deref(),
+                    item.item.updated_at,
+                )
+                .await?;
+                match etag {
+                    Some(_) => self.summary.updated_remote += 1,
+                    None => self.summary.created_remote += 1,
+                }
+            }
+            Written::Changed => self.summary.skipped += 1,
+        }
+        Ok(())
+    }
+
+    /// Copy a to-do into the item with this ID, or into a new item
+    async fn pull(&mut self, todo: &RemoteTodo, item_id: Option<i64>) -> Result<()> {
+        let list_name = todo.category.as_deref().unwrap_or(DEFAULT_LIST);
+        let list_id = self.list_id(list_name).await?;
+        let now = Utc::now();
+        let item = &todo.item;
+
+        let item_id = match item_id {
+            Some(item_id) => {
+                // Items moved to another list go to its bottom
+                sqlx::query(
+                    r#"
+                    UPDATE todo_items
+                    SET name = ?1, is_done = ?2, priority = ?3, due_date = ?4, updated_at = ?5,
+                        ordering = CASE WHEN list_id = ?6 THEN ordering ELSE
+                            (SELECT COALESCE(MAX(ordering), 0) + ?7 FROM todo_items WHERE list_id = ?6)
+                        END,
+                        list_id = ?6
+                    WHERE id = ?8
+                    "#,
+                )
+                .bind(&item.name)
+                .bind(item.is_done)
+                .bind(&item.priority)
+                .bind(item.due_date)
+                .bind(now)
+                .bind(list_id)
+                .bind(ORDERING_GAP)
+                .bind(item_id)
+                .execute(self.pool)
+                .await
+                .with_context(|| "Failed to update todo item from CalDAV")?;
+
+                self.summary.updated_local += 1;
+                item_id
+            }
+            None => {
+                let item_id = sqlx::query_scalar::<_, i64>(
+                    r#"
+                    INSERT INTO todo_items (list_id, name, is_done, priority, due_date, ordering, created_at, updated_at)
+                    VALUES (
+                        ?1, ?2, ?3, ?4, ?5,
+                        (SELECT COALESCE(MAX(ordering), 0) + ?6 FROM todo_items WHERE list_id = ?1),
+                        ?7, ?8
+                    )
+                    RETURNING id
+                    "#,
+                )
+                .bind(list_id)
+                .bind(&item.name)
+                .bind(item.is_done)
+                .bind(&item.priority)
+                .bind(item.due_date)
+                .bind(ORDERING_GAP)
+                .bind(item.created_at.unwrap_or(now))
+                .bind(now)
+                .fetch_one(self.pool)
+                .await
+                .with_context(|| "Failed to create todo item from CalDAV")?;
+
+                self.summary.created_local += 1;
+                item_id
+            }
+        };
+
+        // A to-do without UID gets the one of its item the next time it's written
+        let uid = match &todo.uid {
+            Some(uid) => uid.clone(),
+            None => format!("{}-{}@judo", now.timestamp_micros(), item_id),
+        };
+        self.remember(item_id, &todo.href, &uid, todo.etag.as_deref(), now)
+            .await
+    }
+
+    /// Delete an item deleted on the server
+    async fn delete_local(&mut self, item_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM todo_items WHERE id = ?1")
+            .bind(item_id)
+            .execute(self.pool)
+            .await
+            .with_context(|| "Failed to delete todo item")?;
+
+        self.forget(item_id).await?;
+        self.summary.deleted_local += 1;
+        Ok(())
+    }
+
+    /// Delete from the server the to-do of an item deleted here
+    async fn delete_remote(&mut self, state: &SyncState) -> Result<()> {
+        match self
+            .client
+            .delete(&state.href, state.etag.as_deref())
+            .await?
+        {
+            Written::Done(_) => {
+                self.forget(state.item_id).await?;
+                self.summary.deleted_remote += 1;
+            }
+            Written::Changed => self.summary.skipped += 1,
+        }
+        Ok(())
+    }
+
+    /// ID of the first list with this name, created if there's none
+    async fn list_id(&self, name: &str) -> Result<i64> {
+        let id = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM todo_lists WHERE name = ?1 ORDER BY ordering LIMIT 1",
+        )
+        .bind(name)
+        .fetch_optional(self.pool)
+        .await
+        .with_context(|| "Failed to fetch todo list")?;
+
+        match id {
+            Some(id) => Ok(id),
+            None => {
+                let new_list = NewTodoList {
+                    name: name.to_string(),
+                };
+                Ok(TodoList::create(self.pool, new_list).await?.id)
+            }
+        }
+    }
+}
@@ -0,0 +1,6 @@
+//! Synchronization of databases with task servers.
+//!
+//! [`webdav`] talks to the server, [`caldav`] decides what has to be copied
+//! which way and keeps track of what was synced in the database.
+pub mod caldav;
+pub mod webdav;
@@ -0,0 +1,323 @@
+//! The WebDAV requests of CalDAV (RFC 4791) needed to sync to-dos.
+//!
+//! Every to-do is a resource of the calendar collection holding an iCalendar
+//! object. Writes and deletes are conditional on the ETag the resource had when
+//! it was read, so that a change made on the server in the meantime (by a phone,
+//! say) is never overwritten.
+use anyhow::{Context, Result, bail};
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::{Client, Method, RequestBuilder, StatusCode, Url};
+use roxmltree::{Document, Node};
+
+const DAV_NS: &str = "DAV:";
+const CALDAV_NS: &str = "urn:ietf:params:xml:ns:caldav";
+
+/// REPORT body asking for the ETag and data of every to-do of a collection
+const TODOS_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:getetag/>
+    <c:calendar-data/>
+  </d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VTODO"/>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>
+"#;
+
+/// A calendar object of a collection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resource {
+    /// Path of the resource on the server
+    pub href: String,
+    pub etag: Option<String>,
+    /// The iCalendar object
+    pub data: String,
+}
+
+/// Outcome of a conditional write or delete
+#[derive(Debug, Clone, PartialEq)]
+pub enum Written {
+    /// Done, with the new ETag of the resource if the server told it
+    Done(Option<String>),
+    /// Left alone, since the resource changed on the server after it was read
+    Changed,
+}
+
+/// Client of a CalDAV calendar collection
+#[derive(Debug, Clone)]
+pub struct CalDavClient {
+    http: Client,
+    url: Url,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl CalDavClient {
+    pub fn new(url: &str, username: Option<String>, password: Option<String>) -> Result<Self> {
+        let mut url = Url::parse(url).with_context(|| format!("Invalid CalDAV URL '{}'", url))?;
+        // Resources are resolved against the collection, which needs a trailing slash
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+
+        let http = Client::builder()
+            .user_agent(concat!("judo/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .with_context(|| "Failed to create HTTP client")?;
+
+        Ok(Self {
+            http,
+            url,
+            username,
+            password,
+        })
+    }
+
+    /// URL of the collection
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Path for a new resource holding the to-do with this UID
+    pub fn new_href(&self, uid: &str) -> String {
+        // Only characters that need no percent-encoding, so that the server
+        // lists the resource with the very same path
+        let name: String = uid
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+                _ => '-',
+            })
+            .collect();
+        format!("{}{}.ics", self.url.path(), name)
+    }
+
+    /// All to-dos of the collection
+    pub async fn todos(&self) -> Result<Vec<Resource>> {
+        let method = Method::from_bytes(b"REPORT").with_context(|| "Invalid method")?;
+        let response = self
+            .request(method, self.url.clone())
+            .header("Depth", "1")
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(TODOS_QUERY)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach CalDAV server at {}", self.url))?;
+
+        let status = response.status();
+        if status != StatusCode::MULTI_STATUS {
+            bail!(
+                "CalDAV server answered {} to the query of the to-dos of {}",
+                status,
+                self.url
+            );
+        }
+        let body = response
+            .text()
+            .await
+            .with_context(|| "Failed to read the to-dos sent by the CalDAV server")?;
+
+        let mut resources = parse_multistatus(&body)?;
+        for resource in &mut resources {
+            // Servers may answer with full URLs, paths are what gets stored
+            resource.href = self.resolve(&resource.href)?.path().to_string();
+        }
+        Ok(resources)
+    }
+
+    /// Write a resource if it still has this ETag, or create it if `etag` is None
+    /// and there is no resource at `href`
+    pub async fn put(&self, href: &str, data: String, etag: Option<&str>) -> Result<Written> {
+        let request = self
+            .request(Method::PUT, self.resolve(href)?)
+            .header(CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .body(data);
+        let request = match etag {
+            Some(etag) => request.header(IF_MATCH, etag),
+            None => request.header(IF_NONE_MATCH, "*"),
+        };
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach CalDAV server at {}", self.url))?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(Written::Changed),
+            status if status.is_success() => Ok(Written::Done(etag_of(&response))),
+            status => bail!("CalDAV server answered {} to writing {}", status, href),
+        }
+    }
+
+    /// Delete a resource if it still has this ETag
+    pub async fn delete(&self, href: &str, etag: Option<&str>) -> Result<Written> {
+        let mut request = self.request(Method::DELETE, self.resolve(href)?);
+        if let Some(etag) = etag {
+            request = request.header(IF_MATCH, etag);
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach CalDAV server at {}", self.url))?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(Written::Changed),
+            // Already gone is as good as deleted
+            status if status.is_success() || status == StatusCode::NOT_FOUND => {
+                Ok(Written::Done(None))
+            }
+            status => bail!("CalDAV server answered {} to deleting {}", status, href),
+        }
+    }
+
+    fn resolve(&self, href: &str) -> Result<Url> {
+        self.url
+            .join(href)
+            .with_context(|| format!("Invalid path '{}' of a CalDAV resource", href))
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.http.request(method, url);
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_ref()),
+            None => request,
+        }
+    }
+}
+
+fn etag_of(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(String::from)
+}
+
+/// Resources of a multistatus answer to a calendar query, skipping those
+/// without calendar data (such as the collection itself)
+fn parse_multistatus(xml: &str) -> Result<Vec<Resource>> {
+    let doc =
+        Document::parse(xml).with_context(|| "Invalid XML in the answer of the CalDAV server")?;
+
+    let mut resources = Vec::new();
+    for response in doc
+        .descendants()
+        .filter(|node| node.has_tag_name((DAV_NS, "response")))
+    {
+        let Some(href) = child(response, DAV_NS, "href").and_then(|node| node.text()) else {
+            continue;
+        };
+
+        // Properties found, i.e. those in a propstat with a 200 status
+        let found = response
+            .children()
+            .filter(|node| node.has_tag_name((DAV_NS, "propstat")))
+            .filter(|propstat| {
+                child(*propstat, DAV_NS, "status")
+                    .and_then(|status| status.text())
+                    .is_none_or(|status| status.contains(" 200 "))
+            })
+            .filter_map(|propstat| child(propstat, DAV_NS, "prop"))
+            .collect::<Vec<_>>();
+        let property = |namespace: &str, name: &str| {
+            found
+                .iter()
+                .find_map(|prop| child(*prop, namespace, name))
+                .and_then(|node| node.text())
+        };
+
+        if let Some(data) = property(CALDAV_NS, "calendar-data") {
+            resources.push(Resource {
+                href: href.trim().to_string(),
+                etag: property(DAV_NS, "getetag").map(|etag| etag.trim().to_string()),
+                data: data.to_string(),
+            });
+        }
+    }
+    Ok(resources)
+}
+
+fn child<'a, 'input>(
+    node: Node<'a, 'input>,
+    namespace: &str,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.has_tag_name((namespace, name)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_multistatus() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+            <d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+              <d:response>
+                <d:href>/calendars/bob/tasks/</d:href>
+                <d:propstat>
+                  <d:prop><d:getetag>"c0"</d:getetag></d:prop>
+                  <d:status>HTTP/1.1 200 OK</d:status>
+                </d:propstat>
+              </d:response>
+              <d:response>
+                <d:href>/calendars/bob/tasks/milk.ics</d:href>
+                <d:propstat>
+                  <d:prop>
+                    <d:getetag>"e1"</d:getetag>
+                    <cal:calendar-data>BEGIN:VCALENDAR&#13;
+SUMMARY:Milk &amp; eggs&#13;
+END:VCALENDAR&#13;
+</cal:calendar-data>
+                  </d:prop>
+                  <d:status>HTTP/1.1 200 OK</d:status>
+                </d:propstat>
+              </d:response>
+              <d:response>
+                <d:href>https://dav.example.com/calendars/bob/tasks/cheese.ics</d:href>
+                <d:propstat>
+                  <d:prop><cal:calendar-data><![CDATA[BEGIN:VCALENDAR]]></cal:calendar-data></d:prop>
+                  <d:status>HTTP/1.1 200 OK</d:status>
+                </d:propstat>
+                <d:propstat>
+                  <d:prop><d:getetag/></d:prop>
+                  <d:status>HTTP/1.1 404 Not Found</d:status>
+                </d:propstat>
+              </d:response>
+            </d:multistatus>"#;
+
+        let resources = parse_multistatus(xml).unwrap();
+        assert_eq!(
+            resources,
+            vec![
+                Resource {
+                    href: "/calendars/bob/tasks/milk.ics".to_string(),
+                    etag: Some("\"e1\"".to_string()),
+                    data: "BEGIN:VCALENDAR\r\nSUMMARY:Milk & eggs\r\nEND:VCALENDAR\r\n".to_string(),
+                },
+                Resource {
+                    href: "https://dav.example.com/calendars/bob/tasks/cheese.ics".to_string(),
+                    etag: None,
+                    data: "BEGIN:VCALENDAR".to_string(),
+                },
+            ]
+        );
+
+        assert!(parse_multistatus("<d:multistatus").is_err());
+    }
+
+    #[test]
+    fn test_new_href() {
+        let client =
+            CalDavClient::new("https://dav.example.com/calendars/bob/tasks", None, None).unwrap();
+        assert_eq!(client.url().path(), "/calendars/bob/tasks/");
+        assert_eq!(
+            client.new_href("1760779800000000-4@judo"),
+            "/calendars/bob/tasks/1760779800000000-4-judo.ics"
+        );
+    }
+}
@@ -1,25 +1,29 @@
 pub struct DBSelector;
+use crate::keymap::{Action, KeyMap};
 use crate::db::config::Config;
+use crate::ui::hints::command_hints;
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Alignment, Rect};
-use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph, Widget};
-use std::str::FromStr;
 
 impl DBSelector {
-    pub fn render(area: Rect, buf: &mut Buffer, current_db_name: &str, config: Config) {
-        let fg = config.foreground();
-        let hl = config.highlight();
-
+    pub fn render(
+        area: Rect,
+        buf: &mut Buffer,
+        current_db_name: &str,
+        config: Config,
+        key_map: &KeyMap,
+    ) {
         // Command hints for db
-        let list_command_hints = Line::from(vec![
-            Span::raw(" "),
-            Span::styled("[C]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::styled("hange", Style::default().fg(Color::from_str(fg).unwrap())),
-            Span::raw(" "),
-        ])
-        .left_aligned();
+        let mut hint_spans = vec![Span::raw(" ")];
+        hint_spans.extend(command_hints(
+            key_map,
+            &[(Action::ChangeDb, "Change")],
+            &config,
+        ));
+        hint_spans.push(Span::raw(" "));
+        let list_command_hints = Line::from(hint_spans).left_aligned();
 
         let block = Block::default()
             .padding(Padding::new(2, 2, 0, 0))
@@ -0,0 +1,195 @@
+use crate::keymap::{Action, KeyContext, KeyMap};
+use crate::db::config::Config;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, Widget};
+use std::str::FromStr;
+
+pub struct HelpPopUp;
+
+impl HelpPopUp {
+    /// Bound actions of a context, grouped under their headings in the order
+    /// the groups first appear in `Action::ALL`
+    pub fn groups(key_map: &KeyMap, context: KeyContext) -> Vec<(&'static str, Vec<Action>)> {
+        let mut groups: Vec<(&'static str, Vec<Action>)> = Vec::new();
+
+        for action in Action::ALL {
+            if !action.contexts().contains(&context) || key_map.keys(action).is_empty() {
+                continue;
+            }
+
+            match groups
+                .iter_mut()
+                .find(|(group, _)| *group == action.group())
+            {
+                Some((_, actions)) => actions.push(action),
+                None => groups.push((action.group(), vec![action])),
+            }
+        }
+        groups
+    }
+
+    /// Lines of the help text: a heading per group followed by its bindings
+    fn lines(config: &Config, key_map: &KeyMap, context: KeyContext) -> Vec<Line<'static>> {
+        let hl = Color::from_str(config.highlight()).unwrap();
+        let groups = Self::groups(key_map, context);
+
+        // Every binding of an action, e.g. "↓ / j"
+        let label = |action: Action| {
+            key_map
+                .keys(action)
+                .iter()
+                .map(|sequence| sequence.to_string())
+                .collect::<Vec<_>>()
+                .join(" / ")
+        };
+
+        // Align descriptions in a single column
+        let width = groups
+            .iter()
+            .flat_map(|(_, actions)| actions.iter())
+            .map(|action| label(*action).chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = Vec::new();
+        for (i, (group, actions)) in groups.iter().enumerate() {
+            if i > 0 {
+                lines.push(Line::raw(""));
+            }
+            lines.push(Line::styled(
+                group.to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+            for action in actions {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {:<width$}", label(*action)),
+                        Style::default().fg(hl),
+                    ),
+                    Span::raw(format!("   {}", action.description())),
+                ]));
+            }
+        }
+        lines
+    }
+
+    /// Render the key bindings of a context centered in the area, scrolled down
+    /// by `scroll` lines. Returns the scroll clamped to the length of the help
+    pub fn render(
+        config: &Config,
+        key_map: &KeyMap,
+        context: KeyContext,
+        scroll: usize,
+        area: Rect,
+        buf: &mut Buffer,
+    ) -> usize {
+        let fg = config.foreground();
+        let bg = config.background();
+        let hl = config.highlight();
+
+        // Calculate popup dimensions
+        let popup_width = (area.width * 3) / 4; // 75% of the area width
+        let popup_height = (area.height * 4) / 5; // 80% of the area height
+
+        // Center the popup within the area
+        let popup_area = Rect {
+            x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+            y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+            width: popup_width,
+            height: popup_height,
+        };
+
+        // Command hints for the help popup
+        let mut hint_spans = vec![Span::raw(" ")];
+        hint_spans.push(Span::styled(
+            format!(
+                " {}{} ",
+                key_map.key_label(Action::ScrollHelpUp),
+                key_map.key_label(Action::ScrollHelpDown)
+            ),
+            Style::default(),
+        ));
+        let close = key_map.key_label(Action::CloseHelp);
+        if !close.is_empty() {
+            hint_spans.push(Span::styled(
+                format!("[{}]", close),
+                Style::default().fg(Color::from_str(hl).unwrap()),
+            ));
+        }
+        hint_spans.push(Span::raw(" "));
+
+        let block = Block::new()
+            .title(" Key Bindings ")
+            .title_style(Style::new().fg(Color::from_str(fg).unwrap()))
+            .title_bottom(Line::from(hint_spans))
+            .borders(Borders::ALL)
+            .border_style(Style::new().fg(Color::from_str(fg).unwrap()))
+            .border_type(BorderType::Rounded)
+            .padding(Padding::new(2, 2, 1, 1));
+
+        // Don't scroll past the last line
+        let lines = Self::lines(config, key_map, context);
+        let visible = block.inner(popup_area).height as usize;
+        let scroll = scroll.min(lines.len().saturating_sub(visible));
+
+        // Clear the background of the popup area first
+        Clear.render(popup_area, buf);
+        Paragraph::new(lines)
+            .style(
+                Style::default()
+                    .bg(Color::from_str(bg).unwrap())
+                    .fg(Color::from_str(fg).unwrap()),
+            )
+            .block(block)
+            .scroll((scroll as u16, 0))
+            .render(popup_area, buf);
+
+        scroll
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keymap::KeymapPreset;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_groups_cover_every_bound_action() {
+        let key_map = KeyMap::default();
+
+        for context in [KeyContext::Main, KeyContext::Database] {
+            let listed: Vec<Action> = HelpPopUp::groups(&key_map, context)
+                .into_iter()
+                .flat_map(|(_, actions)| actions)
+                .collect();
+
+            for action in Action::ALL {
+                let bound = !key_map.keys(action).is_empty();
+                let in_context = action.contexts().contains(&context);
+                assert_eq!(listed.contains(&action), bound && in_context, "{action}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_groups_follow_preset() {
+        let key_map = KeyMap::new(KeymapPreset::Vim, &BTreeMap::new()).unwrap();
+        let groups = HelpPopUp::groups(&key_map, KeyContext::Main);
+
+        assert!(groups.iter().any(|(group, _)| *group == "Focused pane"));
+        assert!(
+            !groups
+                .iter()
+                .any(|(_, actions)| actions.contains(&Action::DeleteItem))
+        );
+        assert!(
+            HelpPopUp::groups(&KeyMap::default(), KeyContext::Main)
+                .iter()
+                .all(|(group, _)| *group != "Focused pane")
+        );
+    }
+}
@@ -1,8 +1,10 @@
+use crate::keymap::{Action, KeyMap};
 use crate::db::config::Config;
 use crate::db::models::{NewTodoItem, TodoItem, UIItem, UIList};
-use anyhow::Result;
+use crate::ui::hints::command_hints;
+use anyhow::{Result, bail};
 use ratatui::buffer::Buffer;
-use ratatui::layout::{Alignment, Rect};
+use ratatui::layout::{Alignment, Position, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{
@@ -14,14 +16,26 @@ pub struct ItemsComponent;
 
 impl ItemsComponent {
     /// Apply styling to a todo item based on its completion status
-    fn style_item(ui_item: &UIItem) -> Span<'_> {
+    fn style_item(ui_item: &UIItem) -> Line<'_> {
         let name = ui_item.item.name.clone();
 
-        if ui_item.item.is_done {
+        let name = if ui_item.item.is_done {
             // Strike through completed items
             Span::styled(name, Style::default().add_modifier(Modifier::CROSSED_OUT))
         } else {
             Span::from(name)
+        };
+
+        // Items of a view show where they are stored
+        match &ui_item.origin {
+            Some(origin) => Line::from(vec![
+                name,
+                Span::styled(
+                    format!("  {} · {}", origin.list_name, origin.source.db_name),
+                    Style::default().add_modifier(Modifier::DIM),
+                ),
+            ]),
+            None => Line::from(name),
         }
     }
 
@@ -50,13 +64,16 @@ impl ItemsComponent {
     /// Toggle the "is done" status of the currently selected item
     pub async fn toggle_item_done(ui_list: &mut UIList, pool: &SqlitePool) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
-            ui_list.items[j].item.toggle_done(pool).await?;
+            // Items of a view are toggled in their own database
+            let pool = ui_list.items[j].pool(pool).clone();
+            ui_list.items[j].item.toggle_done(&pool).await?;
         }
         Ok(())
     }
 
     /// Create a new item in the given list
     pub async fn create_item(ui_list: &mut UIList, name: String, pool: &SqlitePool) -> Result<()> {
+        Self::ensure_real_list(ui_list)?;
         let new_item = NewTodoItem {
             name,
             list_id: ui_list.list.id,
@@ -69,11 +86,52 @@ impl ItemsComponent {
         Ok(())
     }
 
+    /// Create a new item at the given position of the list and select it
+    pub async fn create_item_at(
+        ui_list: &mut UIList,
+        name: String,
+        position: usize,
+        pool: &SqlitePool,
+    ) -> Result<()> {
+        Self::ensure_real_list(ui_list)?;
+        let new_item = NewTodoItem {
+            name,
+            list_id: ui_list.list.id,
+            priority: None,
+            due_date: None,
+        };
+
+        let mut item = TodoItem::create(pool, new_item).await?;
+        item.move_to_position(pool, position).await?;
+        ui_list.update_items(pool).await?;
+
+        // Follow the new item
+        ui_list
+            .item_state
+            .select(Some(position.min(ui_list.items.len().saturating_sub(1))));
+        Ok(())
+    }
+
+    /// Select the first item in the list, even if another one is selected
+    pub fn select_top_item(ui_list: &mut UIList) {
+        if !ui_list.items.is_empty() {
+            ui_list.item_state.select(Some(0));
+        }
+    }
+
+    /// Select the last item in the list
+    pub fn select_bottom_item(ui_list: &mut UIList) {
+        if !ui_list.items.is_empty() {
+            ui_list.item_state.select(Some(ui_list.items.len() - 1));
+        }
+    }
+
     /// Update an existing item
     pub async fn update_item(ui_list: &mut UIList, name: String, pool: &SqlitePool) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
-            let mut item = ui_list.items[j].item.clone();
-            item.update_name(pool, name).await?;
+            let ui_item = &ui_list.items[j];
+            let mut item = ui_item.item.clone();
+            item.update_name(ui_item.pool(pool), name).await?;
 
             // Update list elements
             ui_list.update_items(pool).await?;
@@ -84,8 +142,8 @@ impl ItemsComponent {
     /// Delete the currently selected item
     pub async fn delete_selected_item(ui_list: &mut UIList, pool: &SqlitePool) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
-            let item = ui_list.items[j].item.clone();
-            item.delete(pool).await?;
+            let ui_item = &ui_list.items[j];
+            ui_item.item.clone().delete(ui_item.pool(pool)).await?;
 
             // Update list elements
             ui_list.update_items(pool).await?;
@@ -103,6 +161,7 @@ impl ItemsComponent {
     /// Move the currently selected item up
     pub async fn move_selected_item_up(ui_list: &mut UIList, pool: &SqlitePool) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
+            Self::ensure_real_list(ui_list)?;
             let mut item = ui_list.items[j].item.clone();
             item.move_up(pool).await?;
 
@@ -120,6 +179,7 @@ impl ItemsComponent {
     /// Move the currently selected item down
     pub async fn move_selected_item_down(ui_list: &mut UIList, pool: &SqlitePool) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
+            Self::ensure_real_list(ui_list)?;
             let mut item = ui_list.items[j].item.clone();
             item.move_down(pool).await?;
 
@@ -134,47 +194,122 @@ impl ItemsComponent {
         Ok(())
     }
 
+    /// Move the currently selected item to the top of its list
+    pub async fn move_selected_item_to_top(ui_list: &mut UIList, pool: &SqlitePool) -> Result<()> {
+        if let Some(j) = ui_list.item_state.selected() {
+            Self::ensure_real_list(ui_list)?;
+            let mut item = ui_list.items[j].item.clone();
+            item.move_to_top(pool).await?;
+
+            // Update list elements to reflect the new order
+            ui_list.update_items(pool).await?;
+
+            // Follow the moved item
+            ui_list.item_state.select(Some(0));
+        }
+        Ok(())
+    }
+
+    /// Move the currently selected item to the bottom of its list
+    pub async fn move_selected_item_to_bottom(
+        ui_list: &mut UIList,
+        pool: &SqlitePool,
+    ) -> Result<()> {
+        if let Some(j) = ui_list.item_state.selected() {
+            Self::ensure_real_list(ui_list)?;
+            let mut item = ui_list.items[j].item.clone();
+            item.move_to_bottom(pool).await?;
+
+            // Update list elements to reflect the new order
+            ui_list.update_items(pool).await?;
+
+            // Follow the moved item
+            ui_list
+                .item_state
+                .select(Some(ui_list.items.len().saturating_sub(1)));
+        }
+        Ok(())
+    }
+
+    /// Items of a view come from many lists, so they can only be added to and
+    /// reordered in their own list
+    fn ensure_real_list(ui_list: &UIList) -> Result<()> {
+        if ui_list.view.is_some() {
+            bail!("Items can only be added or moved in their own list, not in a view");
+        }
+        Ok(())
+    }
+
+    /// Bordered and padded block framing the items (titles are added when rendering)
+    fn frame() -> Block<'static> {
+        Block::default()
+            .padding(Padding::new(2, 2, 1, 1))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+    }
+
+    /// Index of the item of the given list drawn at the given position, if any
+    pub fn item_at(ui_list: &UIList, area: Rect, position: Position) -> Option<usize> {
+        let inner = Self::frame().inner(area);
+        if !inner.contains(position) {
+            return None;
+        }
+
+        let index = (position.y - inner.y) as usize + ui_list.item_state.offset();
+        (index < ui_list.items.len()).then_some(index)
+    }
+
     /// Render the list of todo items for the selected list
     pub fn render(
         selected_list: Option<&mut UIList>,
         area: Rect,
         buf: &mut Buffer,
         config: Config,
+        key_map: &KeyMap,
+        focused: bool,
     ) {
         let fg = config.foreground();
-        let hl = config.highlight();
         let bg = config.background();
-        // Command hints for items
-        let list_command_hints = Line::from(vec![
-            Span::raw(" "),
-            Span::styled(" ↓↑ ", Style::default()),
-            Span::styled("[a]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::styled("dd", Style::default().fg(Color::from_str(fg).unwrap())),
-            Span::styled(" [d]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::styled("el", Style::default().fg(Color::from_str(fg).unwrap())),
-            Span::styled(" [m]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::styled("odify ", Style::default().fg(Color::from_str(fg).unwrap())),
+        let hl = config.highlight();
+        // Command hints for items, generated from the active key bindings
+        let mut hint_spans = vec![
             Span::raw(" "),
-        ])
-        .left_aligned();
+            Span::styled(
+                format!(
+                    " {}{} ",
+                    key_map.key_label(Action::NextItem),
+                    key_map.key_label(Action::PreviousItem)
+                ),
+                Style::default(),
+            ),
+        ];
+        hint_spans.extend(command_hints(
+            key_map,
+            &[
+                (Action::AddItem, "Add"),
+                (Action::DeleteItem, "Del"),
+                (Action::ModifyItem, "Modify"),
+            ],
+            &config,
+        ));
+        hint_spans.push(Span::raw("  "));
+        let list_command_hints = Line::from(hint_spans).left_aligned();
 
         // Add "quit" hint, in the bottom right corner
-        let quit_hint = Line::from(vec![
-            Span::raw(" "),
-            Span::styled("[q]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::styled("uit ", Style::default().fg(Color::from_str(fg).unwrap())),
-            Span::raw(" "),
-        ])
-        .right_aligned();
+        let mut quit_spans = vec![Span::raw(" ")];
+        quit_spans.extend(command_hints(key_map, &[(Action::Quit, "Quit")], &config));
+        quit_spans.push(Span::raw("  "));
+        let quit_hint = Line::from(quit_spans).right_aligned();
 
-        let block = Block::default()
-            .padding(Padding::new(2, 2, 1, 1))
+        // Highlight the border of the focused pane
+        let border_color = if focused { hl } else { fg };
+
+        let block = Self::frame()
+            .border_style(Style::default().fg(Color::from_str(border_color).unwrap()))
             .title_top(Line::raw("  I T E M S  ").left_aligned())
             .title_bottom(list_command_hints)
             .title_bottom(quit_hint)
-            .title_alignment(Alignment::Center)
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded);
+            .title_alignment(Alignment::Center);
 
         if let Some(ui_list) = selected_list {
             // Extract the corresponding items with styling
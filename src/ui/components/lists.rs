@@ -1,8 +1,11 @@
+use crate::keymap::{Action, KeyMap};
 use crate::db::config::Config;
 use crate::db::models::{NewTodoList, TodoList, UIList};
+use crate::db::query::Query;
+use crate::ui::hints::command_hints;
 use anyhow::Result;
 use ratatui::buffer::Buffer;
-use ratatui::layout::{Alignment, Rect};
+use ratatui::layout::{Alignment, Position, Rect};
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{
@@ -15,6 +18,10 @@ use std::str::FromStr;
 pub struct ListsComponent {
     pub lists: Vec<UIList>,
     pub list_state: ListState,
+    /// Saved views, shown below the lists
+    pub views: Vec<UIList>,
+    /// Query the items of every list are filtered with
+    pub filter: Option<Query>,
 }
 
 impl Default for ListsComponent {
@@ -28,15 +35,49 @@ impl ListsComponent {
         Self {
             lists: Vec::new(),
             list_state: ListState::default(),
+            views: Vec::new(),
+            filter: None,
         }
     }
 
-    /// Initialize lists from database
+    /// Initialize lists from database, and refresh the views that may show their items
     pub async fn load_lists(&mut self, pool: &SqlitePool) -> Result<()> {
-        self.lists = UIList::get_all(pool).await?;
+        self.lists = UIList::get_all_filtered(pool, self.filter.as_ref()).await?;
+        for view in &mut self.views {
+            view.update_items(pool).await?;
+        }
+        Ok(())
+    }
+
+    /// Reload the items of the selected view, if a view is selected
+    pub async fn refresh_selected_view(&mut self, pool: &SqlitePool) -> Result<()> {
+        if let Some(ui_list) = self.get_selected_list_mut()
+            && ui_list.view.is_some()
+        {
+            ui_list.update_items(pool).await?;
+
+            // Keep the selection on an item if items dropped out of the view
+            if let Some(j) = ui_list.item_state.selected()
+                && j >= ui_list.items.len()
+            {
+                ui_list
+                    .item_state
+                    .select(ui_list.items.len().checked_sub(1));
+            }
+        }
         Ok(())
     }
 
+    /// Number of lists and views shown
+    pub fn len(&self) -> usize {
+        self.lists.len() + self.views.len()
+    }
+
+    /// Whether there are neither lists nor views
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Select next element in the list of to-do lists
     pub fn select_next(&mut self) {
         self.list_state.select_next();
@@ -47,26 +88,53 @@ impl ListsComponent {
         self.list_state.select_previous();
     }
 
+    /// Select the first list
+    pub fn select_top(&mut self) {
+        if !self.is_empty() {
+            self.list_state.select(Some(0));
+        }
+    }
+
+    /// Select the last list
+    pub fn select_bottom(&mut self) {
+        if !self.is_empty() {
+            self.list_state.select(Some(self.len() - 1));
+        }
+    }
+
     /// Get currently selected list index
     pub fn selected(&self) -> Option<usize> {
         self.list_state.selected()
     }
 
-    /// Get the currently selected list (mutable)
+    /// Index in `lists` of the selected list, None if a view is selected
+    pub fn selected_list_index(&self) -> Option<usize> {
+        self.list_state.selected().filter(|&i| i < self.lists.len())
+    }
+
+    /// Whether the selection is a view rather than a list
+    pub fn is_view_selected(&self) -> bool {
+        self.get_selected_list()
+            .is_some_and(|ui_list| ui_list.view.is_some())
+    }
+
+    /// Get the currently selected list or view (mutable)
     pub fn get_selected_list_mut(&mut self) -> Option<&mut UIList> {
-        if let Some(i) = self.list_state.selected() {
+        let i = self.list_state.selected()?;
+        if i < self.lists.len() {
             self.lists.get_mut(i)
         } else {
-            None
+            self.views.get_mut(i - self.lists.len())
         }
     }
 
-    /// Get the currently selected list (immutable)
+    /// Get the currently selected list or view (immutable)
     pub fn get_selected_list(&self) -> Option<&UIList> {
-        if let Some(i) = self.list_state.selected() {
+        let i = self.list_state.selected()?;
+        if i < self.lists.len() {
             self.lists.get(i)
         } else {
-            None
+            self.views.get(i - self.lists.len())
         }
     }
 
@@ -77,10 +145,10 @@ impl ListsComponent {
 
         // Restore selection if it was set and still valid
         if let Some(index) = selected_index {
-            if index < self.lists.len() {
+            if index < self.len() {
                 self.list_state.select(Some(index));
-            } else if !self.lists.is_empty() {
-                self.list_state.select(Some(self.lists.len() - 1));
+            } else if !self.is_empty() {
+                self.list_state.select(Some(self.len() - 1));
             }
         }
 
@@ -92,7 +160,7 @@ impl ListsComponent {
         lists_component: &mut ListsComponent,
         pool: &SqlitePool,
     ) -> Result<()> {
-        if let Some(i) = lists_component.list_state.selected() {
+        if let Some(i) = lists_component.selected_list_index() {
             let mut list = lists_component.lists[i].list.clone();
             list.move_up(pool).await?;
 
@@ -112,7 +180,7 @@ impl ListsComponent {
         lists_component: &mut ListsComponent,
         pool: &SqlitePool,
     ) -> Result<()> {
-        if let Some(i) = lists_component.list_state.selected() {
+        if let Some(i) = lists_component.selected_list_index() {
             let mut list = lists_component.lists[i].list.clone();
             list.move_down(pool).await?;
 
@@ -127,12 +195,50 @@ impl ListsComponent {
         Ok(())
     }
 
+    /// Move the currently selected list to the top (static method like ItemsComponent)
+    pub async fn move_selected_list_to_top(
+        lists_component: &mut ListsComponent,
+        pool: &SqlitePool,
+    ) -> Result<()> {
+        if let Some(i) = lists_component.selected_list_index() {
+            let mut list = lists_component.lists[i].list.clone();
+            list.move_to_top(pool).await?;
+
+            // Refresh lists to reflect the new order
+            lists_component.refresh_lists(pool).await?;
+
+            // Follow the moved list
+            lists_component.list_state.select(Some(0));
+        }
+        Ok(())
+    }
+
+    /// Move the currently selected list to the bottom (static method like ItemsComponent)
+    pub async fn move_selected_list_to_bottom(
+        lists_component: &mut ListsComponent,
+        pool: &SqlitePool,
+    ) -> Result<()> {
+        if let Some(i) = lists_component.selected_list_index() {
+            let mut list = lists_component.lists[i].list.clone();
+            list.move_to_bottom(pool).await?;
+
+            // Refresh lists to reflect the new order
+            lists_component.refresh_lists(pool).await?;
+
+            // Follow the moved list
+            lists_component
+                .list_state
+                .select(Some(lists_component.lists.len().saturating_sub(1)));
+        }
+        Ok(())
+    }
+
     /// Delete the currently selected list (static method like ItemsComponent)
     pub async fn delete_selected_list_static(
         lists_component: &mut ListsComponent,
         pool: &SqlitePool,
     ) -> Result<()> {
-        if let Some(i) = lists_component.list_state.selected() {
+        if let Some(i) = lists_component.selected_list_index() {
             let list = lists_component.lists[i].list.clone();
             list.delete(pool).await?;
 
@@ -140,9 +246,10 @@ impl ListsComponent {
             lists_component.load_lists(pool).await?;
 
             // Adjust selection after deletion
-            if lists_component.lists.is_empty() {
+            if lists_component.is_empty() {
                 lists_component.list_state.select(None);
-            } else if i >= lists_component.lists.len() {
+            } else if i >= lists_component.lists.len() && !lists_component.lists.is_empty() {
+                // Stay on the lists rather than moving on to the views
                 lists_component
                     .list_state
                     .select(Some(lists_component.lists.len() - 1));
@@ -169,7 +276,7 @@ impl ListsComponent {
         name: String,
         pool: &SqlitePool,
     ) -> Result<()> {
-        if let Some(i) = lists_component.list_state.selected() {
+        if let Some(i) = lists_component.selected_list_index() {
             let mut list = lists_component.lists[i].list.clone();
             list.update_name(pool, name).await?;
             lists_component.load_lists(pool).await?;
@@ -177,38 +284,81 @@ impl ListsComponent {
         Ok(())
     }
 
+    /// Bordered and padded block framing the lists (titles are added when rendering)
+    fn frame() -> Block<'static> {
+        Block::default()
+            .padding(Padding::new(2, 2, 1, 1))
+            .borders(Borders::TOP | Borders::LEFT | Borders::BOTTOM)
+            .border_type(BorderType::Rounded)
+    }
+
+    /// Index of the list drawn at the given position, if any
+    pub fn list_at(&self, area: Rect, position: Position) -> Option<usize> {
+        let inner = Self::frame().inner(area);
+        if !inner.contains(position) {
+            return None;
+        }
+
+        let index = (position.y - inner.y) as usize + self.list_state.offset();
+        (index < self.len()).then_some(index)
+    }
+
     /// Render the list of todo lists
-    pub fn render(&mut self, area: Rect, buf: &mut Buffer, config: Config) {
+    pub fn render(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        config: Config,
+        key_map: &KeyMap,
+        focused: bool,
+    ) {
         let fg = config.foreground();
-        let hl = config.highlight();
         let bg = config.background();
-        // Command hints for lists
-        let list_command_hints = Line::from(vec![
-            Span::raw(" "),
-            Span::styled(" w,s ", Style::default()),
-            Span::styled("[A]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::styled("dd", Style::default().fg(Color::from_str(fg).unwrap())),
-            Span::styled(" [D]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::styled("el", Style::default().fg(Color::from_str(fg).unwrap())),
-            Span::styled(" [M]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::styled("odify ", Style::default().fg(Color::from_str(fg).unwrap())),
+        let hl = config.highlight();
+        // Command hints for lists, generated from the active key bindings
+        let mut hint_spans = vec![
             Span::raw(" "),
-        ])
-        .left_aligned();
+            Span::styled(
+                format!(
+                    " {},{} ",
+                    key_map.key_label(Action::PreviousList),
+                    key_map.key_label(Action::NextList)
+                ),
+                Style::default(),
+            ),
+        ];
+        hint_spans.extend(command_hints(
+            key_map,
+            &[
+                (Action::AddList, "Add"),
+                (Action::DeleteList, "Del"),
+                (Action::ModifyList, "Modify"),
+            ],
+            &config,
+        ));
+        hint_spans.push(Span::raw("  "));
+        let list_command_hints = Line::from(hint_spans).left_aligned();
 
-        let block = Block::default()
-            .padding(Padding::new(2, 2, 1, 1))
+        // Highlight the border of the focused pane
+        let border_color = if focused { hl } else { fg };
+
+        let block = Self::frame()
+            .border_style(Style::default().fg(Color::from_str(border_color).unwrap()))
             .title_top(Line::raw("  L I S T S  ").left_aligned())
             .title_bottom(list_command_hints)
-            .title_alignment(Alignment::Center)
-            .borders(Borders::TOP | Borders::LEFT | Borders::BOTTOM)
-            .border_type(BorderType::Rounded);
+            .title_alignment(Alignment::Center);
 
-        // Convert lists to display items
+        // Convert lists to display items, followed by the views with a marker
         let items: Vec<ListItem> = self
             .lists
             .iter()
             .map(|ui_list| ListItem::from(ui_list.list.name.clone()))
+            .chain(self.views.iter().map(|ui_list| {
+                ListItem::from(Line::from(vec![
+                    Span::styled("◆ ", Style::default().fg(Color::from_str(hl).unwrap())),
+                    Span::raw(ui_list.list.name.clone()),
+                ]))
+            }))
             .collect();
 
         let list: List = List::new(items)
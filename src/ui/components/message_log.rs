@@ -0,0 +1,108 @@
+use crate::keymap::{Action, KeyMap};
+use crate::app::messages::MessageLog;
+use crate::db::config::Config;
+use crate::ui::components::StatusBar;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, Widget, Wrap};
+use std::str::FromStr;
+
+pub struct MessageLogPopUp;
+
+impl MessageLogPopUp {
+    /// Render the messages reported so far, newest first, scrolled down by
+    /// `scroll` lines. Returns the scroll clamped to the number of messages
+    pub fn render(
+        config: &Config,
+        key_map: &KeyMap,
+        log: &MessageLog,
+        scroll: usize,
+        area: Rect,
+        buf: &mut Buffer,
+    ) -> usize {
+        let fg = config.foreground();
+        let bg = config.background();
+        let hl = config.highlight();
+
+        // Calculate popup dimensions
+        let popup_width = (area.width * 3) / 4; // 75% of the area width
+        let popup_height = (area.height * 4) / 5; // 80% of the area height
+
+        // Center the popup within the area
+        let popup_area = Rect {
+            x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+            y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+            width: popup_width,
+            height: popup_height,
+        };
+
+        // Command hints for the message log
+        let mut hint_spans = vec![Span::raw(" ")];
+        hint_spans.push(Span::styled(
+            format!(
+                " {}{} ",
+                key_map.key_label(Action::ScrollMessagesUp),
+                key_map.key_label(Action::ScrollMessagesDown)
+            ),
+            Style::default(),
+        ));
+        let close = key_map.key_label(Action::CloseMessages);
+        if !close.is_empty() {
+            hint_spans.push(Span::styled(
+                format!("[{}]", close),
+                Style::default().fg(Color::from_str(hl).unwrap()),
+            ));
+        }
+        hint_spans.push(Span::raw(" "));
+
+        let block = Block::new()
+            .title(" Messages ")
+            .title_style(Style::new().fg(Color::from_str(fg).unwrap()))
+            .title_bottom(Line::from(hint_spans))
+            .borders(Borders::ALL)
+            .border_style(Style::new().fg(Color::from_str(fg).unwrap()))
+            .border_type(BorderType::Rounded)
+            .padding(Padding::new(2, 2, 1, 1));
+
+        let lines: Vec<Line> = if log.is_empty() {
+            vec![Line::raw("No messages yet")]
+        } else {
+            log.messages()
+                .rev()
+                .map(|message| {
+                    Line::from(vec![
+                        Span::styled(
+                            message.time.format("%H:%M:%S ").to_string(),
+                            Style::default().add_modifier(Modifier::DIM),
+                        ),
+                        Span::styled(
+                            format!("{:<6}", message.level.label()),
+                            Style::default().fg(StatusBar::level_color(config, message.level)),
+                        ),
+                        Span::raw(message.text.clone()),
+                    ])
+                })
+                .collect()
+        };
+
+        // Don't scroll past the oldest message
+        let scroll = scroll.min(lines.len().saturating_sub(1));
+
+        // Clear the background of the popup area first
+        Clear.render(popup_area, buf);
+        Paragraph::new(lines)
+            .style(
+                Style::default()
+                    .bg(Color::from_str(bg).unwrap())
+                    .fg(Color::from_str(fg).unwrap()),
+            )
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .scroll((scroll as u16, 0))
+            .render(popup_area, buf);
+
+        scroll
+    }
+}
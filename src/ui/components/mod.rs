@@ -1,15 +1,24 @@
 pub mod db_selector;
+pub mod help;
 pub mod input_states;
 pub mod items;
 pub mod lists;
 pub mod logo;
+pub mod message_log;
+pub mod palette;
 pub mod popups;
+pub mod status_bar;
 
 pub use db_selector::DBSelector;
+pub use help::HelpPopUp;
 pub use input_states::InputState;
 pub use items::ItemsComponent;
 pub use lists::ListsComponent;
 pub use logo::Logo;
+pub use message_log::MessageLogPopUp;
+pub use palette::CommandPalettePopUp;
 pub use popups::{
-    AddDBPopUp, AddItemPopUp, AddListPopUp, ChangeDBPopUp, ModifyItemPopUp, ModifyListPopUp,
+    AddDBPopUp, AddItemPopUp, AddListPopUp, ChangeDBPopUp, FilterPopUp, ModifyItemPopUp,
+    ModifyListPopUp, RemoveDBPopUp, RenameDBPopUp, SaveViewPopUp,
 };
+pub use status_bar::StatusBar;
@@ -0,0 +1,121 @@
+use crate::app::palette::PaletteState;
+use crate::db::config::Config;
+use crate::ui::cursor::CursorState;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, BorderType, Borders, Clear, HighlightSpacing, List, ListItem, ListState, Padding,
+    Paragraph, StatefulWidget, Widget,
+};
+use std::str::FromStr;
+
+pub struct CommandPalettePopUp;
+
+impl CommandPalettePopUp {
+    /// Render the query input above the ranked matches, with the matched
+    /// characters highlighted
+    pub fn render(config: Config, state: &PaletteState, area: Rect, buf: &mut Buffer) {
+        let fg = Color::from_str(config.foreground()).unwrap();
+        let bg = Color::from_str(config.background()).unwrap();
+        let hl = Color::from_str(config.highlight()).unwrap();
+
+        // Command hints for the palette
+        let command_hints = Line::from(vec![
+            Span::raw(" "),
+            Span::raw(" ↑↓ "),
+            Span::styled("[Enter]", Style::default().fg(hl)),
+            Span::raw(" "),
+            Span::styled("[Esc]", Style::default().fg(hl)),
+            Span::raw(" "),
+        ]);
+
+        // Calculate popup dimensions
+        let popup_width = (area.width * 3) / 5; // 60% of the area width
+        let popup_height = (area.height * 3) / 5; // 60% of the area height
+
+        // Center the popup within the area
+        let popup_area = Rect {
+            x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+            y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+            width: popup_width,
+            height: popup_height,
+        };
+
+        // Clear the background of the popup area first
+        Clear.render(popup_area, buf);
+        Block::default()
+            .style(Style::default().bg(bg).fg(fg))
+            .render(popup_area, buf);
+
+        let popup_block = Block::new()
+            .title("  Command Palette  ")
+            .title_bottom(command_hints)
+            .borders(Borders::ALL)
+            .border_style(Style::new().fg(fg))
+            .border_type(BorderType::Rounded)
+            .padding(Padding::new(2, 2, 1, 1));
+
+        let inner = popup_block.inner(popup_area);
+        popup_block.render(popup_area, buf);
+
+        let [input_area, _, matches_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+
+        // Query with cursor
+        let mut input_spans = vec![Span::styled("> ", Style::default().fg(hl))];
+        input_spans.extend(state.create_cursor_text_spans(config.clone()));
+        Paragraph::new(Line::from(input_spans)).render(input_area, buf);
+
+        // Ranked matches
+        let items: Vec<ListItem> = state
+            .matches
+            .iter()
+            .map(|(i, fuzzy)| {
+                let entry = &state.entries[*i];
+                let mut spans: Vec<Span> = entry
+                    .label
+                    .chars()
+                    .enumerate()
+                    .map(|(j, c)| {
+                        if fuzzy.positions.contains(&j) {
+                            Span::styled(
+                                c.to_string(),
+                                Style::default().fg(hl).add_modifier(Modifier::BOLD),
+                            )
+                        } else {
+                            Span::raw(c.to_string())
+                        }
+                    })
+                    .collect();
+                if !entry.key.is_empty() {
+                    spans.push(Span::styled(
+                        format!("  [{}]", entry.key),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ));
+                }
+                ListItem::from(Line::from(spans))
+            })
+            .collect();
+
+        let mut list_state = ListState::default();
+        if !state.matches.is_empty() {
+            list_state.select(Some(state.selected));
+        }
+
+        let list = List::new(items)
+            .highlight_symbol(" ▸ ")
+            .highlight_style(
+                // Swap foreground and background for selected entry
+                Style::default().bg(fg).fg(bg),
+            )
+            .highlight_spacing(HighlightSpacing::Always);
+
+        StatefulWidget::render(list, matches_area, buf, &mut list_state);
+    }
+}
@@ -1,7 +1,10 @@
-use crate::db::config::Config;
+use crate::keymap::{Action, KeyMap};
+use crate::db::config::{Config, DBConfig};
+use crate::db::query::Expr;
 use crate::ui::cursor::CursorState;
+use crate::ui::hints::command_hints;
 use ratatui::buffer::Buffer;
-use ratatui::layout::Rect;
+use ratatui::layout::{Position, Rect};
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{
@@ -89,6 +92,15 @@ impl ModifyListPopUp {
     }
 }
 
+pub struct SaveViewPopUp;
+
+impl SaveViewPopUp {
+    /// Render popup for naming a view of the current filter
+    pub fn render<T: CursorState>(config: Config, state: &T, area: Rect, buf: &mut Buffer) {
+        render_list_popup_kernel(config, state, area, buf, "Save View");
+    }
+}
+
 pub struct AddItemPopUp;
 pub struct ModifyItemPopUp;
 
@@ -170,31 +182,139 @@ impl ModifyItemPopUp {
     }
 }
 
+pub struct FilterPopUp;
+
+impl FilterPopUp {
+    /// Render popup for entering the filter query, underlining any error in it
+    pub fn render<T: CursorState>(config: Config, state: &T, area: Rect, buf: &mut Buffer) {
+        let fg = config.foreground();
+        let hl = config.highlight();
+        let bg = config.background();
+        // Command hints for filter popup
+        let filter_command_hints = Line::from(vec![
+            Span::raw(" "),
+            Span::styled("[Esc]", Style::default().fg(Color::from_str(hl).unwrap())),
+            Span::raw(" Cancel "),
+            Span::styled("[Enter]", Style::default().fg(Color::from_str(hl).unwrap())),
+            Span::raw(" Apply, empty shows all "),
+        ]);
+
+        let popup_width = (area.width * 3) / 4; // 75% of the area width
+        let popup_height = 4; // Input field and error line
+        let popup_area = Rect {
+            x: area.x + (area.width.saturating_sub(popup_width)) / 2,
+            y: area.y + (area.height.saturating_sub(popup_height)) / 2,
+            width: popup_width,
+            height: popup_height.min(area.height),
+        };
+
+        // Clear the background of the popup area first
+        Clear.render(popup_area, buf);
+        Block::default()
+            .style(Style::default().bg(Color::from_str(bg).unwrap()))
+            .render(popup_area, buf);
+
+        let popup_block = Block::new()
+            .title("  Filter Items  ")
+            .title_style(Style::new().fg(Color::from_str(fg).unwrap()))
+            .title_bottom(filter_command_hints)
+            .borders(Borders::ALL)
+            .border_style(Style::new().fg(Color::from_str(fg).unwrap()))
+            .border_type(BorderType::Rounded)
+            .padding(Padding::horizontal(1));
+
+        // Point at the error while the query is being typed
+        let text = state.get_text();
+        let error_line = match Expr::parse(text) {
+            Err(e) if !text.trim().is_empty() => Line::styled(
+                e.underline(),
+                Style::default().fg(Color::from_str(hl).unwrap()),
+            ),
+            _ => Line::default(),
+        };
+
+        Paragraph::new(vec![
+            Line::from(state.create_cursor_text_spans(config.clone())),
+            error_line,
+        ])
+        .block(popup_block)
+        .render(popup_area, buf);
+    }
+}
+
 pub struct ChangeDBPopUp;
 
 impl ChangeDBPopUp {
+    /// Bordered and padded block framing the databases (titles are added when rendering)
+    fn frame() -> Block<'static> {
+        Block::new()
+            .padding(Padding::new(2, 2, 1, 1))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+    }
+
+    /// Index of the database drawn at the given position, if any
+    pub fn db_at(
+        config: &Config,
+        selected_index: usize,
+        area: Rect,
+        position: Position,
+    ) -> Option<usize> {
+        let inner = Self::frame().inner(area);
+        if !inner.contains(position) || inner.height == 0 {
+            return None;
+        }
+
+        // The list is rendered from a fresh state, so it only scrolls as far as
+        // needed to keep the selected database visible
+        let offset = (selected_index + 1).saturating_sub(inner.height as usize);
+        let index = (position.y - inner.y) as usize + offset;
+        (index < config.dbs.len()).then_some(index)
+    }
+
     /// Render popup for selecting database
-    pub fn render(config: &Config, selected_index: usize, area: Rect, buf: &mut Buffer) {
+    pub fn render(
+        config: &Config,
+        key_map: &KeyMap,
+        selected_index: usize,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
         let fg = config.foreground();
-        let hl = config.highlight();
         let bg = config.background();
-        // Command hints for change db popup
-        let change_db_command_hints = Line::from(vec![
+        // Command hints for change db popup, generated from the active key bindings
+        let mut hint_spans = vec![
             Span::raw(" "),
-            Span::styled(" ↑↓ ", Style::default()),
-            Span::styled("[A]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::styled("dd", Style::default().fg(Color::from_str(fg).unwrap())),
-            Span::styled(" [S]", Style::default().fg(Color::from_str(hl).unwrap())),
             Span::styled(
-                "et Default",
-                Style::default().fg(Color::from_str(fg).unwrap()),
-            ),
-            Span::styled(
-                " [Esc]",
-                Style::default().fg(Color::from_str(hl).unwrap()),
+                format!(
+                    " {}{} ",
+                    key_map.key_label(Action::PreviousDb),
+                    key_map.key_label(Action::NextDb)
+                ),
+                Style::default(),
             ),
-            Span::raw(" "),
-        ]);
+        ];
+        hint_spans.extend(command_hints(
+            key_map,
+            &[
+                (Action::AddDb, "Add"),
+                (Action::SetDefaultDb, "Set Default"),
+                (Action::CloseDbPopup, ""),
+            ],
+            config,
+        ));
+        hint_spans.push(Span::raw(" "));
+        let change_db_command_hints = Line::from(hint_spans);
+
+        // Changes to the selected database itself go in the top border
+        let mut edit_spans = vec![Span::raw(" ")];
+        edit_spans.extend(command_hints(
+            key_map,
+            &[(Action::RenameDb, "Rename"), (Action::RemoveDb, "Remove")],
+            config,
+        ));
+        edit_spans.push(Span::raw(" "));
+        let edit_db_command_hints = Line::from(edit_spans).right_aligned();
 
         Block::default()
             .style(
@@ -205,14 +325,12 @@ impl ChangeDBPopUp {
             .render(area, buf);
 
         // Define the popup block with styling
-        let popup_block = Block::new()
-            .padding(Padding::new(2, 2, 1, 1))
-            .title(" Select Database ")
+        let popup_block = Self::frame()
+            .title(" Databases ")
             .title_style(Style::new().fg(Color::from_str(fg).unwrap()))
+            .title_top(edit_db_command_hints)
             .title_bottom(change_db_command_hints)
-            .borders(Borders::ALL)
-            .border_style(Style::new().fg(Color::from_str(fg).unwrap()))
-            .border_type(BorderType::Rounded);
+            .border_style(Style::new().fg(Color::from_str(fg).unwrap()));
 
         // Create list items from databases
         let items: Vec<ListItem> = config
@@ -246,45 +364,107 @@ pub struct AddDBPopUp;
 impl AddDBPopUp {
     /// Render popup for entering a new database name
     pub fn render<T: CursorState>(config: Config, state: &T, area: Rect, buf: &mut Buffer) {
-        let fg = config.foreground();
-        let hl = config.highlight();
-        let bg = config.background();
-        // Command hints for add db popup
-        let add_db_command_hints = Line::from(vec![
-            Span::raw(" "),
-            Span::styled("[Esc]", Style::default().fg(Color::from_str(hl).unwrap())),
-            Span::raw(" "),
-        ]);
+        render_db_input_popup(config, state, " Add Database ", area, buf);
+    }
+}
+
+pub struct RenameDBPopUp;
+
+impl RenameDBPopUp {
+    /// Render popup for entering the new name of the selected database
+    pub fn render<T: CursorState>(config: Config, state: &T, area: Rect, buf: &mut Buffer) {
+        render_db_input_popup(config, state, " Rename Database ", area, buf);
+    }
+}
+
+/// Render a database name input over the database selector
+fn render_db_input_popup<T: CursorState>(
+    config: Config,
+    state: &T,
+    title: &str,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let fg = config.foreground();
+    let hl = config.highlight();
+    let bg = config.background();
+    // Command hints for add db popup
+    let add_db_command_hints = Line::from(vec![
+        Span::raw(" "),
+        Span::styled("[Esc]", Style::default().fg(Color::from_str(hl).unwrap())),
+        Span::raw(" "),
+    ]);
+
+    // Clear the entire area background first
+    Clear.render(area, buf);
+    Block::default()
+        .style(
+            Style::default()
+                .bg(Color::from_str(bg).unwrap())
+                .fg(Color::from_str(fg).unwrap()),
+        )
+        .render(area, buf);
+
+    // Define the popup block with styling - use full width
+    let popup_block = Block::new()
+        .padding(Padding::new(2, 2, 1, 1))
+        .title(title)
+        .title_style(Style::new().fg(Color::from_str(fg).unwrap()))
+        .title_bottom(add_db_command_hints)
+        .borders(Borders::ALL)
+        .border_style(Style::new().fg(Color::from_str(fg).unwrap()))
+        .border_type(BorderType::Rounded)
+        .padding(Padding::horizontal(1));
+
+    // Define the text to render
+    let text_spans = state.create_cursor_text_spans(config);
+    let text_line = Line::from(text_spans);
+
+    // Render the input field using the full area
+    Paragraph::new(text_line)
+        .wrap(Wrap { trim: true })
+        .block(popup_block)
+        .render(area, buf);
+}
+
+pub struct RemoveDBPopUp;
+
+impl RemoveDBPopUp {
+    /// Render popup asking whether the file of the removed database is kept
+    pub fn render(config: &Config, db: &DBConfig, area: Rect, buf: &mut Buffer) {
+        let fg = Color::from_str(config.foreground()).unwrap();
+        let hl = Color::from_str(config.highlight()).unwrap();
+        let bg = Color::from_str(config.background()).unwrap();
 
-        // Clear the entire area background first
         Clear.render(area, buf);
         Block::default()
-            .style(
-                Style::default()
-                    .bg(Color::from_str(bg).unwrap())
-                    .fg(Color::from_str(fg).unwrap()),
-            )
+            .style(Style::default().bg(bg).fg(fg))
             .render(area, buf);
 
-        // Define the popup block with styling - use full width
         let popup_block = Block::new()
             .padding(Padding::new(2, 2, 1, 1))
-            .title(" Add Database ")
-            .title_style(Style::new().fg(Color::from_str(fg).unwrap()))
-            .title_bottom(add_db_command_hints)
+            .title(" Remove Database ")
+            .title_style(Style::new().fg(fg))
             .borders(Borders::ALL)
-            .border_style(Style::new().fg(Color::from_str(fg).unwrap()))
-            .border_type(BorderType::Rounded)
-            .padding(Padding::horizontal(1));
-
-        // Define the text to render
-        let text_spans = state.create_cursor_text_spans(config);
-        let text_line = Line::from(text_spans);
+            .border_style(Style::new().fg(fg))
+            .border_type(BorderType::Rounded);
 
-        // Render the input field using the full area
-        Paragraph::new(text_line)
-            .wrap(Wrap { trim: true })
-            .block(popup_block)
-            .render(area, buf);
+        let option = |key: &'static str, label: &'static str| {
+            Line::from(vec![
+                Span::styled(key, Style::default().fg(hl)),
+                Span::raw(label),
+            ])
+        };
+
+        Paragraph::new(vec![
+            Line::from(format!("Remove {}?", db.name)),
+            Line::default(),
+            option("[f]", " Forget, keep the file"),
+            option("[d]", " Delete the file too"),
+            option("[Esc]", " Cancel"),
+        ])
+        .wrap(Wrap { trim: true })
+        .block(popup_block)
+        .render(area, buf);
     }
 }
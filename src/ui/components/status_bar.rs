@@ -0,0 +1,59 @@
+use crate::keymap::{Action, KeyMap};
+use crate::app::messages::{Message, MessageLevel};
+use crate::db::config::Config;
+use crate::ui::hints::command_hints;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Widget;
+use std::str::FromStr;
+
+pub struct StatusBar;
+
+impl StatusBar {
+    /// Colour a message level is shown in
+    pub fn level_color(config: &Config, level: MessageLevel) -> Color {
+        match level {
+            MessageLevel::Info => Color::from_str(config.highlight()).unwrap(),
+            MessageLevel::Warn => Color::Yellow,
+            MessageLevel::Error => Color::Red,
+        }
+    }
+
+    /// Render the latest message while it's fresh, or hints for the global commands
+    pub fn render(
+        config: &Config,
+        key_map: &KeyMap,
+        toast: Option<&Message>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        match toast {
+            Some(message) => {
+                let color = Self::level_color(config, message.level);
+                Line::from(vec![
+                    Span::styled(
+                        format!(" {} ", message.level.label()),
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(message.text.clone()),
+                ])
+                .render(area, buf);
+            }
+            None => {
+                let mut spans = command_hints(
+                    key_map,
+                    &[
+                        (Action::ShowHelp, "Help"),
+                        (Action::OpenPalette, "Palette"),
+                        (Action::ShowMessages, "Messages"),
+                    ],
+                    config,
+                );
+                spans.push(Span::raw(" "));
+                Line::from(spans).right_aligned().render(area, buf);
+            }
+        }
+    }
+}
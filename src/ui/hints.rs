@@ -0,0 +1,32 @@
+use crate::keymap::{Action, KeyMap};
+use crate::db::config::Config;
+use ratatui::style::{Color, Style};
+use ratatui::text::Span;
+use std::str::FromStr;
+
+/// Build the command hint spans shown in block borders, e.g. "[A]dd [D]el",
+/// from the active key bindings so they never drift from what the keys do
+pub fn command_hints(
+    key_map: &KeyMap,
+    hints: &[(Action, &str)],
+    config: &Config,
+) -> Vec<Span<'static>> {
+    let fg = Color::from_str(config.foreground()).unwrap();
+    let hl = Color::from_str(config.highlight()).unwrap();
+
+    let mut spans = Vec::new();
+    for (i, (action, label)) in hints.iter().enumerate() {
+        let (key, rest) = key_map.hint(*action, label);
+        if key.is_empty() {
+            // Unbound actions have nothing to hint at
+            continue;
+        }
+        let separator = if i == 0 { "" } else { " " };
+        spans.push(Span::styled(
+            format!("{separator}{key}"),
+            Style::default().fg(hl),
+        ));
+        spans.push(Span::styled(rest, Style::default().fg(fg)));
+    }
+    spans
+}
@@ -7,9 +7,19 @@ use std::str::FromStr;
 
 pub struct AppLayout;
 
+/// Areas of the main layout from the last render, kept for mouse hit-testing
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MainAreas {
+    pub lists: Rect,
+    pub items: Rect,
+    pub db_selector: Rect,
+    pub closed_selector: Rect,
+    pub status: Rect,
+}
+
 impl AppLayout {
     /// Calculate responsive layout areas
-    pub fn calculate_main_layout(area: Rect) -> (Rect, Rect, Rect, Rect, Rect) {
+    pub fn calculate_main_layout(area: Rect) -> (Rect, Rect, Rect, Rect, Rect, Rect) {
         // Add overall padding around the entire TUI
         // Adjust these values to control how much space you want from terminal borders
         let padded_area = area.inner(Margin {
@@ -31,11 +41,12 @@ impl AppLayout {
 
         let main_layout = Layout::vertical([
             header_height,
-            Constraint::Min(10), // Ensure minimum content area
+            Constraint::Min(10),   // Ensure minimum content area
+            Constraint::Length(1), // Status bar
         ]);
 
         // Extract the areas from the main layout using the padded area
-        let [header_area, content_area] = main_layout.areas(padded_area);
+        let [header_area, content_area, status_area] = main_layout.areas(padded_area);
 
         // Divide header between pure logo and database selector
         let header_layout = Layout::horizontal([Constraint::Min(50), Constraint::Length(35)]);
@@ -62,6 +73,7 @@ impl AppLayout {
             logo_area,
             db_selector_area,
             closed_selector_area,
+            status_area,
         )
     }
 
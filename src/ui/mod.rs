@@ -1,5 +1,6 @@
 pub mod components;
 pub mod cursor;
+pub mod hints;
 pub mod layout;
 
-pub use cursor::CursorState;
+pub use cursor::CursorState;
\ No newline at end of file
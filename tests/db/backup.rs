@@ -0,0 +1,160 @@
+use crate::helpers::db::setup_file_db;
+use anyhow::Result;
+use judo::db::backup::{self, BackupConfig, BackupReason};
+use judo::db::connections::{get_db_pool, init_db};
+use judo::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use sqlx::SqlitePool;
+
+async fn list_names(pool: &SqlitePool) -> Result<Vec<String>> {
+    let lists = TodoList::get_all(pool).await?;
+    Ok(lists.into_iter().map(|list| list.name).collect())
+}
+
+async fn add_list(pool: &SqlitePool, name: &str) -> Result<()> {
+    TodoList::create(
+        pool,
+        NewTodoList {
+            name: name.to_string(),
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restore_brings_back_a_backup() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = setup_file_db(dir.path()).await?;
+    let path = dir.path().join("judo.db");
+
+    let pool = get_db_pool(&connection_str).await?;
+    add_list(&pool, "Chores").await?;
+    let backup = backup::create(&pool, &path, BackupReason::Manual).await?;
+    assert!(
+        backup
+            .path
+            .starts_with(dir.path().join("backups").join("judo"))
+    );
+    add_list(&pool, "Oops").await?;
+
+    backup::restore(pool, &path, &backup).await?;
+
+    let pool = get_db_pool(&connection_str).await?;
+    assert_eq!(list_names(&pool).await?, ["Chores"]);
+    assert_eq!(backup::list(&path)?, vec![backup]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restore_replaces_a_database_in_use() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = setup_file_db(dir.path()).await?;
+    let path = dir.path().join("judo.db");
+
+    let pool = get_db_pool(&connection_str).await?;
+    add_list(&pool, "Chores").await?;
+    let list = &TodoList::get_all(&pool).await?[0];
+    for name in ["Laundry", "Dishes"] {
+        TodoItem::create(
+            &pool,
+            NewTodoItem {
+                list_id: list.id,
+                name: name.to_string(),
+                priority: None,
+                due_date: None,
+            },
+        )
+        .await?;
+    }
+    let backup = backup::create(&pool, &path, BackupReason::Manual).await?;
+
+    // Changes after the backup, still in the write-ahead log of the database
+    list.clone().delete(&pool).await?;
+    add_list(&pool, "Errands").await?;
+
+    backup::restore(pool, &path, &backup).await?;
+    let staged = dir.path().join("judo.db.restoring");
+    assert!(!staged.exists());
+
+    let pool = get_db_pool(&connection_str).await?;
+    let lists = TodoList::get_all(&pool).await?;
+    assert_eq!(list_names(&pool).await?, ["Chores"]);
+    let items: Vec<String> = lists[0]
+        .get_all_items(&pool)
+        .await?
+        .into_iter()
+        .map(|item| item.name)
+        .collect();
+    assert_eq!(items, ["Laundry", "Dishes"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schedule_keeps_the_latest_backups() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = setup_file_db(dir.path()).await?;
+    let path = dir.path().join("judo.db");
+    let pool = get_db_pool(&connection_str).await?;
+
+    let config = BackupConfig {
+        interval_hours: 1,
+        keep: 2,
+    };
+    let first = backup::run_schedule(&pool, &path, &config).await?;
+    assert!(first.is_some());
+    // Not due again within the hour
+    assert_eq!(backup::run_schedule(&pool, &path, &config).await?, None);
+
+    for _ in 0..2 {
+        backup::create(&pool, &path, BackupReason::Manual).await?;
+    }
+    let removed = backup::prune(&path, config.keep)?;
+    assert_eq!(removed, vec![first.unwrap()]);
+    let reasons: Vec<BackupReason> = backup::list(&path)?
+        .into_iter()
+        .map(|backup| backup.reason)
+        .collect();
+    assert_eq!(reasons, [BackupReason::Manual, BackupReason::Manual]);
+
+    // Never scheduled with an interval of 0
+    let config = BackupConfig {
+        interval_hours: 0,
+        keep: 2,
+    };
+    assert!(!backup::is_due(&path, &config)?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_init_db_backs_up_before_migrating() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = setup_file_db(dir.path()).await?;
+    let path = dir.path().join("judo.db");
+
+    // Nothing to migrate, nothing to back up
+    init_db(&connection_str).await?.close().await;
+    assert!(backup::list(&path)?.is_empty());
+
+    // Undo the latest migration, as if the database came from an older judo
+    let pool = get_db_pool(&connection_str).await?;
+    add_list(&pool, "Chores").await?;
+    sqlx::query("DROP TABLE caldav_sync_state")
+        .execute(&pool)
+        .await?;
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)",
+    )
+    .execute(&pool)
+    .await?;
+    pool.close().await;
+
+    let pool = init_db(&connection_str).await?;
+    let backups = backup::list(&path)?;
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].reason, BackupReason::Migration);
+
+    let copy = get_db_pool(&format!("sqlite:{}", backups[0].path.display())).await?;
+    assert_eq!(list_names(&copy).await?, ["Chores"]);
+    assert_eq!(list_names(&pool).await?, ["Chores"]);
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use crate::helpers::db::setup_file_db;
+use anyhow::Result;
+use judo::db::connections::get_db_pool;
+use judo::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use judo::db::snapshot::{ImportMode, ItemSnapshot, ListSnapshot, Snapshot};
+use std::collections::HashSet;
+
+const POOLS: usize = 4;
+const OPS_PER_POOL: usize = 25;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_item_creation_from_several_pools() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = setup_file_db(dir.path()).await?;
+
+    let pool = get_db_pool(&connection_str).await?;
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Shared".to_string(),
+        },
+    )
+    .await?;
+
+    // Each task opens its own pool, as separate judo processes would
+    let mut handles = Vec::new();
+    for p in 0..POOLS {
+        let connection_str = connection_str.clone();
+        let list_id = list.id;
+        handles.push(tokio::spawn(async move {
+            let pool = get_db_pool(&connection_str).await?;
+            for i in 0..OPS_PER_POOL {
+                TodoItem::create(
+                    &pool,
+                    NewTodoItem {
+                        list_id,
+                        name: format!("Item {p}-{i}"),
+                        priority: None,
+                        due_date: None,
+                    },
+                )
+                .await?;
+            }
+            anyhow::Ok(())
+        }));
+    }
+    for handle in handles {
+        handle.await??;
+    }
+
+    // Every item must be there, each with its own ordering slot
+    let items = TodoItem::get_by_list_id(&pool, list.id).await?;
+    assert_eq!(items.len(), POOLS * OPS_PER_POOL);
+
+    let orderings: HashSet<i64> = items.iter().map(|i| i.ordering).collect();
+    assert_eq!(orderings.len(), items.len());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_list_creation_from_several_pools() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = setup_file_db(dir.path()).await?;
+
+    let mut handles = Vec::new();
+    for p in 0..POOLS {
+        let connection_str = connection_str.clone();
+        handles.push(tokio::spawn(async move {
+            let pool = get_db_pool(&connection_str).await?;
+            for i in 0..OPS_PER_POOL {
+                TodoList::create(
+                    &pool,
+                    NewTodoList {
+                        name: format!("List {p}-{i}"),
+                    },
+                )
+                .await?;
+            }
+            anyhow::Ok(())
+        }));
+    }
+    for handle in handles {
+        handle.await??;
+    }
+
+    let pool = get_db_pool(&connection_str).await?;
+    let lists = TodoList::get_all(&pool).await?;
+    assert_eq!(lists.len(), POOLS * OPS_PER_POOL);
+
+    let orderings: HashSet<i64> = lists.iter().map(|l| l.ordering).collect();
+    assert_eq!(orderings.len(), lists.len());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_item_moves_keep_orderings_unique() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = setup_file_db(dir.path()).await?;
+
+    let pool = get_db_pool(&connection_str).await?;
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Shuffled".to_string(),
+        },
+    )
+    .await?;
+
+    let mut ids = Vec::new();
+    for i in 0..10 {
+        let item = TodoItem::create(
+            &pool,
+            NewTodoItem {
+                list_id: list.id,
+                name: format!("Item {i}"),
+                priority: None,
+                due_date: None,
+            },
+        )
+        .await?;
+        ids.push(item.id);
+    }
+    let original_orderings: HashSet<i64> = TodoItem::get_by_list_id(&pool, list.id)
+        .await?
+        .iter()
+        .map(|i| i.ordering)
+        .collect();
+
+    // Hammer the same items from several pools, some moving up and some down,
+    // always starting from a possibly stale in-memory copy of the item
+    let mut handles = Vec::new();
+    for p in 0..POOLS {
+        let connection_str = connection_str.clone();
+        let ids = ids.clone();
+        handles.push(tokio::spawn(async move {
+            let pool = get_db_pool(&connection_str).await?;
+            for i in 0..OPS_PER_POOL {
+                let id = ids[(p + i) % ids.len()];
+                let mut item = TodoItem::get_by_id(&pool, id)
+                    .await?
+                    .expect("Item should exist");
+                if (p + i) % 2 == 0 {
+                    item.move_up(&pool).await?;
+                } else {
+                    item.move_down(&pool).await?;
+                }
+            }
+            anyhow::Ok(())
+        }));
+    }
+    for handle in handles {
+        handle.await??;
+    }
+
+    // Swaps only permute the existing orderings, so no value may be lost or duplicated
+    let items = TodoItem::get_by_list_id(&pool, list.id).await?;
+    assert_eq!(items.len(), ids.len());
+
+    let orderings: HashSet<i64> = items.iter().map(|i| i.ordering).collect();
+    assert_eq!(orderings, original_orderings);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_list_moves_keep_orderings_unique() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = setup_file_db(dir.path()).await?;
+
+    let pool = get_db_pool(&connection_str).await?;
+    let mut ids = Vec::new();
+    for i in 0..6 {
+        let list = TodoList::create(
+            &pool,
+            NewTodoList {
+                name: format!("List {i}"),
+            },
+        )
+        .await?;
+        ids.push(list.id);
+    }
+    let original_orderings: HashSet<i64> = TodoList::get_all(&pool)
+        .await?
+        .iter()
+        .map(|l| l.ordering)
+        .collect();
+
+    let mut handles = Vec::new();
+    for p in 0..POOLS {
+        let connection_str = connection_str.clone();
+        let ids = ids.clone();
+        handles.push(tokio::spawn(async move {
+            let pool = get_db_pool(&connection_str).await?;
+            for i in 0..OPS_PER_POOL {
+                let id = ids[(p * 3 + i) % ids.len()];
+                let mut list = TodoList::get_by_id(&pool, id)
+                    .await?
+                    .expect("List should exist");
+                if i % 2 == 0 {
+                    list.move_down(&pool).await?;
+                } else {
+                    list.move_up(&pool).await?;
+                }
+            }
+            anyhow::Ok(())
+        }));
+    }
+    for handle in handles {
+        handle.await??;
+    }
+
+    let lists = TodoList::get_all(&pool).await?;
+    let orderings: HashSet<i64> = lists.iter().map(|l| l.ordering).collect();
+    assert_eq!(orderings, original_orderings);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_imports_from_several_pools() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = setup_file_db(dir.path()).await?;
+
+    // Every import merges into the same list, which must be created only once
+    let mut handles = Vec::new();
+    for p in 0..POOLS {
+        let connection_str = connection_str.clone();
+        handles.push(tokio::spawn(async move {
+            let pool = get_db_pool(&connection_str).await?;
+            for i in 0..OPS_PER_POOL {
+                let snapshot = Snapshot::new(vec![ListSnapshot {
+                    id: 1,
+                    name: "Shared".to_string(),
+                    ordering: 1,
+                    created_at: None,
+                    updated_at: None,
+                    items: vec![ItemSnapshot::new(format!("Item {p}-{i}"))],
+                }]);
+                snapshot.import(&pool, ImportMode::Merge).await?;
+            }
+            anyhow::Ok(())
+        }));
+    }
+    for handle in handles {
+        handle.await??;
+    }
+
+    let pool = get_db_pool(&connection_str).await?;
+    let lists = TodoList::get_all(&pool).await?;
+    assert_eq!(lists.len(), 1);
+
+    let items = TodoItem::get_by_list_id(&pool, lists[0].id).await?;
+    assert_eq!(items.len(), POOLS * OPS_PER_POOL);
+
+    Ok(())
+}
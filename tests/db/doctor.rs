@@ -0,0 +1,118 @@
+use crate::helpers::db::setup_file_db;
+use anyhow::Result;
+use judo::db::connections::get_db_pool;
+use judo::db::doctor::{self, OrphanFix, RECOVERED_LIST, RepairSummary, Report};
+use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use sqlx::SqlitePool;
+
+/// A database with items a, b and c in Chores and lost in Gone, then damaged
+/// as no judo command would: b and c share an ordering key, a and b have
+/// invalid priorities and Gone is deleted without its item
+async fn setup_damaged_db(dir: &std::path::Path) -> Result<SqlitePool> {
+    let pool = get_db_pool(&setup_file_db(dir).await?).await?;
+    for (list_name, names) in [("Chores", &["a", "b", "c"][..]), ("Gone", &["lost"][..])] {
+        let list = TodoList::create(
+            &pool,
+            NewTodoList {
+                name: list_name.to_string(),
+            },
+        )
+        .await?;
+        for name in names {
+            TodoItem::create(
+                &pool,
+                NewTodoItem {
+                    list_id: list.id,
+                    name: name.to_string(),
+                    priority: Some(Priority::Low),
+                    due_date: None,
+                },
+            )
+            .await?;
+        }
+    }
+
+    let mut conn = pool.acquire().await?;
+    for statement in [
+        "PRAGMA foreign_keys = OFF",
+        "PRAGMA ignore_check_constraints = ON",
+        "UPDATE todo_items SET ordering = 2048 WHERE name IN ('b', 'c')",
+        "UPDATE todo_items SET priority = 'High' WHERE name = 'a'",
+        "UPDATE todo_items SET priority = 'urgent' WHERE name = 'b'",
+        "DELETE FROM todo_lists WHERE name = 'Gone'",
+        "PRAGMA ignore_check_constraints = OFF",
+        "PRAGMA foreign_keys = ON",
+    ] {
+        sqlx::query(statement).execute(&mut *conn).await?;
+    }
+    Ok(pool)
+}
+
+#[tokio::test]
+async fn test_doctor_finds_and_fixes_problems() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let pool = setup_damaged_db(dir.path()).await?;
+
+    let report = doctor::check(&pool).await?;
+    assert_eq!(
+        report,
+        Report {
+            duplicate_item_orderings: vec![1],
+            orphans: vec![4],
+            invalid_priorities: vec![(1, "High".to_string()), (2, "urgent".to_string())],
+            ..Report::default()
+        }
+    );
+    assert!(report.is_repairable());
+
+    let summary = doctor::repair(&pool, &report, OrphanFix::Reattach).await?;
+    assert_eq!(
+        summary,
+        RepairSummary {
+            renormalized: 1,
+            reattached: 1,
+            priorities_fixed: 2,
+            ..RepairSummary::default()
+        }
+    );
+    doctor::vacuum(&pool).await?;
+    assert!(doctor::check(&pool).await?.is_healthy());
+
+    let lists = TodoList::get_all(&pool).await?;
+    assert_eq!(lists[1].name, RECOVERED_LIST);
+    let items = lists[0].get_all_items(&pool).await?;
+    let priorities: Vec<_> = items.iter().map(|item| item.priority.clone()).collect();
+    assert_eq!(
+        priorities,
+        [Some(Priority::High), None, Some(Priority::Low)]
+    );
+    assert_eq!(lists[1].get_all_items(&pool).await?[0].name, "lost");
+
+    // Moves no longer skip items
+    let mut c = items[2].clone();
+    c.move_up(&pool).await?;
+    let names: Vec<String> = lists[0]
+        .get_all_items(&pool)
+        .await?
+        .into_iter()
+        .map(|item| item.name)
+        .collect();
+    assert_eq!(names, ["a", "c", "b"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_doctor_deletes_orphans() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let pool = setup_damaged_db(dir.path()).await?;
+
+    let report = doctor::check(&pool).await?;
+    let summary = doctor::repair(&pool, &report, OrphanFix::Delete).await?;
+    assert_eq!(summary.deleted, 1);
+    assert_eq!(
+        TodoItem::get_by_id(&pool, 4).await?.map(|item| item.id),
+        None
+    );
+    assert_eq!(TodoList::get_all(&pool).await?.len(), 1);
+    Ok(())
+}
@@ -0,0 +1,269 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use chrono::{Duration, Local, Utc};
+use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList, UIList};
+use judo::db::ops::{ItemQuery, ItemSort, QueryParam};
+use judo::db::query::{Expr, Query};
+use sqlx::SqlitePool;
+
+/// Two lists: "work" with four items and "home" with one
+async fn setup_items() -> Result<SqlitePool> {
+    let pool = setup_test_db().await?;
+    let now = Utc::now();
+
+    let work = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "work".to_string(),
+        },
+    )
+    .await?;
+    let home = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "home".to_string(),
+        },
+    )
+    .await?;
+
+    let items = [
+        (
+            work.id,
+            "Write report",
+            Some(Priority::High),
+            Some(now - Duration::days(2)),
+        ),
+        (
+            work.id,
+            "Email Bob",
+            Some(Priority::Low),
+            Some(now + Duration::days(3)),
+        ),
+        (work.id, "100% coverage", None, None),
+        (
+            work.id,
+            "Plan_sprint",
+            Some(Priority::Medium),
+            Some(now + Duration::days(1)),
+        ),
+        (
+            home.id,
+            "Water plants",
+            Some(Priority::High),
+            Some(now - Duration::days(1)),
+        ),
+    ];
+    for (list_id, name, priority, due_date) in items {
+        TodoItem::create(
+            &pool,
+            NewTodoItem {
+                list_id,
+                name: name.to_string(),
+                priority,
+                due_date,
+            },
+        )
+        .await?;
+    }
+
+    // "Water plants" is done
+    let mut done = TodoItem::get_by_id(&pool, 5).await?.unwrap();
+    done.toggle_done(&pool).await?;
+
+    Ok(pool)
+}
+
+async fn names(pool: &SqlitePool, query: ItemQuery) -> Result<Vec<String>> {
+    Ok(query
+        .fetch(pool)
+        .await?
+        .into_iter()
+        .map(|listed| listed.item.name)
+        .collect())
+}
+
+#[tokio::test]
+async fn test_default_order_follows_lists() -> Result<()> {
+    let pool = setup_items().await?;
+
+    let items = ItemQuery::new().fetch(&pool).await?;
+    let names: Vec<(&str, &str)> = items
+        .iter()
+        .map(|listed| (listed.list_name.as_str(), listed.item.name.as_str()))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            ("work", "Write report"),
+            ("work", "Email Bob"),
+            ("work", "100% coverage"),
+            ("work", "Plan_sprint"),
+            ("home", "Water plants"),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filters() -> Result<()> {
+    let pool = setup_items().await?;
+
+    assert_eq!(
+        names(&pool, ItemQuery::new().list_name("home")).await?,
+        vec!["Water plants"]
+    );
+    assert_eq!(
+        names(&pool, ItemQuery::new().done(true)).await?,
+        vec!["Water plants"]
+    );
+    assert_eq!(
+        names(&pool, ItemQuery::new().priority(&Priority::High)).await?,
+        vec!["Write report", "Water plants"]
+    );
+    assert_eq!(
+        names(&pool, ItemQuery::new().due_after(Utc::now())).await?,
+        vec!["Email Bob", "Plan_sprint"]
+    );
+    assert_eq!(
+        names(&pool, ItemQuery::new().due_before(Utc::now())).await?,
+        vec!["Write report", "Water plants"]
+    );
+    // Done items are never overdue
+    assert_eq!(
+        names(&pool, ItemQuery::new().overdue(Utc::now())).await?,
+        vec!["Write report"]
+    );
+    // Filters combine
+    assert_eq!(
+        names(
+            &pool,
+            ItemQuery::new().list_name("work").priority(&Priority::High)
+        )
+        .await?,
+        vec!["Write report"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_ignores_case_and_wildcards() -> Result<()> {
+    let pool = setup_items().await?;
+
+    assert_eq!(
+        names(&pool, ItemQuery::new().search("bob")).await?,
+        vec!["Email Bob"]
+    );
+    // "%" and "_" match themselves, not any text
+    assert_eq!(
+        names(&pool, ItemQuery::new().search("100%")).await?,
+        vec!["100% coverage"]
+    );
+    assert_eq!(
+        names(&pool, ItemQuery::new().search("_")).await?,
+        vec!["Plan_sprint"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sort_and_limit() -> Result<()> {
+    let pool = setup_items().await?;
+
+    assert_eq!(
+        names(&pool, ItemQuery::new().sort(ItemSort::Due)).await?,
+        vec![
+            "Write report",
+            "Water plants",
+            "Plan_sprint",
+            "Email Bob",
+            "100% coverage"
+        ]
+    );
+    assert_eq!(
+        names(&pool, ItemQuery::new().sort(ItemSort::Priority).limit(3)).await?,
+        vec!["Write report", "Water plants", "Plan_sprint"]
+    );
+    assert_eq!(
+        names(&pool, ItemQuery::new().sort(ItemSort::Name).limit(2)).await?,
+        vec!["100% coverage", "Email Bob"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_values_are_bound_as_parameters() -> Result<()> {
+    let pool = setup_items().await?;
+
+    let query = ItemQuery::new().list_name("work' OR 1=1 --").limit(10);
+    let (sql, params) = query.to_sql();
+    assert!(!sql.contains("OR 1=1"));
+    assert_eq!(
+        params,
+        vec![
+            QueryParam::Text("work' OR 1=1 --".to_string()),
+            QueryParam::Int(10)
+        ]
+    );
+    assert!(query.fetch(&pool).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_matching_query() -> Result<()> {
+    let pool = setup_items().await?;
+    let now = Local::now();
+
+    let matching = |text: &str| ItemQuery::new().matching(&Expr::parse(text).unwrap(), &now);
+
+    assert_eq!(
+        names(&pool, matching("priority:high open")).await?,
+        vec!["Write report"]
+    );
+    assert_eq!(
+        names(&pool, matching("list:WORK (bob or plan)")).await?,
+        vec!["Email Bob", "Plan_sprint"]
+    );
+    assert_eq!(
+        names(&pool, matching("priority>=medium not list:home")).await?,
+        vec!["Write report", "Plan_sprint"]
+    );
+    assert_eq!(
+        names(&pool, matching("due:none")).await?,
+        vec!["100% coverage"]
+    );
+    assert_eq!(
+        names(&pool, matching("overdue or done")).await?,
+        vec!["Write report", "Water plants"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filtered_ui_lists_keep_every_list() -> Result<()> {
+    let pool = setup_items().await?;
+    let query: Query = "priority:high".parse()?;
+
+    let lists = UIList::get_all_filtered(&pool, Some(&query)).await?;
+    let items: Vec<(&str, Vec<&str>)> = lists
+        .iter()
+        .map(|ui_list| {
+            (
+                ui_list.list.name.as_str(),
+                ui_list
+                    .items
+                    .iter()
+                    .map(|ui_item| ui_item.item.name.as_str())
+                    .collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            ("work", vec!["Write report"]),
+            ("home", vec!["Water plants"])
+        ]
+    );
+    assert_eq!(lists[0].filter.as_ref(), Some(&query));
+    Ok(())
+}
@@ -1,2 +1,9 @@
+pub mod backup;
+pub mod concurrency;
+pub mod doctor;
+pub mod item_query;
+pub mod snapshot;
+pub mod taskwarrior;
 pub mod todo_item_ops;
 pub mod todo_list_ops;
+pub mod views;
@@ -0,0 +1,228 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use judo::db::snapshot::{ImportMode, ListSnapshot, SNAPSHOT_VERSION, Snapshot};
+use judo::formats::markdown::{from_markdown, to_markdown};
+use sqlx::SqlitePool;
+
+/// Lists with their items, by name
+async fn contents(pool: &SqlitePool) -> Result<Vec<(String, Vec<String>)>> {
+    let mut contents = Vec::new();
+    for list in TodoList::get_all(pool).await? {
+        let items = list.get_all_items(pool).await?;
+        contents.push((list.name, items.into_iter().map(|item| item.name).collect()));
+    }
+    Ok(contents)
+}
+
+async fn setup_chores(pool: &SqlitePool) -> Result<()> {
+    for (list_name, items) in [
+        ("Chores", ["Laundry", "Dishes"]),
+        ("Work", ["Report", "Email"]),
+    ] {
+        let list = TodoList::create(
+            pool,
+            NewTodoList {
+                name: list_name.to_string(),
+            },
+        )
+        .await?;
+        for name in items {
+            TodoItem::create(
+                pool,
+                NewTodoItem {
+                    list_id: list.id,
+                    name: name.to_string(),
+                    priority: Some(Priority::Low),
+                    due_date: None,
+                },
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Lists of a snapshot without the IDs, which importing changes
+fn without_ids(snapshot: &Snapshot) -> Vec<ListSnapshot> {
+    let mut lists = snapshot.lists.clone();
+    for list in &mut lists {
+        list.id = 0;
+        for item in &mut list.items {
+            item.id = 0;
+        }
+    }
+    lists
+}
+
+#[tokio::test]
+async fn test_export_import_round_trip() -> Result<()> {
+    let source = setup_test_db().await?;
+    setup_chores(&source).await?;
+
+    // Move things around and change them, so that every field is exercised
+    let mut work = TodoList::get_all(&source).await?.remove(1);
+    work.move_to_top(&source).await?;
+    let mut email = work.get_all_items(&source).await?.remove(1);
+    email.move_to_top(&source).await?;
+    email.toggle_done(&source).await?;
+
+    let exported = Snapshot::from_db(&source).await?;
+    assert_eq!(exported.version, SNAPSHOT_VERSION);
+
+    let target = setup_test_db().await?;
+    let imported = Snapshot::from_json(&exported.to_json()?)?;
+    let summary = imported.import(&target, ImportMode::Replace).await?;
+    assert_eq!(summary.lists_created, 2);
+    assert_eq!(summary.items_created, 4);
+
+    assert_eq!(
+        contents(&target).await?,
+        vec![
+            (
+                "Work".to_string(),
+                vec!["Email".to_string(), "Report".to_string()]
+            ),
+            (
+                "Chores".to_string(),
+                vec!["Laundry".to_string(), "Dishes".to_string()]
+            ),
+        ]
+    );
+    let reexported = Snapshot::from_db(&target).await?;
+    assert_eq!(without_ids(&reexported), without_ids(&exported));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_merge_remaps_ids_and_skips_known_items() -> Result<()> {
+    let source = setup_test_db().await?;
+    setup_chores(&source).await?;
+    let snapshot = Snapshot::from_db(&source).await?;
+
+    // The target already has a list of its own, taking the IDs of the source
+    let target = setup_test_db().await?;
+    let groceries = TodoList::create(
+        &target,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+    let summary = snapshot.import(&target, ImportMode::Merge).await?;
+    assert_eq!(summary.lists_created, 2);
+    assert_eq!(summary.items_created, 4);
+
+    for list in &snapshot.lists {
+        let new_id = summary.list_ids[&list.id];
+        assert_ne!(new_id, groceries.id);
+        let new_list = TodoList::get_by_id(&target, new_id).await?.unwrap();
+        assert_eq!(new_list.name, list.name);
+
+        for item in &list.items {
+            let new_item = TodoItem::get_by_id(&target, summary.item_ids[&item.id])
+                .await?
+                .unwrap();
+            assert_eq!(new_item.list_id, new_id);
+            assert_eq!(new_item.name, item.name);
+        }
+    }
+
+    // Importing again finds every item already there
+    let summary = snapshot.import(&target, ImportMode::Merge).await?;
+    assert_eq!(summary.lists_merged, 2);
+    assert_eq!(summary.items_created, 0);
+    assert_eq!(summary.items_skipped, 4);
+
+    // New items of the source are appended to the merged list
+    let chores = TodoList::get_all(&source).await?.remove(0);
+    TodoItem::create(
+        &source,
+        NewTodoItem {
+            list_id: chores.id,
+            name: "Vacuum".to_string(),
+            priority: None,
+            due_date: None,
+        },
+    )
+    .await?;
+    let summary = Snapshot::from_db(&source)
+        .await?
+        .import(&target, ImportMode::Merge)
+        .await?;
+    assert_eq!(summary.items_created, 1);
+
+    assert_eq!(
+        contents(&target).await?,
+        vec![
+            ("Groceries".to_string(), vec![]),
+            (
+                "Chores".to_string(),
+                vec![
+                    "Laundry".to_string(),
+                    "Dishes".to_string(),
+                    "Vacuum".to_string()
+                ]
+            ),
+            (
+                "Work".to_string(),
+                vec!["Report".to_string(), "Email".to_string()]
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_replace_drops_existing_lists() -> Result<()> {
+    let source = setup_test_db().await?;
+    setup_chores(&source).await?;
+    let snapshot = Snapshot::from_db(&source).await?;
+
+    let target = setup_test_db().await?;
+    setup_chores(&target).await?;
+    TodoList::create(
+        &target,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+
+    snapshot.import(&target, ImportMode::Replace).await?;
+    assert_eq!(contents(&target).await?, contents(&source).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_markdown_round_trip() -> Result<()> {
+    let source = setup_test_db().await?;
+    setup_chores(&source).await?;
+    let mut laundry = TodoItem::get_by_id(&source, 1).await?.unwrap();
+    laundry.toggle_done(&source).await?;
+
+    let markdown = to_markdown(&Snapshot::from_db(&source).await?, Some("dojo"));
+    let target = setup_test_db().await?;
+    from_markdown(&markdown)
+        .import(&target, ImportMode::Merge)
+        .await?;
+    assert_eq!(contents(&target).await?, contents(&source).await?);
+
+    let imported = TodoList::get_all(&target).await?[0]
+        .get_all_items(&target)
+        .await?;
+    assert!(imported[0].is_done);
+    assert_eq!(imported[0].priority, Some(Priority::Low));
+
+    // Without creation times, items are recognised by their name
+    let summary = from_markdown(&markdown)
+        .import(&target, ImportMode::Merge)
+        .await?;
+    assert_eq!(summary.items_created, 0);
+    assert_eq!(summary.items_skipped, 4);
+
+    Ok(())
+}
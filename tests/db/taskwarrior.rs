@@ -0,0 +1,73 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use judo::db::models::{Priority, TodoList};
+use judo::db::snapshot::ImportMode;
+use judo::formats::taskwarrior::from_taskwarrior;
+use std::collections::BTreeMap;
+
+#[tokio::test]
+async fn test_import_taskwarrior_export() -> Result<()> {
+    let pool = setup_test_db().await?;
+    let (snapshot, report) = from_taskwarrior(include_str!("../fixtures/taskwarrior.json"))?;
+
+    assert_eq!(report.deleted, 1);
+    assert_eq!(report.recurring, 1);
+    assert_eq!(
+        report.unmapped,
+        BTreeMap::from([
+            ("depends".to_string(), 1),
+            ("estimate".to_string(), 1),
+            ("parent".to_string(), 1),
+            ("recur".to_string(), 1),
+            ("wait".to_string(), 1),
+        ])
+    );
+
+    let summary = snapshot.import(&pool, ImportMode::Merge).await?;
+    assert_eq!(summary.lists_created, 3);
+    assert_eq!(summary.items_created, 5);
+
+    let lists = TodoList::get_all(&pool).await?;
+    let names: Vec<&str> = lists.iter().map(|list| list.name.as_str()).collect();
+    assert_eq!(names, ["Home", "Errands", "Inbox"]);
+
+    let home = lists[0].get_all_items(&pool).await?;
+    let names: Vec<&str> = home.iter().map(|item| item.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "Fix the fence +garden +weekend (Ask Bob for a ladder)",
+            "Buy nails",
+            "Water the plants",
+        ]
+    );
+    let fence = &home[0];
+    assert_eq!(fence.priority, Some(Priority::High));
+    assert!(!fence.is_done);
+    assert_eq!(
+        fence.due_date,
+        Some(Utc.with_ymd_and_hms(2025, 10, 31, 23, 0, 0).unwrap())
+    );
+    assert_eq!(
+        fence.created_at,
+        Utc.with_ymd_and_hms(2025, 10, 1, 8, 0, 0).unwrap()
+    );
+
+    let passport = &lists[1].get_all_items(&pool).await?[0];
+    assert!(passport.is_done);
+    assert_eq!(passport.priority, Some(Priority::Medium));
+    assert_eq!(
+        passport.updated_at,
+        Utc.with_ymd_and_hms(2025, 10, 10, 12, 0, 0).unwrap()
+    );
+
+    let mum = &lists[2].get_all_items(&pool).await?[0];
+    assert_eq!(mum.priority, Some(Priority::Low));
+
+    // Importing the export again changes nothing
+    let (snapshot, _) = from_taskwarrior(include_str!("../fixtures/taskwarrior.json"))?;
+    let summary = snapshot.import(&pool, ImportMode::Merge).await?;
+    assert_eq!(summary.items_created, 0);
+    Ok(())
+}
@@ -2,6 +2,7 @@ use crate::helpers::db::setup_test_db;
 use anyhow::Result;
 use chrono::{Duration, Utc};
 use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use judo::db::ops::ORDERING_GAP;
 
 #[tokio::test]
 async fn test_todo_item_crud_operations() -> Result<()> {
@@ -37,7 +38,7 @@ async fn test_todo_item_crud_operations() -> Result<()> {
     // Verify the created list has correct properties
     assert!(created_item_with_all_fields.id > 0);
     assert_eq!(created_item_with_all_fields.list_id, created_list.id);
-    assert_eq!(created_item_with_all_fields.is_done, false);
+    assert!(!created_item_with_all_fields.is_done);
     assert_eq!(created_item_with_all_fields.name, "My item");
     assert_eq!(created_item_with_all_fields.priority, Some(Priority::High));
     assert!(created_item_with_all_fields.due_date.is_some());
@@ -53,7 +54,7 @@ async fn test_todo_item_crud_operations() -> Result<()> {
     assert!(created_item_without_due_date.id > 0);
     assert_eq!(created_item_without_due_date.list_id, created_list.id);
     assert_eq!(created_item_without_due_date.name, "My item without date");
-    assert_eq!(created_item_without_due_date.is_done, false);
+    assert!(!created_item_without_due_date.is_done);
     assert_eq!(created_item_without_due_date.priority, Some(Priority::Low));
     assert!(created_item_without_due_date.due_date.is_none());
     assert!(created_item_without_due_date.created_at <= Utc::now());
@@ -175,7 +176,7 @@ async fn test_todo_item_creation_edge_cases() -> Result<()> {
     assert_eq!(created_minimal.name, "Minimal item");
     assert_eq!(created_minimal.priority, None);
     assert_eq!(created_minimal.due_date, None);
-    assert_eq!(created_minimal.is_done, false);
+    assert!(!created_minimal.is_done);
 
     // Test creating item with empty name
     let empty_name_item = NewTodoItem {
@@ -297,6 +298,14 @@ async fn test_todo_item_priority_operations() -> Result<()> {
         .expect("Item should exist");
     assert_eq!(fetched_item.priority, Some(Priority::High));
 
+    // Remove the priority again
+    item_without_priority.clear_priority(&pool).await?;
+    assert_eq!(item_without_priority.priority, None);
+    let fetched_item = TodoItem::get_by_id(&pool, item_without_priority.id)
+        .await?
+        .expect("Item should exist");
+    assert_eq!(fetched_item.priority, None);
+
     Ok(())
 }
 
@@ -355,6 +364,14 @@ async fn test_todo_item_due_date_operations() -> Result<()> {
         .expect("Item should exist");
     assert_eq!(fetched_item.due_date, Some(far_future_date));
 
+    // Remove the due date again
+    item_without_due_date.clear_due_date(&pool).await?;
+    assert_eq!(item_without_due_date.due_date, None);
+    let fetched_item = TodoItem::get_by_id(&pool, item_without_due_date.id)
+        .await?
+        .expect("Item should exist");
+    assert_eq!(fetched_item.due_date, None);
+
     Ok(())
 }
 
@@ -379,7 +396,7 @@ async fn test_todo_item_toggle_done_edge_cases() -> Result<()> {
     .await?;
 
     // Initial state should be false
-    assert_eq!(test_item.is_done, false);
+    assert!(!test_item.is_done);
 
     // Toggle multiple times to test both directions
     for i in 0..10 {
@@ -674,3 +691,148 @@ async fn test_todo_item_ordering_by_creation_date() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_todo_item_move_to_position() -> Result<()> {
+    let pool = setup_test_db().await?;
+
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Reorder Test List".to_string(),
+        },
+    )
+    .await?;
+
+    let mut items = Vec::new();
+    for name in ["A", "B", "C", "D", "E"] {
+        items.push(
+            TodoItem::create(
+                &pool,
+                NewTodoItem {
+                    list_id: list.id,
+                    name: name.to_string(),
+                    priority: None,
+                    due_date: None,
+                },
+            )
+            .await?,
+        );
+    }
+
+    let names = |items: &[TodoItem]| items.iter().map(|i| i.name.clone()).collect::<Vec<_>>();
+
+    // Move the last item to the top
+    items[4].move_to_top(&pool).await?;
+    let fetched = TodoItem::get_by_list_id(&pool, list.id).await?;
+    assert_eq!(names(&fetched), ["E", "A", "B", "C", "D"]);
+
+    // Move the (original) first item to the bottom
+    items[0].move_to_bottom(&pool).await?;
+    let fetched = TodoItem::get_by_list_id(&pool, list.id).await?;
+    assert_eq!(names(&fetched), ["E", "B", "C", "D", "A"]);
+
+    // Drag "D" between "E" and "B"
+    let mut d = fetched[3].clone();
+    d.move_to_position(&pool, 1).await?;
+    let fetched = TodoItem::get_by_list_id(&pool, list.id).await?;
+    assert_eq!(names(&fetched), ["E", "D", "B", "C", "A"]);
+    assert_eq!(fetched[1].ordering, d.ordering);
+
+    // Positions past the end clamp to the bottom
+    let mut e = fetched[0].clone();
+    e.move_to_position(&pool, 100).await?;
+    let fetched = TodoItem::get_by_list_id(&pool, list.id).await?;
+    assert_eq!(names(&fetched), ["D", "B", "C", "A", "E"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_todo_item_move_to_position_renormalizes_when_out_of_gaps() -> Result<()> {
+    let pool = setup_test_db().await?;
+
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Gap Test List".to_string(),
+        },
+    )
+    .await?;
+
+    let mut items = Vec::new();
+    for i in 0..3 {
+        items.push(
+            TodoItem::create(
+                &pool,
+                NewTodoItem {
+                    list_id: list.id,
+                    name: format!("Item {i}"),
+                    priority: None,
+                    due_date: None,
+                },
+            )
+            .await?,
+        );
+    }
+
+    // Repeatedly insert between the first two items until the gap runs out
+    let mut last = items[2].clone();
+    for _ in 0..20 {
+        last.move_to_position(&pool, 1).await?;
+        let fetched = TodoItem::get_by_list_id(&pool, list.id).await?;
+        assert_eq!(fetched[1].id, last.id);
+        last = fetched[2].clone();
+    }
+
+    // Ordering keys stay strictly increasing
+    let fetched = TodoItem::get_by_list_id(&pool, list.id).await?;
+    assert!(fetched.windows(2).all(|w| w[0].ordering < w[1].ordering));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_todo_item_renormalize() -> Result<()> {
+    let pool = setup_test_db().await?;
+
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Renormalize Test List".to_string(),
+        },
+    )
+    .await?;
+
+    for i in 0..4 {
+        TodoItem::create(
+            &pool,
+            NewTodoItem {
+                list_id: list.id,
+                name: format!("Item {i}"),
+                priority: None,
+                due_date: None,
+            },
+        )
+        .await?;
+    }
+
+    // Squeeze two items together, then respace the whole list
+    let mut fetched = TodoItem::get_by_list_id(&pool, list.id).await?;
+    fetched[3].move_to_position(&pool, 1).await?;
+    let before: Vec<i64> = TodoItem::get_by_list_id(&pool, list.id)
+        .await?
+        .iter()
+        .map(|i| i.id)
+        .collect();
+
+    TodoItem::renormalize(&pool, list.id).await?;
+
+    let after = TodoItem::get_by_list_id(&pool, list.id).await?;
+    assert_eq!(after.iter().map(|i| i.id).collect::<Vec<_>>(), before);
+    for (i, item) in after.iter().enumerate() {
+        assert_eq!(item.ordering, (i as i64 + 1) * ORDERING_GAP);
+    }
+
+    Ok(())
+}
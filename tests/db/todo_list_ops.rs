@@ -2,6 +2,7 @@ use crate::helpers::db::setup_test_db;
 use anyhow::Result;
 use chrono::{Duration, Utc};
 use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList, UIList};
+use judo::db::ops::ORDERING_GAP;
 
 #[tokio::test]
 async fn test_todo_list_crud_operations() -> Result<()> {
@@ -250,7 +251,7 @@ async fn test_todo_list_cascade_deletion() -> Result<()> {
     let item3 = TodoItem::create(
         &pool,
         NewTodoItem {
-            list_id: test_list.id.clone(),
+            list_id: test_list.id,
             name: "Item 3".to_string(),
             priority: None,
             due_date: Some(Utc::now() - Duration::days(1)),
@@ -259,21 +260,21 @@ async fn test_todo_list_cascade_deletion() -> Result<()> {
     .await?;
 
     // Save the id
-    let test_list_id = test_list.id.clone();
+    let test_list_id = test_list.id;
 
     // Verify items exist
-    let items_before = TodoItem::get_by_list_id(&pool, test_list_id.clone()).await?;
+    let items_before = TodoItem::get_by_list_id(&pool, test_list_id).await?;
     assert_eq!(items_before.len(), 3);
 
     // Delete the list
     test_list.delete(&pool).await?;
 
     // Verify list is deleted
-    let deleted_list = TodoList::get_by_id(&pool, test_list_id.clone()).await?;
+    let deleted_list = TodoList::get_by_id(&pool, test_list_id).await?;
     assert!(deleted_list.is_none());
 
     // Verify all items are cascade deleted
-    let items_after = TodoItem::get_by_list_id(&pool, test_list_id.clone()).await?;
+    let items_after = TodoItem::get_by_list_id(&pool, test_list_id).await?;
     assert_eq!(items_after.len(), 0);
 
     // Verify each item individually
@@ -632,3 +633,46 @@ async fn test_multiple_lists_with_mixed_items() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_todo_list_move_to_position() -> Result<()> {
+    let pool = setup_test_db().await?;
+
+    let mut lists = Vec::new();
+    for name in ["Home", "Work", "Errands", "Books"] {
+        lists.push(
+            TodoList::create(
+                &pool,
+                NewTodoList {
+                    name: name.to_string(),
+                },
+            )
+            .await?,
+        );
+    }
+
+    let names = |lists: &[TodoList]| lists.iter().map(|l| l.name.clone()).collect::<Vec<_>>();
+
+    lists[3].move_to_top(&pool).await?;
+    let fetched = TodoList::get_all(&pool).await?;
+    assert_eq!(names(&fetched), ["Books", "Home", "Work", "Errands"]);
+
+    lists[0].move_to_bottom(&pool).await?;
+    let fetched = TodoList::get_all(&pool).await?;
+    assert_eq!(names(&fetched), ["Books", "Work", "Errands", "Home"]);
+
+    let mut errands = fetched[2].clone();
+    errands.move_to_position(&pool, 1).await?;
+    let fetched = TodoList::get_all(&pool).await?;
+    assert_eq!(names(&fetched), ["Books", "Errands", "Work", "Home"]);
+
+    // Renormalizing keeps the order and respaces the keys
+    TodoList::renormalize(&pool).await?;
+    let renormalized = TodoList::get_all(&pool).await?;
+    assert_eq!(names(&renormalized), names(&fetched));
+    for (i, list) in renormalized.iter().enumerate() {
+        assert_eq!(list.ordering, (i as i64 + 1) * ORDERING_GAP);
+    }
+
+    Ok(())
+}
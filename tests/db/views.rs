@@ -0,0 +1,121 @@
+use crate::helpers::db::setup_file_db;
+use anyhow::Result;
+use judo::db::connections::get_db_pool;
+use judo::db::models::{
+    ItemSource, NewTodoItem, NewTodoList, Priority, SavedView, TodoItem, TodoList, UIList,
+};
+use sqlx::SqlitePool;
+use std::path::Path;
+
+/// A file database in `dir` with a list holding the given items
+async fn setup_db(
+    dir: &Path,
+    list_name: &str,
+    items: &[(&str, Option<Priority>)],
+) -> Result<SqlitePool> {
+    let pool = get_db_pool(&setup_file_db(dir).await?).await?;
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: list_name.to_string(),
+        },
+    )
+    .await?;
+    for (name, priority) in items {
+        TodoItem::create(
+            &pool,
+            NewTodoItem {
+                list_id: list.id,
+                name: name.to_string(),
+                priority: priority.clone(),
+                due_date: None,
+            },
+        )
+        .await?;
+    }
+    Ok(pool)
+}
+
+async fn urgent_view(work: &SqlitePool, home: &SqlitePool) -> Result<UIList> {
+    let source = |db_name: &str, pool: &SqlitePool| ItemSource {
+        db_name: db_name.to_string(),
+        pool: pool.clone(),
+    };
+    UIList::get_view(SavedView {
+        name: "urgent".to_string(),
+        query: "priority:high open".parse()?,
+        sources: vec![source("work", work), source("home", home)],
+    })
+    .await
+}
+
+fn names(ui_list: &UIList) -> Vec<(&str, &str, &str)> {
+    ui_list
+        .items
+        .iter()
+        .map(|ui_item| {
+            let origin = ui_item.origin.as_ref().unwrap();
+            (
+                ui_item.item.name.as_str(),
+                origin.list_name.as_str(),
+                origin.source.db_name.as_str(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_view_spans_databases() -> Result<()> {
+    let work_dir = tempfile::tempdir()?;
+    let home_dir = tempfile::tempdir()?;
+    let work = setup_db(
+        work_dir.path(),
+        "jobs",
+        &[("Report", Some(Priority::High)), ("Email", None)],
+    )
+    .await?;
+    let home = setup_db(
+        home_dir.path(),
+        "chores",
+        &[("Taxes", Some(Priority::High))],
+    )
+    .await?;
+
+    let view = urgent_view(&work, &home).await?;
+    assert_eq!(view.list.id, 0);
+    assert_eq!(view.list.name, "urgent");
+    assert_eq!(
+        names(&view),
+        vec![("Report", "jobs", "work"), ("Taxes", "chores", "home")]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_view_items_change_in_their_own_database() -> Result<()> {
+    let work_dir = tempfile::tempdir()?;
+    let home_dir = tempfile::tempdir()?;
+    let work = setup_db(work_dir.path(), "jobs", &[("Report", Some(Priority::High))]).await?;
+    let home = setup_db(
+        home_dir.path(),
+        "chores",
+        &[("Taxes", Some(Priority::High))],
+    )
+    .await?;
+
+    let mut view = urgent_view(&work, &home).await?;
+
+    // The pool passed for the current database is ignored for items of a view
+    let taxes = &mut view.items[1];
+    let pool = taxes.pool(&work).clone();
+    taxes.item.toggle_done(&pool).await?;
+
+    let stored = TodoItem::get_by_id(&home, taxes.item.id).await?.unwrap();
+    assert!(stored.is_done);
+    assert!(!TodoItem::get_by_id(&work, 1).await?.unwrap().is_done);
+
+    // Done items drop out of the view once it's reloaded
+    view.update_items(&work).await?;
+    assert_eq!(names(&view), vec![("Report", "jobs", "work")]);
+    Ok(())
+}
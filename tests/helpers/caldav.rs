@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::any;
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Path of the calendar collection on the mock server
+pub const COLLECTION: &str = "/calendars/bob/tasks/";
+
+/// Resources of the collection by path, with their ETag and data
+type Store = Arc<Mutex<BTreeMap<String, (String, String)>>>;
+
+/// A CalDAV server keeping its to-dos in memory, answering calendar queries and
+/// conditional writes and deletes like a real one
+pub struct CalDavServer {
+    server: MockServer,
+    store: Store,
+}
+
+impl CalDavServer {
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        let store = Store::default();
+        Mock::given(any())
+            .respond_with(Collection {
+                store: store.clone(),
+            })
+            .mount(&server)
+            .await;
+
+        Self { server, store }
+    }
+
+    /// URL of the collection
+    pub fn url(&self) -> String {
+        format!("{}{}", self.server.uri(), COLLECTION)
+    }
+
+    /// Paths and data of the to-dos
+    pub fn todos(&self) -> Vec<(String, String)> {
+        let store = self.store.lock().unwrap();
+        store
+            .iter()
+            .map(|(href, (_, data))| (href.clone(), data.clone()))
+            .collect()
+    }
+
+    /// Write a to-do as another client would
+    pub fn put(&self, href: &str, data: &str) {
+        let mut store = self.store.lock().unwrap();
+        let etag = next_etag();
+        store.insert(href.to_string(), (etag, data.to_string()));
+    }
+
+    /// Delete a to-do as another client would
+    pub fn delete(&self, href: &str) {
+        self.store.lock().unwrap().remove(href);
+    }
+}
+
+/// A to-do as written by another client
+pub fn todo_ics(uid: &str, properties: &[&str]) -> String {
+    let mut lines = vec!["BEGIN:VCALENDAR", "VERSION:2.0", "BEGIN:VTODO"];
+    let uid = format!("UID:{}", uid);
+    lines.push(&uid);
+    lines.extend(properties);
+    lines.extend(["END:VTODO", "END:VCALENDAR", ""]);
+    lines.join("\r\n")
+}
+
+/// A new ETag, never given before
+fn next_etag() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    format!("\"{}\"", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+struct Collection {
+    store: Store,
+}
+
+impl Respond for Collection {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let mut store = self.store.lock().unwrap();
+        let path = request.url.path().to_string();
+        let header = |name: &str| {
+            request
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let current_etag = store.get(&path).map(|(etag, _)| etag.clone());
+        let precondition_failed = match (header("If-Match"), header("If-None-Match")) {
+            (Some(etag), _) => current_etag.as_deref() != Some(etag),
+            (_, Some("*")) => current_etag.is_some(),
+            _ => false,
+        };
+
+        match request.method.as_str() {
+            "REPORT" if path == COLLECTION => {
+                let mut xml = String::from(
+                    r#"<d:multistatus xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">"#,
+                );
+                for (href, (etag, data)) in store.iter() {
+                    let data = data
+                        .replace('&', "&amp;")
+                        .replace('<', "&lt;")
+                        .replace('>', "&gt;");
+                    xml.push_str(&format!(
+                        "<d:response><d:href>{}</d:href><d:propstat><d:prop>\
+                         <d:getetag>{}</d:getetag><c:calendar-data>{}</c:calendar-data>\
+                         </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+                        href,
+                        etag.replace('"', "&quot;"),
+                        data
+                    ));
+                }
+                xml.push_str("</d:multistatus>");
+                ResponseTemplate::new(207).set_body_string(xml)
+            }
+            "PUT" | "DELETE" if precondition_failed => ResponseTemplate::new(412),
+            "PUT" => {
+                let etag = next_etag();
+                let data = String::from_utf8(request.body.clone()).unwrap();
+                let status = if current_etag.is_some() { 204 } else { 201 };
+                store.insert(path, (etag.clone(), data));
+                ResponseTemplate::new(status).insert_header("ETag", etag.as_str())
+            }
+            "DELETE" => match store.remove(&path) {
+                Some(_) => ResponseTemplate::new(204),
+                None => ResponseTemplate::new(404),
+            },
+            _ => ResponseTemplate::new(405),
+        }
+    }
+}
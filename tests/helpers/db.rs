@@ -1,6 +1,8 @@
 use anyhow::{Context, Result};
+use judo::db::connections::init_db;
 use sqlx::migrate::Migrator;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use std::path::Path;
 use std::str::FromStr;
 
 /// Create an in-memory SQLite database for testing
@@ -21,3 +23,14 @@ pub async fn setup_test_db() -> Result<SqlitePool> {
 
     Ok(pool)
 }
+
+/// Create a file-backed SQLite database in the given directory and return its
+/// connection string. Migrations are applied once so that further pools can be
+/// opened on the same file with `get_db_pool`.
+pub async fn setup_file_db(dir: &Path) -> Result<String> {
+    let connection_str = format!("sqlite:{}", dir.join("judo.db").display());
+    let pool = init_db(&connection_str).await?;
+    pool.close().await;
+
+    Ok(connection_str)
+}
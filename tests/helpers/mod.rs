@@ -1 +1,2 @@
+pub mod caldav;
 pub mod db;
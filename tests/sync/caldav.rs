@@ -0,0 +1,277 @@
+use crate::helpers::caldav::{COLLECTION, CalDavServer, todo_ics};
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use judo::sync::caldav::{SyncSummary, sync};
+use judo::sync::webdav::CalDavClient;
+use sqlx::SqlitePool;
+
+/// Lists with the names and checks of their items
+async fn contents(pool: &SqlitePool) -> Result<Vec<(String, Vec<(String, bool)>)>> {
+    let mut contents = Vec::new();
+    for list in TodoList::get_all(pool).await? {
+        let items = list.get_all_items(pool).await?;
+        let items = items
+            .into_iter()
+            .map(|item| (item.name, item.is_done))
+            .collect();
+        contents.push((list.name, items));
+    }
+    Ok(contents)
+}
+
+async fn add_item(pool: &SqlitePool, list_id: i64, name: &str) -> Result<TodoItem> {
+    TodoItem::create(
+        pool,
+        NewTodoItem {
+            list_id,
+            name: name.to_string(),
+            priority: Some(Priority::High),
+            due_date: None,
+        },
+    )
+    .await
+}
+
+/// A database with Laundry and Dishes in Chores, synced once with the server
+async fn setup_synced(server: &CalDavServer) -> Result<(SqlitePool, CalDavClient)> {
+    let pool = setup_test_db().await?;
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Chores".to_string(),
+        },
+    )
+    .await?;
+    add_item(&pool, list.id, "Laundry").await?;
+    add_item(&pool, list.id, "Dishes").await?;
+
+    let client = CalDavClient::new(&server.url(), Some("bob".to_string()), None)?;
+    let summary = sync(&pool, &client).await?;
+    assert_eq!(summary.created_remote, 2);
+    Ok((pool, client))
+}
+
+/// Path of the to-do whose data contains the text
+fn href_of(server: &CalDavServer, text: &str) -> String {
+    server
+        .todos()
+        .into_iter()
+        .find(|(_, data)| data.contains(text))
+        .map(|(href, _)| href)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_sync_uploads_new_items_once() -> Result<()> {
+    let server = CalDavServer::start().await;
+    let (pool, client) = setup_synced(&server).await?;
+
+    let todos = server.todos();
+    assert_eq!(todos.len(), 2);
+    assert!(todos.iter().all(|(href, _)| href.starts_with(COLLECTION)));
+    let (_, laundry) = &todos[0];
+    assert!(laundry.contains("SUMMARY:Laundry\r\n"));
+    assert!(laundry.contains("CATEGORIES:Chores\r\n"));
+    assert!(laundry.contains("PRIORITY:1\r\n"));
+
+    // Nothing changed, nothing to do
+    assert_eq!(sync(&pool, &client).await?, SyncSummary::default());
+    assert_eq!(server.todos(), todos);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_copies_changes_of_the_server() -> Result<()> {
+    let server = CalDavServer::start().await;
+    let (pool, client) = setup_synced(&server).await?;
+
+    // A phone adds a to-do, completes Laundry and deletes Dishes
+    server.put(
+        &format!("{}milk.ics", COLLECTION),
+        &todo_ics("milk", &["SUMMARY:Milk", "CATEGORIES:Groceries"]),
+    );
+    let laundry = href_of(&server, "Laundry");
+    let (_, data) = server
+        .todos()
+        .into_iter()
+        .find(|(href, _)| *href == laundry)
+        .unwrap();
+    server.put(
+        &laundry,
+        &data.replace("STATUS:NEEDS-ACTION", "STATUS:COMPLETED"),
+    );
+    server.delete(&href_of(&server, "Dishes"));
+
+    let summary = sync(&pool, &client).await?;
+    assert_eq!(
+        summary,
+        SyncSummary {
+            created_local: 1,
+            updated_local: 1,
+            deleted_local: 1,
+            ..SyncSummary::default()
+        }
+    );
+    assert_eq!(
+        contents(&pool).await?,
+        vec![
+            ("Chores".to_string(), vec![("Laundry".to_string(), true)]),
+            ("Groceries".to_string(), vec![("Milk".to_string(), false)]),
+        ]
+    );
+
+    assert_eq!(sync(&pool, &client).await?, SyncSummary::default());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_copies_changes_of_the_database() -> Result<()> {
+    let server = CalDavServer::start().await;
+    let (pool, client) = setup_synced(&server).await?;
+
+    let list = &TodoList::get_all(&pool).await?[0];
+    let mut items = list.get_all_items(&pool).await?;
+    items[0]
+        .update_name(&pool, "Laundry, whites first".to_string())
+        .await?;
+    items.remove(1).delete(&pool).await?;
+    add_item(&pool, list.id, "Groceries").await?;
+
+    let summary = sync(&pool, &client).await?;
+    assert_eq!(
+        summary,
+        SyncSummary {
+            created_remote: 1,
+            updated_remote: 1,
+            deleted_remote: 1,
+            ..SyncSummary::default()
+        }
+    );
+    let todos = server.todos();
+    assert_eq!(todos.len(), 2);
+    assert!(
+        todos
+            .iter()
+            .any(|(_, data)| data.contains("SUMMARY:Laundry\\, whites first\r\n"))
+    );
+    assert!(
+        todos
+            .iter()
+            .any(|(_, data)| data.contains("SUMMARY:Groceries\r\n"))
+    );
+
+    assert_eq!(sync(&pool, &client).await?, SyncSummary::default());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_keeps_the_last_change_of_a_conflict() -> Result<()> {
+    let server = CalDavServer::start().await;
+    let (pool, client) = setup_synced(&server).await?;
+
+    // Both items are renamed here, then on the server: Laundry before and
+    // Dishes after the renaming here
+    let list = &TodoList::get_all(&pool).await?[0];
+    for mut item in list.get_all_items(&pool).await? {
+        let name = format!("{} here", item.name);
+        item.update_name(&pool, name).await?;
+    }
+    for (name, modified) in [
+        ("Laundry", "20000101T000000Z"),
+        ("Dishes", "30000101T000000Z"),
+    ] {
+        server.put(
+            &href_of(&server, name),
+            &todo_ics(
+                name,
+                &[
+                    &format!("SUMMARY:{} there", name),
+                    "CATEGORIES:Chores",
+                    &format!("LAST-MODIFIED:{}", modified),
+                ],
+            ),
+        );
+    }
+
+    let summary = sync(&pool, &client).await?;
+    assert_eq!(
+        summary,
+        SyncSummary {
+            updated_local: 1,
+            updated_remote: 1,
+            conflicts: 2,
+            ..SyncSummary::default()
+        }
+    );
+    assert_eq!(
+        contents(&pool).await?,
+        vec![(
+            "Chores".to_string(),
+            vec![
+                ("Laundry here".to_string(), false),
+                ("Dishes there".to_string(), false),
+            ]
+        )]
+    );
+    assert!(
+        server
+            .todos()
+            .iter()
+            .any(|(_, data)| data.contains("SUMMARY:Laundry here\r\n"))
+    );
+
+    // A to-do deleted here but changed on the server comes back
+    let mut items = list.get_all_items(&pool).await?;
+    let dishes = href_of(&server, "Dishes there");
+    items.remove(1).delete(&pool).await?;
+    server.put(
+        &dishes,
+        &todo_ics("Dishes", &["SUMMARY:Dishes again", "CATEGORIES:Chores"]),
+    );
+
+    let summary = sync(&pool, &client).await?;
+    assert_eq!(summary.created_local, 1);
+    assert_eq!(summary.deleted_remote, 0);
+    assert_eq!(
+        contents(&pool).await?[0].1,
+        vec![
+            ("Laundry here".to_string(), false),
+            ("Dishes again".to_string(), false),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_keeps_items_of_unreadable_todos() -> Result<()> {
+    let server = CalDavServer::start().await;
+    let (pool, client) = setup_synced(&server).await?;
+
+    // Another client breaks the to-do of Laundry
+    server.put(
+        &href_of(&server, "Laundry"),
+        &todo_ics("Laundry", &["SUMMARY:", "CATEGORIES:Chores"]),
+    );
+
+    let summary = sync(&pool, &client).await?;
+    assert_eq!(
+        summary,
+        SyncSummary {
+            skipped: 1,
+            ..SyncSummary::default()
+        }
+    );
+    assert_eq!(
+        contents(&pool).await?,
+        vec![(
+            "Chores".to_string(),
+            vec![
+                ("Laundry".to_string(), false),
+                ("Dishes".to_string(), false),
+            ]
+        )]
+    );
+    assert_eq!(server.todos().len(), 2);
+    Ok(())
+}